@@ -0,0 +1,85 @@
+use num::{BigUint, ToPrimitive};
+use std::fmt;
+
+/// The result of solving one part of a puzzle.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Answer {
+    Number(i64),
+    Text(String),
+    /// Multi-line ASCII art, e.g. the letters formed by the folded paper of day 13.
+    Art(String),
+    /// The part has not been solved yet.
+    Unsolved,
+}
+
+macro_rules! from_integer {
+    ($($t:ty),*) => {
+        $(
+            impl From<$t> for Answer {
+                /// Values that do not fit an `i64` are kept as text instead of wrapping.
+                fn from(value: $t) -> Answer {
+                    match i64::try_from(value) {
+                        Ok(value) => Answer::Number(value),
+                        Err(_) => Answer::Text(value.to_string()),
+                    }
+                }
+            }
+        )*
+    };
+}
+
+from_integer!(i32, i64, isize, u8, u16, u32, u64, usize);
+
+impl From<BigUint> for Answer {
+    fn from(value: BigUint) -> Answer {
+        match value.to_i64() {
+            Some(value) => Answer::Number(value),
+            None => Answer::Text(value.to_string()),
+        }
+    }
+}
+
+impl From<String> for Answer {
+    fn from(value: String) -> Answer {
+        Answer::Text(value)
+    }
+}
+
+impl From<&str> for Answer {
+    fn from(value: &str) -> Answer {
+        Answer::Text(String::from(value))
+    }
+}
+
+impl fmt::Display for Answer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Answer::Number(v) => write!(f, "{}", v),
+            Answer::Text(s) => write!(f, "{}", s),
+            Answer::Art(s) => write!(f, "{}", s),
+            Answer::Unsolved => write!(f, "(unsolved)"),
+        }
+    }
+}
+
+/// Formats an answer for the terminal. Art is put below the label so the lines stay aligned.
+pub fn format_part(part: u8, answer: &Answer) -> String {
+    match answer {
+        Answer::Art(s) => format!("Part {}:\n{}", part, s),
+        _ => format!("Part {}: {}", part, answer),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn large_integers_do_not_wrap() {
+        assert_eq!(Answer::from(42usize), Answer::Number(42));
+        assert_eq!(Answer::from(-3isize), Answer::Number(-3));
+        let large = i64::MAX as u64 + 1;
+        assert_eq!(Answer::from(large), Answer::Text(large.to_string()));
+        assert_eq!(Answer::from(u64::MAX).to_string(), "18446744073709551615");
+    }
+}
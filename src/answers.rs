@@ -0,0 +1,179 @@
+use crate::answer::Answer;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+/// Recorded answers keyed by `(day, part)`.
+pub type Answers = HashMap<(u8, u8), Answer>;
+
+/// Reads an answers file. The format is a small subset of TOML, one table per day:
+///
+/// ```toml
+/// [day1]
+/// part1 = 1832
+/// part2 = "text"
+///
+/// [day13]
+/// part2 = """
+/// #  #
+/// ####"""
+/// ```
+///
+/// Integers become numbers, single line strings text and multi-line strings art.
+pub fn read(path: &Path) -> Result<Answers, String> {
+    let contents = fs::read_to_string(path)
+        .map_err(|err| format!("Could not read {}: {}", path.display(), err))?;
+    parse(&contents).map_err(|err| format!("{}: {}", path.display(), err))
+}
+
+pub fn parse(s: &str) -> Result<Answers, String> {
+    let mut answers = Answers::new();
+    let mut day: Option<u8> = None;
+    let mut lines = s.lines().enumerate();
+
+    while let Some((i, line)) = lines.next() {
+        let line_number = i + 1;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        if let Some(table) = line.strip_prefix('[') {
+            let name = table
+                .strip_suffix(']')
+                .ok_or(format!("line {}: unterminated table header", line_number))?;
+            let number = name
+                .strip_prefix("day")
+                .and_then(|n| n.parse::<u8>().ok())
+                .ok_or(format!("line {}: invalid table [{}]", line_number, name))?;
+            day = Some(number);
+            continue;
+        }
+
+        let (key, value) = line
+            .split_once('=')
+            .ok_or(format!("line {}: expected key = value", line_number))?;
+        let part = match key.trim() {
+            "part1" => 1,
+            "part2" => 2,
+            k => return Err(format!("line {}: unknown key {}", line_number, k)),
+        };
+        let day = day.ok_or(format!(
+            "line {}: key outside of a [dayN] table",
+            line_number
+        ))?;
+        let value = value.trim();
+
+        let answer = if let Some(rest) = value.strip_prefix("\"\"\"") {
+            // multi-line string, a newline directly after the opening quotes is dropped
+            let mut art = String::from(rest);
+            while !art.ends_with("\"\"\"") {
+                match lines.next() {
+                    Some((_, l)) => {
+                        if !art.is_empty() {
+                            art.push('\n');
+                        }
+                        art.push_str(l);
+                    }
+                    None => {
+                        return Err(format!("line {}: unterminated string", line_number));
+                    }
+                }
+            }
+            art.truncate(art.len() - 3);
+            Answer::Art(unescape(&art))
+        } else if let Some(rest) = value.strip_prefix('"') {
+            let text = rest
+                .strip_suffix('"')
+                .ok_or(format!("line {}: unterminated string", line_number))?;
+            Answer::Text(unescape(text))
+        } else {
+            let number = value
+                .replace('_', "")
+                .parse::<i64>()
+                .map_err(|_| format!("line {}: invalid value {}", line_number, value))?;
+            Answer::Number(number)
+        };
+        answers.insert((day, part), answer);
+    }
+
+    return Ok(answers);
+}
+
+fn unescape(s: &str) -> String {
+    let mut result = String::new();
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            result.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => result.push('\n'),
+            Some('t') => result.push('\t'),
+            Some(other) => result.push(other),
+            None => result.push('\\'),
+        }
+    }
+    return result;
+}
+
+/// Compares a computed answer with a recorded one. Trailing whitespace of art lines is ignored,
+/// editors tend to strip it from the answers file.
+pub fn matches(actual: &Answer, expected: &Answer) -> bool {
+    match (actual, expected) {
+        (Answer::Art(a), Answer::Art(b)) => {
+            let a: Vec<&str> = a.lines().map(|l| l.trim_end()).collect();
+            let b: Vec<&str> = b.lines().map(|l| l.trim_end()).collect();
+            a == b
+        }
+        _ => actual == expected,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_all_value_kinds() {
+        let answers = parse(
+            "\
+# recorded answers
+[day1]
+part1 = 1_832
+part2 = \"a \\\"quoted\\\" text\"
+
+[day13]
+part2 = \"\"\"
+# #
+###
+\"\"\"
+",
+        )
+        .unwrap();
+        assert_eq!(answers[&(1, 1)], Answer::Number(1832));
+        assert_eq!(
+            answers[&(1, 2)],
+            Answer::Text(String::from("a \"quoted\" text"))
+        );
+        assert!(matches(
+            &Answer::Art(String::from("# # \n###")),
+            &answers[&(13, 2)]
+        ));
+    }
+
+    #[test]
+    fn reports_line_of_invalid_value() {
+        let err = parse("[day1]\npart1 = abc").unwrap_err();
+        assert_eq!(err, "line 2: invalid value abc");
+    }
+
+    #[test]
+    fn different_kinds_do_not_match() {
+        assert!(!matches(
+            &Answer::Number(12),
+            &Answer::Text(String::from("12"))
+        ));
+    }
+}
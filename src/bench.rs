@@ -0,0 +1,234 @@
+//! Repeated measurements of the parse step and the parts of a day, see `aoc bench`.
+
+use crate::readfile::Lines;
+use crate::solver::{Day, Params};
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::collections::BTreeMap;
+use std::fs;
+use std::hint::black_box;
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+static ALLOCATIONS: AtomicU64 = AtomicU64::new(0);
+static ALLOCATED_BYTES: AtomicU64 = AtomicU64::new(0);
+
+/// The system allocator, counting allocations. Counts are only available if a binary installs
+/// it with `#[global_allocator]`, otherwise they stay zero.
+pub struct CountingAllocator;
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        ALLOCATED_BYTES.fetch_add(layout.size() as u64, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        ALLOCATED_BYTES.fetch_add(new_size as u64, Ordering::Relaxed);
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+fn allocation_counters() -> (u64, u64) {
+    (
+        ALLOCATIONS.load(Ordering::Relaxed),
+        ALLOCATED_BYTES.load(Ordering::Relaxed),
+    )
+}
+
+/// Timings of one step over all repetitions, allocations are per repetition.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Stats {
+    pub min: Duration,
+    pub median: Duration,
+    pub p95: Duration,
+    pub allocations: u64,
+    pub bytes: u64,
+}
+
+impl Stats {
+    fn from_samples(mut samples: Vec<Duration>, allocations: u64, bytes: u64) -> Stats {
+        samples.sort_unstable();
+        let n = samples.len();
+        let p95_index = ((n as f64 * 0.95).ceil() as usize).max(1) - 1;
+        Stats {
+            min: samples[0],
+            median: samples[n / 2],
+            p95: samples[p95_index],
+            allocations: allocations / n as u64,
+            bytes: bytes / n as u64,
+        }
+    }
+}
+
+pub const STEPS: [&str; 3] = ["parse", "part1", "part2"];
+
+pub struct BenchResult {
+    pub day: u8,
+    /// Stats of parse, part 1 and part 2, in the order of [`STEPS`].
+    pub steps: [Stats; 3],
+}
+
+/// Runs `f` `warmup` times unmeasured and then `repetitions` times measured.
+fn measure<F: FnMut()>(warmup: usize, repetitions: usize, mut f: F) -> Stats {
+    for _ in 0..warmup {
+        f();
+    }
+    let mut samples = Vec::with_capacity(repetitions);
+    let (allocations_before, bytes_before) = allocation_counters();
+    for _ in 0..repetitions {
+        let start = Instant::now();
+        f();
+        samples.push(start.elapsed());
+    }
+    let (allocations_after, bytes_after) = allocation_counters();
+    // the samples vector was allocated upfront, everything else comes from `f`
+    Stats::from_samples(
+        samples,
+        allocations_after - allocations_before,
+        bytes_after - bytes_before,
+    )
+}
+
+/// Measures parsing and both parts of `day`. The parts run with `params` applied, errors are
+/// returned as the message to show.
+pub fn bench_day(
+    day: &Day,
+    lines: &Lines,
+    params: &Params,
+    warmup: usize,
+    repetitions: usize,
+) -> Result<BenchResult, String> {
+    let repetitions = repetitions.max(1);
+    let mut solver = day.load(lines).map_err(|err| err.render())?;
+    solver.configure(params)?;
+    let parse = measure(warmup, repetitions, || {
+        black_box(day.load(lines).unwrap());
+    });
+    let part1 = measure(warmup, repetitions, || {
+        black_box(solver.part1());
+    });
+    let part2 = measure(warmup, repetitions, || {
+        black_box(solver.part2());
+    });
+    Ok(BenchResult {
+        day: day.number,
+        steps: [parse, part1, part2],
+    })
+}
+
+/// Medians of earlier runs keyed by day and step, as saved by [`save_baseline`].
+pub type Baseline = BTreeMap<(u8, String), Stats>;
+
+/// Reads a baseline file, a missing file is an empty baseline. Each line holds
+/// `day step min_ns median_ns p95_ns allocations bytes`.
+pub fn read_baseline(path: &Path) -> Result<Baseline, String> {
+    let mut baseline = Baseline::new();
+    let contents = match fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(_) if !path.exists() => return Ok(baseline),
+        Err(err) => return Err(format!("Could not read {}: {}", path.display(), err)),
+    };
+    for (i, line) in contents.lines().enumerate() {
+        if line.trim().is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        let invalid = || format!("{}:{}: invalid baseline entry", path.display(), i + 1);
+        if fields.len() != 7 {
+            return Err(invalid());
+        }
+        let number = |s: &str| s.parse::<u64>().map_err(|_| invalid());
+        let day = fields[0].parse::<u8>().map_err(|_| invalid())?;
+        let stats = Stats {
+            min: Duration::from_nanos(number(fields[2])?),
+            median: Duration::from_nanos(number(fields[3])?),
+            p95: Duration::from_nanos(number(fields[4])?),
+            allocations: number(fields[5])?,
+            bytes: number(fields[6])?,
+        };
+        baseline.insert((day, String::from(fields[1])), stats);
+    }
+    return Ok(baseline);
+}
+
+/// Adds the results to the baseline in `path`, replacing earlier entries of the same days.
+pub fn save_baseline(path: &Path, results: &[BenchResult]) -> Result<(), String> {
+    let mut baseline = read_baseline(path)?;
+    for result in results {
+        for (step, stats) in STEPS.iter().zip(result.steps) {
+            baseline.insert((result.day, String::from(*step)), stats);
+        }
+    }
+
+    let mut contents = String::from("# day step min_ns median_ns p95_ns allocations bytes\n");
+    for ((day, step), s) in &baseline {
+        contents.push_str(&format!(
+            "{} {} {} {} {} {} {}\n",
+            day,
+            step,
+            s.min.as_nanos(),
+            s.median.as_nanos(),
+            s.p95.as_nanos(),
+            s.allocations,
+            s.bytes
+        ));
+    }
+    fs::write(path, contents).map_err(|err| format!("Could not write {}: {}", path.display(), err))
+}
+
+/// The relative change of the median compared to the baseline, e.g. `-12.5%`.
+pub fn compare_median(stats: &Stats, baseline: &Stats) -> String {
+    let before = baseline.median.as_secs_f64();
+    if before == 0.0 {
+        return String::from("-");
+    }
+    let change = (stats.median.as_secs_f64() - before) / before * 100.0;
+    format!("{:+.1}%", change)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ms(values: &[u64]) -> Vec<Duration> {
+        values.iter().map(|v| Duration::from_millis(*v)).collect()
+    }
+
+    #[test]
+    fn percentiles() {
+        let samples: Vec<u64> = (1..=20).rev().collect();
+        let stats = Stats::from_samples(ms(&samples), 40, 400);
+        assert_eq!(stats.min, Duration::from_millis(1));
+        assert_eq!(stats.median, Duration::from_millis(11));
+        assert_eq!(stats.p95, Duration::from_millis(19));
+        assert_eq!((stats.allocations, stats.bytes), (2, 20));
+
+        let single = Stats::from_samples(ms(&[7]), 0, 0);
+        assert_eq!(single.median, Duration::from_millis(7));
+        assert_eq!(single.p95, Duration::from_millis(7));
+    }
+
+    #[test]
+    fn baseline_round_trip() {
+        let path = std::env::temp_dir().join(format!("aoc-baseline-{}", std::process::id()));
+        let stats = Stats::from_samples(ms(&[3, 1, 2]), 6, 60);
+        let result = BenchResult {
+            day: 9,
+            steps: [stats; 3],
+        };
+        save_baseline(&path, &[result]).unwrap();
+        let baseline = read_baseline(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(baseline.len(), 3);
+        assert_eq!(baseline[&(9, String::from("part2"))], stats);
+        assert_eq!(compare_median(&stats, &stats), "+0.0%");
+    }
+}
@@ -0,0 +1,244 @@
+use aoc::readfile::Source;
+use aoc::solver::Params;
+use std::path::PathBuf;
+
+pub const USAGE: &str = "Usage: aoc <command> [options]
+
+Commands:
+  <day>             run a single day, e.g. 15
+  <from>-<to>       run a range of days, e.g. 1-10, skipping days without an input
+  all               run every implemented day that has an input
+  list              list the implemented days and their parameters
+  verify [<days>]   compare the answers of the selected days (default all)
+                    with the recorded answers
+  bench <days>      measure parsing and both parts repeatedly
+  generate <day>    print a random input for the day, see --size and --seed
+  new <day>         add src/dayN.rs from the template, register it and create
+                    an empty input
+
+Options:
+  --input <file>    read the puzzle input from <file> (single day only), - for stdin
+  -                 same as --input -
+  --inputs-dir <dir>
+                    directory containing the dayN.txt inputs
+  --answers <file>  recorded answers for verify, defaults to answers.toml
+                    in the inputs directory
+  --part <1|2>      only run the given part
+  --param <name>=<value>
+                    change a puzzle constant declared by the day, e.g.
+                    --param days=1000 for day 6, see list; may be repeated
+  --visualize       show the simulation of the day as animation in the terminal
+                    (days 9, 11, 13, 15, 20, 23 and 25)
+  --fps <n>         frames per second of --visualize (default 10)
+  --export-image <file>
+                    save a picture of the result as Netpbm image, the
+                    extension depends on the day: .pbm (days 13 and 20),
+                    .pgm (days 5 and 11) or .ppm (days 9 and 15)
+  --size <n>        generate: how large the input is, the meaning depends on
+                    the day (default 10)
+  --seed <n>        generate: seed of the random numbers (default 1)
+  --format <format> text (default) or json, one record per part and line
+  --jobs <n>        number of days and parts solved in parallel, defaults to
+                    the number of CPUs
+  --reps <n>        bench: measured repetitions of each step (default 20)
+  --warmup <n>      bench: unmeasured repetitions before measuring (default 3)
+  --baseline <file> bench: compare the medians with a saved baseline
+  --save-baseline <file>
+                    bench: save the results as baseline, replacing the
+                    entries of the benchmarked days";
+
+pub enum Command {
+    List,
+    Run(String),
+    Verify(String),
+    Bench(String),
+    New(String),
+    Generate(String),
+}
+
+pub enum Format {
+    Text,
+    Json,
+}
+
+pub struct Options {
+    pub command: Command,
+    pub input: Option<Source>,
+    pub inputs_dir: Option<PathBuf>,
+    pub answers: Option<PathBuf>,
+    pub part: Option<u8>,
+    pub params: Params,
+    pub visualize: bool,
+    pub frames_per_second: u32,
+    pub export_image: Option<PathBuf>,
+    pub size: usize,
+    pub seed: u64,
+    pub format: Format,
+    pub jobs: Option<usize>,
+    pub repetitions: usize,
+    pub warmup: usize,
+    pub baseline: Option<PathBuf>,
+    pub save_baseline: Option<PathBuf>,
+}
+
+fn source_from_arg(arg: &str) -> Source {
+    if arg == "-" {
+        Source::Stdin
+    } else {
+        Source::File(PathBuf::from(arg))
+    }
+}
+
+pub fn parse_args(args: &[String]) -> Result<Options, String> {
+    let mut command: Option<Command> = None;
+    let mut input: Option<Source> = None;
+    let mut inputs_dir: Option<PathBuf> = None;
+    let mut answers: Option<PathBuf> = None;
+    let mut part: Option<u8> = None;
+    let mut params = Params::default();
+    let mut visualize = false;
+    let mut frames_per_second = 10;
+    let mut export_image: Option<PathBuf> = None;
+    let mut size = 10;
+    let mut seed = 1;
+    let mut format = Format::Text;
+    let mut jobs: Option<usize> = None;
+    let mut repetitions = 20;
+    let mut warmup = 3;
+    let mut baseline: Option<PathBuf> = None;
+    let mut save_baseline: Option<PathBuf> = None;
+
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--input" => {
+                let value = iter.next().ok_or("--input requires a file")?;
+                input = Some(source_from_arg(value));
+            }
+            "-" => input = Some(Source::Stdin),
+            "--inputs-dir" => {
+                let value = iter.next().ok_or("--inputs-dir requires a directory")?;
+                inputs_dir = Some(PathBuf::from(value));
+            }
+            "--answers" => {
+                let value = iter.next().ok_or("--answers requires a file")?;
+                answers = Some(PathBuf::from(value));
+            }
+            "--part" => {
+                part = match iter.next().map(|s| s.as_str()) {
+                    Some("1") => Some(1),
+                    Some("2") => Some(2),
+                    Some(other) => return Err(format!("Invalid part: {}", other)),
+                    None => return Err(String::from("--part requires 1 or 2")),
+                };
+            }
+            "--param" => {
+                let value = iter.next().ok_or("--param requires name=value")?;
+                params.insert(value)?;
+            }
+            "--visualize" => visualize = true,
+            "--fps" => {
+                let value = iter.next().ok_or("--fps requires a number")?;
+                match value.parse::<u32>() {
+                    Ok(n) if n > 0 => frames_per_second = n,
+                    _ => return Err(format!("Invalid number of frames per second: {}", value)),
+                }
+            }
+            "--export-image" => {
+                let value = iter.next().ok_or("--export-image requires a file")?;
+                export_image = Some(PathBuf::from(value));
+            }
+            "--size" => {
+                let value = iter.next().ok_or("--size requires a number")?;
+                size = value
+                    .parse()
+                    .map_err(|_| format!("Invalid size: {}", value))?;
+            }
+            "--seed" => {
+                let value = iter.next().ok_or("--seed requires a number")?;
+                seed = value
+                    .parse()
+                    .map_err(|_| format!("Invalid seed: {}", value))?;
+            }
+            "--format" => {
+                format = match iter.next().map(|s| s.as_str()) {
+                    Some("text") => Format::Text,
+                    Some("json") => Format::Json,
+                    Some(other) => return Err(format!("Unknown format: {}", other)),
+                    None => return Err(String::from("--format requires text or json")),
+                };
+            }
+            "--jobs" => {
+                let value = iter.next().ok_or("--jobs requires a number")?;
+                match value.parse::<usize>() {
+                    Ok(n) if n > 0 => jobs = Some(n),
+                    _ => return Err(format!("Invalid number of jobs: {}", value)),
+                }
+            }
+            "--reps" => {
+                let value = iter.next().ok_or("--reps requires a number")?;
+                match value.parse::<usize>() {
+                    Ok(n) if n > 0 => repetitions = n,
+                    _ => return Err(format!("Invalid number of repetitions: {}", value)),
+                }
+            }
+            "--warmup" => {
+                let value = iter.next().ok_or("--warmup requires a number")?;
+                warmup = value
+                    .parse()
+                    .map_err(|_| format!("Invalid number of warmup runs: {}", value))?;
+            }
+            "--baseline" => {
+                let value = iter.next().ok_or("--baseline requires a file")?;
+                baseline = Some(PathBuf::from(value));
+            }
+            "--save-baseline" => {
+                let value = iter.next().ok_or("--save-baseline requires a file")?;
+                save_baseline = Some(PathBuf::from(value));
+            }
+            _ if arg.starts_with("--") => return Err(format!("Unknown option: {}", arg)),
+            _ if matches!(&command, Some(Command::Verify(s)) if s == "all") => {
+                command = Some(Command::Verify(arg.clone()))
+            }
+            _ if command.is_some() => return Err(format!("Unexpected argument: {}", arg)),
+            "list" => command = Some(Command::List),
+            "verify" => command = Some(Command::Verify(String::from("all"))),
+            "new" => match iter.next() {
+                Some(day) => command = Some(Command::New(day.clone())),
+                None => return Err(String::from("new requires a day")),
+            },
+            "generate" => match iter.next() {
+                Some(day) => command = Some(Command::Generate(day.clone())),
+                None => return Err(String::from("generate requires a day")),
+            },
+            "bench" => match iter.next() {
+                Some(days) => command = Some(Command::Bench(days.clone())),
+                None => return Err(String::from("bench requires a day")),
+            },
+            _ => command = Some(Command::Run(arg.clone())),
+        }
+    }
+
+    match command {
+        Some(command) => Ok(Options {
+            command,
+            input,
+            inputs_dir,
+            answers,
+            part,
+            params,
+            visualize,
+            frames_per_second,
+            export_image,
+            size,
+            seed,
+            format,
+            jobs,
+            repetitions,
+            warmup,
+            baseline,
+            save_baseline,
+        }),
+        None => Err(String::from("No day specified")),
+    }
+}
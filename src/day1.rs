@@ -1,34 +1,104 @@
+use crate::answer::Answer;
+use crate::error::ParseError;
+use crate::generate::Rng;
 use crate::readfile;
+use crate::solver::Solver;
 
-fn part1(lines: &readfile::Lines) {
-    let mut increases = 0;
-    let mut current_value = 0;
-    for (i, line) in lines.lines().enumerate() {
-        let value: i32 = line.parse().unwrap();
-        if i > 0 && value > current_value {
-            increases += 1;
+pub struct Day1 {
+    values: Vec<i32>,
+}
+
+impl Solver for Day1 {
+    fn parse(lines: &readfile::Lines) -> Result<Day1, ParseError> {
+        let values = lines
+            .numbered()
+            .map(|l| l.parse_field(l.text()))
+            .collect::<Result<_, _>>()?;
+        Ok(Day1 { values })
+    }
+
+    fn part1(&self) -> Answer {
+        let mut increases = 0;
+        let mut current_value = 0;
+        for (i, value) in self.values.iter().enumerate() {
+            if i > 0 && *value > current_value {
+                increases += 1;
+            }
+            current_value = *value;
         }
-        current_value = value;
+        increases.into()
     }
-    println!("Part 1: {}", increases);
-}
 
-fn part2(lines: &readfile::Lines) {
-    let num_values: Vec<i32> = lines.lines().map(|l| l.parse().unwrap()).collect();
-    let mut increases = 0;
-    let mut current_value = 0;
-    for i in 0..num_values.len() - 2 {
-        let value = num_values[i] + num_values[i + 1] + num_values[i + 2];
-        if i > 0 && value > current_value {
-            increases += 1;
+    fn part2(&self) -> Answer {
+        let mut increases = 0;
+        let mut current_value = 0;
+        for (i, window) in self.values.windows(3).enumerate() {
+            let value: i32 = window.iter().sum();
+            if i > 0 && value > current_value {
+                increases += 1;
+            }
+            current_value = value;
         }
-        current_value = value;
+        increases.into()
+    }
+
+    /// `size` depths (at least 3) that mostly increase, like the sea floor of the puzzle.
+    fn generate(rng: &mut Rng, size: usize) -> Option<String> {
+        let mut depth = rng.range(100..=200);
+        let depths: Vec<String> = (0..size.max(3))
+            .map(|_| {
+                depth = (depth + rng.range(-20..=30)).max(0);
+                depth.to_string()
+            })
+            .collect();
+        Some(depths.join("\n"))
     }
-    println!("Part 2: {}", increases);
 }
 
-pub fn run() {
-    let lines = readfile::Lines::new("day1.txt");
-    part1(&lines);
-    part2(&lines);
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generate;
+
+    const EXAMPLE: &str = "\
+199
+200
+208
+210
+200
+207
+240
+269
+260
+263";
+
+    #[test]
+    fn example() {
+        let day = Day1::parse(&readfile::Lines::from(EXAMPLE)).unwrap();
+        assert_eq!(day.part1(), Answer::Number(7));
+        assert_eq!(day.part2(), Answer::Number(5));
+    }
+
+    #[test]
+    fn fewer_depths_than_a_window() {
+        let day = Day1::parse(&readfile::Lines::from("5")).unwrap();
+        assert_eq!(day.part1(), Answer::Number(0));
+        assert_eq!(day.part2(), Answer::Number(0));
+    }
+
+    #[test]
+    fn matches_brute_force() {
+        generate::check_answers::<Day1>(
+            0..20,
+            |seed| 3 + seed as usize,
+            |day, _| {
+                let increases = |v: &[i32]| v.windows(2).filter(|w| w[1] > w[0]).count();
+                let sums: Vec<i32> = day.values.windows(3).map(|w| w.iter().sum()).collect();
+                [
+                    Some(increases(&day.values).into()),
+                    Some(increases(&sums).into()),
+                ]
+            },
+        );
+    }
 }
@@ -1,80 +1,227 @@
+use crate::answer::Answer;
+use crate::error::ParseError;
+use crate::generate::Rng;
 use crate::readfile;
+use crate::solver::Solver;
 
+/// The brackets still open at the end of `line`, or the first closing bracket that does not
+/// match. Parsing only accepts brackets, so every other character is an opening one.
 fn parse_line(line: &str) -> Result<Vec<char>, char> {
     let mut stack: Vec<char> = Vec::new();
     for c in line.chars() {
-        match c {
-            '(' | '{' | '[' | '<' => stack.push(c),
-            ')' => {
-                if stack.pop().unwrap() != '(' {
-                    return Err(c);
-                }
-            }
-            '}' => {
-                if stack.pop().unwrap() != '{' {
-                    return Err(c);
-                }
-            }
-            ']' => {
-                if stack.pop().unwrap() != '[' {
-                    return Err(c);
-                }
+        let opening = match c {
+            ')' => '(',
+            '}' => '{',
+            ']' => '[',
+            '>' => '<',
+            _ => {
+                stack.push(c);
+                continue;
             }
-            '>' => {
-                if stack.pop().unwrap() != '<' {
-                    return Err(c);
-                }
-            }
-            _ => panic!("Invalid character: {}", c),
+        };
+        if stack.pop() != Some(opening) {
+            return Err(c);
         }
     }
     return Ok(stack);
 }
 
-fn part1(lines: &readfile::Lines) {
-    let mut sum: usize = 0;
-    for l in lines.lines() {
-        match parse_line(l) {
-            Ok(_) => {}
-            Err(c) => {
-                sum += match c {
-                    ')' => 3,
-                    ']' => 57,
-                    '}' => 1197,
-                    '>' => 25137,
-                    _ => 0,
+pub struct Day10 {
+    lines: Vec<String>,
+}
+
+impl Solver for Day10 {
+    fn parse(lines: &readfile::Lines) -> Result<Day10, ParseError> {
+        let mut chunks = Vec::new();
+        for l in lines.numbered() {
+            let mut chars = l.text().chars().enumerate();
+            if let Some((i, c)) = chars.find(|(_, c)| !"(){}[]<>".contains(*c)) {
+                return Err(l.error(i + 1, &format!("invalid character '{}'", c)));
+            }
+            chunks.push(String::from(l.text()));
+        }
+        Ok(Day10 { lines: chunks })
+    }
+
+    fn part1(&self) -> Answer {
+        let mut sum: usize = 0;
+        for l in &self.lines {
+            match parse_line(l) {
+                Ok(_) => {}
+                Err(c) => {
+                    sum += match c {
+                        ')' => 3,
+                        ']' => 57,
+                        '}' => 1197,
+                        '>' => 25137,
+                        _ => 0,
+                    }
                 }
             }
         }
+        sum.into()
     }
-    println!("Part 1: {}", sum);
-}
 
-fn part2(lines: &readfile::Lines) {
-    let mut scores: Vec<usize> = Vec::new();
-    for l in lines.lines() {
-        if let Ok(mut remaining) = parse_line(l) {
-            let mut score: usize = 0;
-            while !remaining.is_empty() {
-                score *= 5;
-                let c = remaining.pop().unwrap();
-                score += match c {
-                    '(' => 1,
-                    '{' => 3,
-                    '[' => 2,
-                    '<' => 4,
-                    _ => 0,
+    /// Unsolved if no line is incomplete, there is no middle score then.
+    fn part2(&self) -> Answer {
+        let mut scores: Vec<usize> = Vec::new();
+        for l in &self.lines {
+            if let Ok(mut remaining) = parse_line(l) {
+                let mut score: usize = 0;
+                while !remaining.is_empty() {
+                    score *= 5;
+                    let c = remaining.pop().unwrap();
+                    score += match c {
+                        '(' => 1,
+                        '{' => 3,
+                        '[' => 2,
+                        '<' => 4,
+                        _ => 0,
+                    }
                 }
+                scores.push(score);
             }
-            scores.push(score);
         }
+        if scores.is_empty() {
+            return Answer::Unsolved;
+        }
+        scores.sort_unstable();
+        scores[scores.len() / 2].into()
+    }
+
+    /// `size` corrupted or incomplete lines, an odd number of them incomplete so that there
+    /// is a middle score.
+    fn generate(rng: &mut Rng, size: usize) -> Option<String> {
+        let size = size.max(1);
+        let mut is_incomplete: Vec<bool> = (0..size).map(|i| i < (size / 2) | 1).collect();
+        rng.shuffle(&mut is_incomplete);
+        let brackets = [('(', ')'), ('[', ']'), ('{', '}'), ('<', '>')];
+        let closing = |c: char| brackets.iter().find(|(o, _)| *o == c).unwrap().1;
+        let lines: Vec<String> = is_incomplete
+            .into_iter()
+            .map(|incomplete| {
+                let mut line = String::new();
+                let mut open: Vec<char> = vec![];
+                for _ in 0..rng.range(5..=25) {
+                    match open.last() {
+                        Some(c) if rng.chance(1, 2) => {
+                            line.push(closing(*c));
+                            open.pop();
+                        }
+                        _ => {
+                            let (opening, _) = *rng.choose(&brackets);
+                            line.push(opening);
+                            open.push(opening);
+                        }
+                    }
+                }
+                if incomplete && open.is_empty() {
+                    line.push('(');
+                } else if !incomplete {
+                    let expected = open.last().map(|c| closing(*c));
+                    let wrong: Vec<char> =
+                        ")]}>".chars().filter(|c| Some(*c) != expected).collect();
+                    line.push(*rng.choose(&wrong));
+                }
+                line
+            })
+            .collect();
+        Some(lines.join("\n"))
     }
-    scores.sort_unstable();
-    println!("Part 2: {}", scores[scores.len() / 2]);
 }
 
-pub fn run() {
-    let lines = readfile::Lines::new("day10.txt");
-    part1(&lines);
-    part2(&lines);
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generate;
+
+    const EXAMPLE: &str = "\
+[({(<(())[]>[[{[]{<()<>>
+[(()[<>])]({[<{<<[]>>(
+{([(<{}[<>[]}>{[]{[(<()>
+(((({<>}<{<{<>}{[]{[]{}
+[[<[([]))<([[{}[[()]]]
+[{[{({}]{}}([{[{{{}}([]
+{<[[]]>}<{[{[{[]{()[[[]
+[<(<(<(<{}))><([]([]()
+<{([([[(<>()){}]>(<<{{
+<{([{{}}[<[[[<>{}]]]>[]]";
+
+    #[test]
+    fn example() {
+        let day = Day10::parse(&readfile::Lines::from(EXAMPLE)).unwrap();
+        assert_eq!(day.part1(), Answer::Number(26397));
+        assert_eq!(day.part2(), Answer::Number(288957));
+    }
+
+    /// Removes matching pairs until none are left. Then a closing bracket is the first illegal
+    /// one, otherwise the open brackets remain.
+    fn brute_force(line: &str) -> Result<Vec<char>, char> {
+        let mut line = String::from(line);
+        loop {
+            let reduced = ["()", "[]", "{}", "<>"]
+                .iter()
+                .fold(line.clone(), |l, pair| l.replace(pair, ""));
+            if reduced == line {
+                break;
+            }
+            line = reduced;
+        }
+        match line.chars().find(|c| ")]}>".contains(*c)) {
+            Some(c) => Err(c),
+            None => Ok(line.chars().collect()),
+        }
+    }
+
+    #[test]
+    fn matches_brute_force() {
+        generate::check_answers::<Day10>(
+            0..20,
+            |seed| 1 + seed as usize,
+            |_, input| {
+                let points = [(')', 3), (']', 57), ('}', 1197), ('>', 25137)];
+                let mut corrupted: usize = 0;
+                let mut scores: Vec<usize> = vec![];
+                for line in input.lines() {
+                    assert_eq!(parse_line(line), brute_force(line), "{}", line);
+                    match brute_force(line) {
+                        Err(c) => corrupted += points.iter().find(|(d, _)| *d == c).unwrap().1,
+                        Ok(open) => scores.push(
+                            open.iter()
+                                .rev()
+                                .fold(0, |score, c| 5 * score + " ([{<".find(*c).unwrap()),
+                        ),
+                    }
+                }
+                scores.sort_unstable();
+                [
+                    Some(corrupted.into()),
+                    Some(scores[scores.len() / 2].into()),
+                ]
+            },
+        );
+    }
+
+    #[test]
+    fn corrupted_line() {
+        assert_eq!(parse_line("{([(<{}[<>[]}>{[]{[(<()>"), Err('}'));
+        assert_eq!(parse_line("[[<[([]))<([[{}[[()]]]"), Err(')'));
+        assert_eq!(parse_line("()>"), Err('>'));
+    }
+
+    #[test]
+    fn no_incomplete_line() {
+        let day = Day10::parse(&readfile::Lines::from("(]\n{()()()>")).unwrap();
+        assert_eq!(day.part1(), Answer::Number(57 + 25137));
+        assert_eq!(day.part2(), Answer::Unsolved);
+    }
+
+    #[test]
+    fn incomplete_line() {
+        let remaining: String = parse_line("[({(<(())[]>[[{[]{<()<>>")
+            .unwrap()
+            .into_iter()
+            .collect();
+        assert_eq!(remaining, "[({([[{{");
+    }
 }
@@ -1,5 +1,13 @@
+use crate::answer::Answer;
+use crate::error::ParseError;
+use crate::generate::Rng;
+use crate::grid::{Grid, Point};
+use crate::netpbm::Image;
 use crate::readfile;
+use crate::solver::{Param, Params, Solver};
+use crate::visualize::Animation;
 use colored::*;
+use std::collections::HashSet;
 
 #[derive(Copy, Clone)]
 struct Octopus {
@@ -7,144 +15,259 @@ struct Octopus {
     has_flashed: bool,
 }
 
-struct Grid {
-    octos: Vec<Octopus>,
-    len_x: usize,
-    len_y: usize,
+type Cavern = Grid<Octopus>;
+
+fn parse_cavern(lines: &readfile::Lines) -> Result<Cavern, ParseError> {
+    let energies = Grid::parse_digits(lines)?;
+    return Ok(energies.map(|e| Octopus {
+        energy: *e as u8,
+        has_flashed: false,
+    }));
 }
 
-impl Grid {
-    pub fn new(lines: &readfile::Lines) -> Grid {
-        let all: Vec<&str> = lines.lines().collect();
-        let octos = all
-            .iter()
-            .flat_map(|l| {
-                l.chars().map(|c| Octopus {
-                    energy: c.to_digit(10).unwrap() as u8,
-                    has_flashed: false,
-                })
-            })
-            .collect();
-        return Grid {
-            octos,
-            len_x: all[0].len(),
-            len_y: all.len(),
-        };
+/// Runs one step and returns the number of octopuses that flashed.
+fn tick(cavern: &mut Cavern) -> usize {
+    for octo in cavern.values_mut() {
+        octo.has_flashed = false;
+        octo.energy += 1;
     }
 
-    pub fn get(&self, x: isize, y: isize) -> Option<&Octopus> {
-        if x < 0 || y < 0 {
-            return None;
-        };
+    while flash(cavern) {}
 
-        let pos = (y as usize) * self.len_x + (x as usize);
-        if pos >= self.octos.len() {
-            return None;
+    let mut n_flashes = 0;
+    for octo in cavern.values_mut() {
+        if octo.has_flashed {
+            octo.energy = 0;
+            n_flashes += 1;
         }
-        return Some(&self.octos[pos]);
     }
+    return n_flashes;
+}
 
-    pub fn get_mut(&mut self, x: isize, y: isize) -> Option<&mut Octopus> {
-        if x < 0 || y < 0 || x >= (self.len_x as isize) || y >= (self.len_y as isize) {
-            return None;
+/// The energy levels, the octopuses that just flashed are bright.
+fn render(cavern: &Cavern) -> String {
+    cavern.render(|_, octo| {
+        let energy = if octo.energy > 9 {
+            String::from("+")
+        } else {
+            octo.energy.to_string()
         };
+        if octo.has_flashed {
+            energy
+        } else {
+            energy.bright_black().to_string()
+        }
+    })
+}
 
-        let pos = (y as usize) * self.len_x + (x as usize);
-        if pos >= self.octos.len() {
-            return None;
+fn flash(cavern: &mut Cavern) -> bool {
+    let mut flashed_some = false;
+    for p in cavern.points() {
+        let octo = cavern[p];
+        if octo.energy > 9 && !octo.has_flashed {
+            cavern[p].has_flashed = true;
+            let neighbours: Vec<Point> = cavern.neighbours8(p).collect();
+            for n in neighbours {
+                cavern[n].energy += 1;
+            }
+            flashed_some = true;
         }
-        return Some(&mut self.octos[pos]);
     }
+    return flashed_some;
+}
 
-    pub fn get_value(&self, x: isize, y: isize) -> Option<Octopus> {
-        return self.get(x, y).copied();
+/// The first step in which all octopuses flash, `None` if they never do. The steps are
+/// deterministic, so once the energy levels repeat they cycle without ever synchronizing.
+fn steps_until_synchronized(cavern: &Cavern) -> Option<usize> {
+    let mut cavern = cavern.clone();
+    let mut seen: HashSet<Vec<u8>> = HashSet::new();
+    let mut steps = 0;
+    while seen.insert(cavern.values().map(|o| o.energy).collect()) {
+        steps += 1;
+        if tick(&mut cavern) == cavern.len() {
+            return Some(steps);
+        }
     }
+    return None;
+}
 
-    pub fn tick(&mut self) -> usize {
-        for i in 0..self.octos.len() {
-            self.octos[i].has_flashed = false;
-            self.octos[i].energy += 1;
+pub struct Day11 {
+    cavern: Cavern,
+    steps: Option<usize>,
+}
+
+impl Solver for Day11 {
+    fn parse(lines: &readfile::Lines) -> Result<Day11, ParseError> {
+        Ok(Day11 {
+            cavern: parse_cavern(lines)?,
+            steps: None,
+        })
+    }
+
+    fn part1(&self) -> Answer {
+        let mut cavern = self.cavern.clone();
+        let mut num_flashes = 0;
+        for _ in 0..self.steps.unwrap_or(100) {
+            num_flashes += tick(&mut cavern);
+        }
+        num_flashes.into()
+    }
+
+    /// Unsolved if the energy levels repeat before all octopuses flash at once.
+    fn part2(&self) -> Answer {
+        match steps_until_synchronized(&self.cavern) {
+            Some(steps) => steps.into(),
+            None => Answer::Unsolved,
         }
+    }
 
-        while self.flash() {}
+    fn params() -> &'static [Param] {
+        &[Param {
+            name: "steps",
+            help: "number of steps in part 1 (100)",
+        }]
+    }
+
+    fn configure(&mut self, params: &Params) -> Result<(), String> {
+        self.steps = params.get("steps")?;
+        Ok(())
+    }
 
-        let mut n_flashes = 0;
-        for i in 0..self.octos.len() {
-            if self.octos[i].has_flashed {
-                self.octos[i].energy = 0;
-                n_flashes += 1;
+    /// A `size` x `size` cavern (2 to 10 wide) with random energy levels. Like in the puzzle
+    /// the octopuses synchronize, random caverns are retried until they do. Larger caverns
+    /// rarely synchronize.
+    fn generate(rng: &mut Rng, size: usize) -> Option<String> {
+        let size = size.clamp(2, 10);
+        loop {
+            let energies: Grid<u32> = Grid::from_fn(size, size, |_| rng.below(10) as u32);
+            let cavern = energies.map(|e| Octopus {
+                energy: *e as u8,
+                has_flashed: false,
+            });
+            if steps_until_synchronized(&cavern).is_some() {
+                return Some(energies.render(|_, energy| energy.to_string()));
             }
         }
-        return n_flashes;
-    }
-
-    #[allow(dead_code)]
-    pub fn print(&self) {
-        for y in 0..(self.len_y as isize) {
-            for x in 0..(self.len_x as isize) {
-                let octo = self.get(x, y).unwrap();
-                let energy = if octo.energy > 9 {
-                    String::from("+")
-                } else {
-                    octo.energy.to_string()
-                };
-                if octo.has_flashed {
-                    print!("{}", energy);
-                } else {
-                    print!("{}", energy.bright_black());
-                }
-            }
-            println!();
+    }
+
+    /// The energy levels after the steps of part 1, the octopuses that just flashed are black.
+    fn image(&self) -> Option<Image> {
+        let mut cavern = self.cavern.clone();
+        for _ in 0..self.steps.unwrap_or(100) {
+            tick(&mut cavern);
         }
+        Some(Image::Graymap(cavern.map(|o| o.energy as u16), 9))
     }
 
-    fn flash(&mut self) -> bool {
-        let mut flashed_some = false;
-        for y in 0..(self.len_y as isize) {
-            for x in 0..(self.len_x as isize) {
-                if let Some(octo) = self.get_value(x, y) {
-                    if octo.energy > 9 && !octo.has_flashed {
-                        self.flash_octo(x, y);
-                        self.get_mut(x, y).unwrap().has_flashed = true;
-                        flashed_some = true;
-                    }
-                }
-            }
+    /// The steps until all octopuses flash at once, or the given number of steps. 100 steps if
+    /// they never synchronize.
+    fn visualize(&self, animation: &mut Animation) -> bool {
+        let steps = self
+            .steps
+            .or_else(|| steps_until_synchronized(&self.cavern))
+            .unwrap_or(100);
+        let mut cavern = self.cavern.clone();
+        animation.frame("step 0", &render(&cavern));
+        for step in 1..=steps {
+            let flashes = tick(&mut cavern);
+            let caption = format!("step {}: {} flashes", step, flashes);
+            animation.frame(&caption, &render(&cavern));
         }
-        return flashed_some;
+        true
     }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generate;
 
-    fn flash_octo(&mut self, x: isize, y: isize) {
-        for ny in [y - 1, y, y + 1] {
-            for nx in [x - 1, x, x + 1] {
-                if let Some(o) = self.get_mut(nx, ny) {
-                    o.energy += 1
+    const EXAMPLE: &str = "\
+5483143223
+2745854711
+5264556173
+6141336146
+6357385478
+4167524645
+2176841721
+6882881134
+4846848554
+5283751526";
+
+    #[test]
+    fn example() {
+        let day = Day11::parse(&readfile::Lines::from(EXAMPLE)).unwrap();
+        assert_eq!(day.part1(), Answer::Number(1656));
+        assert_eq!(day.part2(), Answer::Number(195));
+    }
+
+    /// A step by following each flash with a queue of octopuses to increase.
+    fn brute_force_step(energies: &mut [Vec<u32>]) -> usize {
+        let size = energies.len() as isize;
+        let mut queue: Vec<(isize, isize)> = vec![];
+        for y in 0..size {
+            for x in 0..size {
+                queue.push((x, y));
+            }
+        }
+        let mut flashed = vec![vec![false; size as usize]; size as usize];
+        while let Some((x, y)) = queue.pop() {
+            let (ux, uy) = (x as usize, y as usize);
+            energies[uy][ux] += 1;
+            if energies[uy][ux] > 9 && !flashed[uy][ux] {
+                flashed[uy][ux] = true;
+                for (dx, dy) in [
+                    (-1, -1),
+                    (0, -1),
+                    (1, -1),
+                    (-1, 0),
+                    (1, 0),
+                    (-1, 1),
+                    (0, 1),
+                    (1, 1),
+                ] {
+                    let (nx, ny) = (x + dx, y + dy);
+                    if (0..size).contains(&nx) && (0..size).contains(&ny) {
+                        queue.push((nx, ny));
+                    }
                 }
             }
         }
+        for row in energies.iter_mut() {
+            for energy in row.iter_mut().filter(|e| **e > 9) {
+                *energy = 0;
+            }
+        }
+        flashed.iter().flatten().filter(|f| **f).count()
     }
-}
 
-fn part1(lines: &readfile::Lines) {
-    let mut grid = Grid::new(lines);
-    let mut num_flashes = 0;
-    for _ in 0..100 {
-        num_flashes += grid.tick();
+    #[test]
+    fn matches_brute_force() {
+        generate::check_answers::<Day11>(
+            0..10,
+            |seed| 2 + seed as usize,
+            |_, input| {
+                let mut energies: Vec<Vec<u32>> = input
+                    .lines()
+                    .map(|l| l.chars().map(|c| c.to_digit(10).unwrap()).collect())
+                    .collect();
+                let flashes: usize = (0..100).map(|_| brute_force_step(&mut energies)).sum();
+                [Some(flashes.into()), None]
+            },
+        );
     }
-    println!("Part 1: {}", num_flashes);
-}
 
-fn part2(lines: &readfile::Lines) {
-    let mut grid = Grid::new(lines);
-    let mut i = 1;
-    while grid.tick() != grid.len_x * grid.len_y {
-        i += 1;
+    #[test]
+    fn never_synchronized() {
+        // the two pairs flash in different steps, each flash only lifts the others by 2
+        let day = Day11::parse(&readfile::Lines::from("05\n50")).unwrap();
+        assert_eq!(day.part2(), Answer::Unsolved);
     }
-    println!("Part 2: {}", i);
-}
 
-pub fn run() {
-    let lines = readfile::Lines::new("day11.txt");
-    part1(&lines);
-    part2(&lines);
+    #[test]
+    fn first_ten_steps() {
+        let mut cavern = parse_cavern(&readfile::Lines::from(EXAMPLE)).unwrap();
+        let flashes: usize = (0..10).map(|_| tick(&mut cavern)).sum();
+        assert_eq!(flashes, 204);
+    }
 }
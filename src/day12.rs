@@ -1,78 +1,269 @@
+use crate::answer::Answer;
+use crate::error::ParseError;
+use crate::generate::Rng;
 use crate::readfile;
-use std::collections::HashMap;
+use crate::search;
+use crate::solver::Solver;
 
+/// The caves by number, with the numbers of the caves connected to them.
 struct AdjacencyList {
-    list: HashMap<String, Vec<String>>,
+    names: Vec<String>,
+    list: Vec<Vec<usize>>,
+}
+
+/// A path through the caves so far: where it is, the small caves it went through and whether
+/// one of them may still be visited a second time.
+#[derive(Clone, PartialEq, Eq, Hash)]
+struct Walk {
+    cave: usize,
+    visited: Vec<bool>,
+    may_revisit: bool,
 }
 
 impl AdjacencyList {
-    pub fn new(lines: &readfile::Lines) -> AdjacencyList {
-        let mut adjacency: HashMap<String, Vec<String>> = HashMap::new();
-        for l in lines.lines() {
-            let parts: Vec<&str> = l.split('-').collect();
-            let from = parts[0];
-            let to = parts[1];
-            if !adjacency.contains_key(from) {
-                adjacency.insert(String::from(from), Vec::new());
+    pub fn new(lines: &readfile::Lines) -> Result<AdjacencyList, ParseError> {
+        let mut adjacency = AdjacencyList {
+            names: Vec::new(),
+            list: Vec::new(),
+        };
+        for l in lines.numbered() {
+            let (from, to) = l
+                .text()
+                .split_once('-')
+                .ok_or_else(|| l.error(1, "expected a connection like 'start-A'"))?;
+            for cave in [from, to] {
+                if cave.is_empty() || !cave.chars().all(|c| c.is_ascii_alphabetic()) {
+                    return Err(l.error_at(cave, &format!("invalid cave name '{}'", cave)));
+                }
             }
-            adjacency.get_mut(from).unwrap().push(String::from(to));
-            if !adjacency.contains_key(to) {
-                adjacency.insert(String::from(to), Vec::new());
+            let is_big = |cave: &str| cave.starts_with(|c: char| c.is_ascii_uppercase());
+            if is_big(from) && is_big(to) {
+                return Err(l.error(
+                    1,
+                    "big caves connected to each other allow infinitely many paths",
+                ));
             }
-            adjacency.get_mut(to).unwrap().push(String::from(from));
+            let (from, to) = (adjacency.number(from), adjacency.number(to));
+            adjacency.list[from].push(to);
+            adjacency.list[to].push(from);
         }
-        return AdjacencyList { list: adjacency };
+        return Ok(adjacency);
     }
 
-    pub fn find_paths(&self, node: &str, visited: &[String], allow_twice: bool) -> usize {
-        if node == "end" {
-            return 1;
+    /// The number of the cave, new caves are added.
+    fn number(&mut self, name: &str) -> usize {
+        match self.names.iter().position(|n| n == name) {
+            Some(cave) => cave,
+            None => {
+                self.names.push(String::from(name));
+                self.list.push(Vec::new());
+                self.names.len() - 1
+            }
         }
+    }
 
-        let mut vclone = visited.to_owned();
-        vclone.push(String::from(node));
-        let empty_vec: Vec<String> = Vec::new();
-        let adjacency_for_node: &Vec<String> = match self.list.get(node) {
-            Some(v) => v,
-            None => &empty_vec,
-        };
-        let mut sum = 0;
-        for n in adjacency_for_node {
-            if n == "start" {
+    fn find(&self, name: &str) -> Option<usize> {
+        self.names.iter().position(|n| n == name)
+    }
+
+    fn is_small(&self, cave: usize) -> bool {
+        self.names[cave].chars().next().unwrap().is_ascii_lowercase()
+    }
+
+    /// The walks one cave further, never back to the start.
+    fn next_walks(&self, walk: &Walk) -> Vec<Walk> {
+        let start = self.find("start");
+        let mut next = Vec::new();
+        for &n in &self.list[walk.cave] {
+            if Some(n) == start {
                 continue;
             }
-
-            let mut still_allow_twice = allow_twice;
-            if n.chars().next().unwrap().is_ascii_lowercase() && vclone.contains(n) {
-                if allow_twice {
-                    still_allow_twice = false;
-                } else {
+            let mut extended = walk.clone();
+            extended.cave = n;
+            if self.is_small(n) && walk.visited[n] {
+                if !walk.may_revisit {
                     continue;
                 }
+                extended.may_revisit = false;
             }
-            sum += self.find_paths(n, &vclone, still_allow_twice);
+            extended.visited[n] = self.is_small(n);
+            next.push(extended);
         }
+        return next;
+    }
 
-        return sum;
+    /// The number of paths from start to end that visit small caves at most once, except for
+    /// a single one visited twice if `allow_twice`. Walks in big caves cannot go in circles since
+    /// parsing rejects connections between big caves, so the paths can be counted per walk.
+    pub fn find_paths(&self, allow_twice: bool) -> usize {
+        let (start, end) = match (self.find("start"), self.find("end")) {
+            (Some(start), Some(end)) => (start, end),
+            _ => return 0,
+        };
+        let mut visited = vec![false; self.names.len()];
+        visited[start] = true;
+        let walk = Walk {
+            cave: start,
+            visited,
+            may_revisit: allow_twice,
+        };
+        search::count_paths(walk, |w| self.next_walks(w), |w| w.cave == end)
     }
 }
 
-fn part1(lines: &readfile::Lines) {
-    let adjacency = AdjacencyList::new(lines);
-    let visited: Vec<String> = Vec::new();
-    let num_paths = adjacency.find_paths("start", &visited, false);
-    println!("Part 1: {}", num_paths);
+pub struct Day12 {
+    adjacency: AdjacencyList,
 }
 
-fn part2(lines: &readfile::Lines) {
-    let adjacency = AdjacencyList::new(lines);
-    let visited: Vec<String> = Vec::new();
-    let num_paths = adjacency.find_paths("start", &visited, true);
-    println!("Part 2: {}", num_paths);
+impl Solver for Day12 {
+    fn parse(lines: &readfile::Lines) -> Result<Day12, ParseError> {
+        Ok(Day12 {
+            adjacency: AdjacencyList::new(lines)?,
+        })
+    }
+
+    fn part1(&self) -> Answer {
+        self.adjacency.find_paths(false).into()
+    }
+
+    fn part2(&self) -> Answer {
+        self.adjacency.find_paths(true).into()
+    }
+
+    /// A cave system with `size` small caves and a third as many big ones. Big caves are never
+    /// connected to each other, otherwise there would be infinitely many paths.
+    fn generate(rng: &mut Rng, size: usize) -> Option<String> {
+        let letter = |i: usize| (b'a' + i as u8) as char;
+        let name = |i: usize| format!("{}{}", letter(i / 26), letter(i % 26));
+        let mut caves = vec![String::from("start"), String::from("end")];
+        caves.extend((0..size.max(1)).map(name));
+        caves.extend((0..size.div_ceil(3)).map(|i| name(i).to_uppercase()));
+        let is_big = |cave: &str| cave.chars().all(|c| c.is_ascii_uppercase());
+
+        let mut connections: Vec<(usize, usize)> = Vec::new();
+        for _ in 0..3 * caves.len() / 2 {
+            let (a, b) = (rng.below(caves.len()), rng.below(caves.len()));
+            if a == b
+                || (is_big(&caves[a]) && is_big(&caves[b]))
+                || connections.contains(&(a, b))
+                || connections.contains(&(b, a))
+            {
+                continue;
+            }
+            connections.push((a, b));
+        }
+        let lines: Vec<String> = connections
+            .iter()
+            .map(|(a, b)| format!("{}-{}", caves[*a], caves[*b]))
+            .collect();
+        Some(lines.join("\n"))
+    }
 }
 
-pub fn run() {
-    let lines = readfile::Lines::new("day12.txt");
-    part1(&lines);
-    part2(&lines);
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generate;
+
+    const SMALL_EXAMPLE: &str = "\
+start-A
+start-b
+A-c
+A-b
+b-d
+A-end
+b-end";
+
+    const LARGER_EXAMPLE: &str = "\
+dc-end
+HN-start
+start-kj
+dc-start
+dc-HN
+LN-dc
+HN-end
+kj-sa
+kj-HN
+kj-dc";
+
+    /// Counts the paths by extending every partial path by one cave until none is left.
+    fn brute_force(input: &str, allow_twice: bool) -> usize {
+        let connections: Vec<(&str, &str)> =
+            input.lines().map(|l| l.split_once('-').unwrap()).collect();
+        let neighbours = |cave: &str| -> Vec<&str> {
+            connections
+                .iter()
+                .filter_map(|&(a, b)| {
+                    if a == cave {
+                        Some(b)
+                    } else if b == cave {
+                        Some(a)
+                    } else {
+                        None
+                    }
+                })
+                .collect()
+        };
+        let is_small = |cave: &str| cave.chars().all(|c| c.is_ascii_lowercase());
+        let may_visit = |path: &[&str], cave: &str| {
+            if cave == "start" || !is_small(cave) || !path.contains(&cave) {
+                return cave != "start";
+            }
+            let small: Vec<&str> = path.iter().copied().filter(|c| is_small(c)).collect();
+            let has_twice = small.iter().any(|c| small.iter().filter(|d| d == &c).count() > 1);
+            allow_twice && !has_twice
+        };
+
+        let mut count = 0;
+        let mut paths: Vec<Vec<&str>> = vec![vec!["start"]];
+        while let Some(path) = paths.pop() {
+            let last = path[path.len() - 1];
+            if last == "end" {
+                count += 1;
+                continue;
+            }
+            for n in neighbours(last) {
+                if may_visit(&path, n) {
+                    let mut extended = path.clone();
+                    extended.push(n);
+                    paths.push(extended);
+                }
+            }
+        }
+        return count;
+    }
+
+    #[test]
+    fn matches_brute_force() {
+        generate::check_answers::<Day12>(
+            0..30,
+            |seed| 2 + seed as usize % 5,
+            |_, input| {
+                [
+                    Some(brute_force(input, false).into()),
+                    Some(brute_force(input, true).into()),
+                ]
+            },
+        );
+    }
+
+    #[test]
+    fn connected_big_caves() {
+        let lines = readfile::Lines::from("start-A\nA-B\nB-end");
+        assert_eq!(Day12::parse(&lines).err().unwrap().line, 2);
+    }
+
+    #[test]
+    fn small_example() {
+        let day = Day12::parse(&readfile::Lines::from(SMALL_EXAMPLE)).unwrap();
+        assert_eq!(day.part1(), Answer::Number(10));
+        assert_eq!(day.part2(), Answer::Number(36));
+    }
+
+    #[test]
+    fn larger_example() {
+        let day = Day12::parse(&readfile::Lines::from(LARGER_EXAMPLE)).unwrap();
+        assert_eq!(day.part1(), Answer::Number(19));
+        assert_eq!(day.part2(), Answer::Number(103));
+    }
 }
@@ -1,65 +1,92 @@
+use crate::answer::Answer;
+use crate::error::ParseError;
+use crate::generate::Rng;
+use crate::grid::Grid;
+use crate::netpbm::Image;
 use crate::readfile;
+use crate::solver::Solver;
+use crate::visualize::Animation;
 use regex::Regex;
 use std::collections::VecDeque;
 
 type Point = [usize; 2];
 
+#[derive(Copy, Clone)]
 enum FoldDirection {
     Horizontal = 0,
     Vertical = 1,
 }
 
+#[derive(Copy, Clone)]
 struct FoldInstruction {
     direction: FoldDirection,
     coordinate: usize,
 }
 
+#[derive(Clone)]
 struct Game {
     points: Vec<Point>,
     folds: VecDeque<FoldInstruction>,
 }
 
 impl Game {
-    pub fn new(lines: &readfile::Lines) -> Game {
-        let re = Regex::new("fold along (x|y)=(\\d+)").unwrap();
+    pub fn new(lines: &readfile::Lines) -> Result<Game, ParseError> {
+        let re = Regex::new("^fold along (x|y)=(\\d+)$").unwrap();
         let mut points: Vec<Point> = Vec::new();
         let mut folds: VecDeque<FoldInstruction> = VecDeque::new();
+        // the coordinates of the dots along each axis, folded along with the paper so that every
+        // fold can be checked to keep the dots on the sheet
+        let mut coordinates: [Vec<usize>; 2] = [vec![], vec![]];
 
         // true if parsing points, false if parsing fold instructions
         let mut parse_mode = true;
-        for l in lines.lines() {
+        for l in lines.numbered() {
             if l.is_empty() {
                 parse_mode = false;
                 continue;
             }
 
             if parse_mode {
-                let coordinates: Vec<&str> = l.split(',').collect();
-                let p = [
-                    coordinates[0].parse().unwrap(),
-                    coordinates[1].parse().unwrap(),
-                ];
+                let (x, y) = l
+                    .text()
+                    .split_once(',')
+                    .ok_or_else(|| l.error(1, "expected a point like '6,10'"))?;
+                let p: Point = [l.parse_field(x)?, l.parse_field(y)?];
+                coordinates[0].push(p[0]);
+                coordinates[1].push(p[1]);
                 points.push(p);
             } else {
-                let groups = re.captures(l).unwrap();
+                let groups = l.captures(&re)?;
                 let direction = match &groups[1] {
                     "x" => FoldDirection::Horizontal,
-                    "y" => FoldDirection::Vertical,
-                    _ => panic!("Invalid fold direction"),
+                    _ => FoldDirection::Vertical,
                 };
-                let coordinate: usize = groups[2].parse().unwrap();
+                let coordinate: usize = l.parse_field(&groups[2])?;
+                let along = &mut coordinates[direction as usize];
+                if along.iter().any(|c| c.saturating_sub(coordinate) > coordinate) {
+                    return Err(l.error_at(
+                        &groups[2],
+                        "folding here would move dots past the edge of the paper",
+                    ));
+                }
+                for c in along.iter_mut().filter(|c| **c > coordinate) {
+                    *c = coordinate - (*c - coordinate);
+                }
                 folds.push_back(FoldInstruction {
                     direction,
                     coordinate,
                 });
             }
         }
+        if folds.is_empty() {
+            return Err(lines.error_at_end("expected at least one fold instruction"));
+        }
         points.sort_unstable();
 
-        return Game {
+        return Ok(Game {
             points,
             folds,
-        };
+        });
     }
 
     pub fn fold(&mut self) {
@@ -72,7 +99,7 @@ impl Game {
             .map(|p| {
                 if p[coord_index] > coordinate {
                     let mut new_point = *p;
-                    new_point[coord_index] = 2 * coordinate - p[coord_index];
+                    new_point[coord_index] = coordinate - (p[coord_index] - coordinate);
                     return new_point;
                 } else {
                     return *p;
@@ -84,38 +111,212 @@ impl Game {
         self.points = new_points;
     }
 
-    pub fn print(&self) {
-        let max_x = self.points.iter().map(|p| p[0]).max().unwrap();
-        let max_y = self.points.iter().map(|p| p[1]).max().unwrap();
-        for y in 0..=max_y {
-            for x in 0..=max_x {
-                if self.points.contains(&[x, y]) {
-                    print!("#");
-                } else {
-                    print!(" ");
-                }
-            }
-            println!();
+    /// The paper up to the last dot, `true` where there is a dot.
+    pub fn paper(&self) -> Grid<bool> {
+        let max_x = self.points.iter().map(|p| p[0]).max().unwrap_or(0);
+        let max_y = self.points.iter().map(|p| p[1]).max().unwrap_or(0);
+        let mut paper = Grid::new(max_x + 1, max_y + 1, false);
+        for p in &self.points {
+            paper[*p] = true;
         }
+        return paper;
+    }
+
+    pub fn render(&self) -> String {
+        self.paper().render(|_, dot| String::from(if *dot { "#" } else { " " }))
     }
 }
 
-fn part1(lines: &readfile::Lines) {
-    let mut game = Game::new(lines);
-    game.fold();
-    println!("Part 1: {}", game.points.len());
+pub struct Day13 {
+    game: Game,
 }
 
-fn part2(lines: &readfile::Lines) {
-    let mut game = Game::new(lines);
-    while !game.folds.is_empty() {
+impl Solver for Day13 {
+    fn parse(lines: &readfile::Lines) -> Result<Day13, ParseError> {
+        Ok(Day13 {
+            game: Game::new(lines)?,
+        })
+    }
+
+    fn part1(&self) -> Answer {
+        let mut game = self.game.clone();
         game.fold();
+        game.points.len().into()
+    }
+
+    fn part2(&self) -> Answer {
+        let mut game = self.game.clone();
+        while !game.folds.is_empty() {
+            game.fold();
+        }
+        Answer::Art(game.render())
+    }
+
+    /// The paper after all folds, the dots in black.
+    fn image(&self) -> Option<Image> {
+        let mut game = self.game.clone();
+        while !game.folds.is_empty() {
+            game.fold();
+        }
+        Some(Image::Bitmap(game.paper()))
+    }
+
+    fn visualize(&self, animation: &mut Animation) -> bool {
+        let mut game = self.game.clone();
+        animation.frame(&format!("{} dots", game.points.len()), &game.render());
+        while let Some(fold) = game.folds.front().copied() {
+            let axis = match fold.direction {
+                FoldDirection::Horizontal => "x",
+                FoldDirection::Vertical => "y",
+            };
+            game.fold();
+            let caption = format!(
+                "fold along {}={}: {} dots",
+                axis,
+                fold.coordinate,
+                game.points.len()
+            );
+            animation.frame(&caption, &game.render());
+        }
+        true
+    }
+
+    /// Random dots on a small sheet, unfolded `size` times (at least once) along its middle.
+    /// Like in the puzzle the dots never lie on a fold.
+    fn generate(rng: &mut Rng, size: usize) -> Option<String> {
+        let mut dimensions = [rng.below(6) + 5, rng.below(4) + 3];
+        let mut points: Vec<Point> = (0..dimensions[0] * dimensions[1] / 2)
+            .map(|_| [rng.below(dimensions[0]), rng.below(dimensions[1])])
+            .collect();
+        let mut folds: Vec<String> = vec![];
+        for _ in 0..size.max(1) {
+            let axis = rng.below(2);
+            let coordinate = dimensions[axis];
+            dimensions[axis] = 2 * coordinate + 1;
+            // each dot stays, moves to the other half or is on both
+            let mut unfolded = vec![];
+            for p in points {
+                let mut mirrored = p;
+                mirrored[axis] = 2 * coordinate - p[axis];
+                match rng.below(3) {
+                    0 => unfolded.push(p),
+                    1 => unfolded.push(mirrored),
+                    _ => unfolded.extend([p, mirrored]),
+                }
+            }
+            points = unfolded;
+            folds.push(format!("fold along {}={}", ["x", "y"][axis], coordinate));
+        }
+        points.sort_unstable();
+        points.dedup();
+        rng.shuffle(&mut points);
+        folds.reverse();
+        let points: Vec<String> = points.iter().map(|[x, y]| format!("{},{}", x, y)).collect();
+        Some(format!("{}\n\n{}", points.join("\n"), folds.join("\n")))
     }
-    game.print();
 }
 
-pub fn run() {
-    let lines = readfile::Lines::new("day13.txt");
-    part1(&lines);
-    part2(&lines);
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generate;
+
+    const EXAMPLE: &str = "\
+6,10
+0,14
+9,10
+0,3
+10,4
+4,11
+6,0
+6,12
+4,1
+0,13
+10,12
+3,4
+3,0
+8,4
+1,10
+2,14
+8,10
+9,0
+
+fold along y=7
+fold along x=5";
+
+    #[test]
+    fn example() {
+        let day = Day13::parse(&readfile::Lines::from(EXAMPLE)).unwrap();
+        assert_eq!(day.part1(), Answer::Number(17));
+        let square = "\
+#####
+#   #
+#   #
+#   #
+#####";
+        assert_eq!(day.part2(), Answer::Art(String::from(square)));
+    }
+
+    /// Folds a sheet of paper by putting its halves on top of each other.
+    fn brute_force_fold(paper: &Grid<bool>, fold: &FoldInstruction) -> Grid<bool> {
+        let c = fold.coordinate;
+        match fold.direction {
+            FoldDirection::Horizontal => Grid::from_fn(c, paper.height(), |[x, y]| {
+                paper[[x, y]] || paper.get([2 * c - x, y]) == Some(&true)
+            }),
+            FoldDirection::Vertical => Grid::from_fn(paper.width(), c, |[x, y]| {
+                paper[[x, y]] || paper.get([x, 2 * c - y]) == Some(&true)
+            }),
+        }
+    }
+
+    #[test]
+    fn matches_brute_force() {
+        generate::check_answers::<Day13>(
+            0..20,
+            |seed| 1 + seed as usize % 4,
+            |day, _| {
+                let mut paper = Game {
+                    points: day.game.points.clone(),
+                    folds: VecDeque::new(),
+                }
+                .paper();
+                let mut first_fold = None;
+                for fold in &day.game.folds {
+                    paper = brute_force_fold(&paper, fold);
+                    let dots = paper.values().filter(|d| **d).count();
+                    first_fold.get_or_insert(Answer::from(dots));
+                }
+                let points = paper.iter().filter(|(_, d)| **d).map(|(p, _)| p).collect();
+                let expected = Game {
+                    points,
+                    folds: VecDeque::new(),
+                };
+                [first_fold, Some(Answer::Art(expected.render()))]
+            },
+        );
+    }
+
+    #[test]
+    fn invalid_folds() {
+        let err = Day13::parse(&readfile::Lines::from("6,10\n0,14")).err().unwrap();
+        assert_eq!(err.line, 3);
+        let err = Day13::parse(&readfile::Lines::from("6,10\n0,14\n\nfold along y=6"))
+            .err()
+            .unwrap();
+        assert_eq!((err.line, err.column), (4, 14));
+        let input = "6,10\n0,14\n\nfold along y=7\nfold along x=2";
+        assert!(Day13::parse(&readfile::Lines::from(input)).is_err());
+    }
+
+    #[test]
+    fn visualize_shows_every_fold() {
+        let day = Day13::parse(&readfile::Lines::from(EXAMPLE)).unwrap();
+        let mut animation = Animation::recording();
+        assert!(day.visualize(&mut animation));
+        let frames = animation.recorded();
+        assert_eq!(frames.len(), 3);
+        assert!(frames[0].starts_with("18 dots\n"));
+        assert!(frames[2].starts_with("fold along x=5: 16 dots\n#####"));
+    }
 }
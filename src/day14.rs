@@ -1,19 +1,27 @@
+use crate::answer::Answer;
+use crate::error::ParseError;
+use crate::generate::Rng;
 use crate::readfile;
+use crate::solver::{Param, Params, Solver};
+use num::{BigUint, Zero};
 use std::collections::HashMap;
+use std::ops::AddAssign;
 
 type Pair = [char; 2];
 
-trait UpsertMapTrait<K> {
-    fn insert_or_increase(&mut self, pair: &K, amount: usize);
-    fn upsert_with_value(&mut self, pair: &K, value: usize);
+trait UpsertMapTrait<K, V> {
+    fn insert_or_increase(&mut self, pair: &K, amount: V);
 }
 
-type PairMap = HashMap<Pair, usize>;
-impl<K> UpsertMapTrait<K> for HashMap<K, usize>
+/// The number of each pair in the polymer. The polymer about doubles in every step, so the
+/// counts no longer fit into a u64 after around 60 steps.
+type PairMap = HashMap<Pair, BigUint>;
+impl<K, V> UpsertMapTrait<K, V> for HashMap<K, V>
 where
     K: std::cmp::Eq + std::hash::Hash + Copy + std::fmt::Debug,
+    V: AddAssign,
 {
-    fn insert_or_increase(&mut self, k: &K, amount: usize) {
+    fn insert_or_increase(&mut self, k: &K, amount: V) {
         if self.contains_key(k) {
             let val_ref = self.get_mut(k).unwrap();
             *val_ref += amount;
@@ -21,17 +29,9 @@ where
             self.insert(*k, amount);
         }
     }
-
-    fn upsert_with_value(&mut self, k: &K, value: usize) {
-        if self.contains_key(k) {
-            let val_ref = self.get_mut(k).unwrap();
-            *val_ref = value;
-        } else {
-            self.insert(*k, value);
-        }
-    }
 }
 
+#[derive(Clone)]
 struct Chemistry {
     pairs: PairMap,
     rules: HashMap<Pair, char>,
@@ -39,78 +39,182 @@ struct Chemistry {
 }
 
 impl Chemistry {
-    pub fn new(lines: &readfile::Lines) -> Chemistry {
-        let mut iter = lines.lines();
-        let polymer: Vec<char> = String::from(iter.next().unwrap()).chars().collect();
+    pub fn new(lines: &readfile::Lines) -> Result<Chemistry, ParseError> {
+        let mut iter = lines.numbered();
+        let first = lines.first()?;
+        let polymer: Vec<char> = first.text().chars().collect();
+        if polymer.len() < 2 {
+            return Err(first.error(1, "expected a polymer of at least two elements"));
+        }
         let mut pairs = PairMap::new();
         for i in 0..polymer.len() - 1 {
-            pairs.insert_or_increase(&[polymer[i], polymer[i + 1]], 1);
+            pairs.insert_or_increase(&[polymer[i], polymer[i + 1]], BigUint::from(1u32));
         }
 
         let mut rules: HashMap<Pair, char> = HashMap::new();
-        iter.next();
+        iter.nth(1);
         for l in iter {
-            let parts: Vec<&str> = l.split(" -> ").collect();
-            let pair: Vec<char> = parts[0].chars().collect();
-            rules.insert([pair[0], pair[1]], parts[1].chars().next().unwrap());
+            let (pair, element) = l
+                .text()
+                .split_once(" -> ")
+                .ok_or_else(|| l.error(1, "expected a rule like 'CH -> B'"))?;
+            let pair: Vec<char> = pair.chars().collect();
+            let element: Vec<char> = element.chars().collect();
+            if pair.len() != 2 || element.len() != 1 {
+                return Err(l.error(1, "expected a rule like 'CH -> B'"));
+            }
+            rules.insert([pair[0], pair[1]], element[0]);
         }
 
-        return Chemistry {
+        return Ok(Chemistry {
             pairs,
             rules,
             last_element: polymer[polymer.len() - 1],
-        };
+        });
     }
 
     pub fn step(&mut self) {
         let current_pairs = self.pairs.clone();
         for (pair, num) in current_pairs.iter() {
             if self.rules.contains_key(pair) {
-                let occurences = *num;
+                let occurences = num.clone();
                 let insert = *self.rules.get(pair).unwrap();
-                *self.pairs.get_mut(pair).unwrap() -= occurences;
+                *self.pairs.get_mut(pair).unwrap() -= &occurences;
                 self.pairs
-                    .insert_or_increase(&[pair[0], insert], occurences);
+                    .insert_or_increase(&[pair[0], insert], occurences.clone());
                 self.pairs
                     .insert_or_increase(&[insert, pair[1]], occurences);
             }
         }
 
         // remove all pairs with value 0
-        self.pairs.retain(|_k, v| *v > 0);
+        self.pairs.retain(|_k, v| !v.is_zero());
     }
 }
 
-fn iterate(lines: &readfile::Lines, steps: usize) -> usize {
-    let mut chem = Chemistry::new(lines);
+fn iterate(chemistry: &Chemistry, steps: usize) -> BigUint {
+    let mut chem = chemistry.clone();
 
     for _ in 0..steps {
         chem.step();
     }
 
-    let mut char_occurences: HashMap<char, usize> = HashMap::new();
+    let mut char_occurences: HashMap<char, BigUint> = HashMap::new();
     for (pair, num) in chem.pairs.iter() {
-        char_occurences.insert_or_increase(&pair[0], *num);
+        char_occurences.insert_or_increase(&pair[0], num.clone());
     }
-    char_occurences.insert_or_increase(&chem.last_element, 1);
+    char_occurences.insert_or_increase(&chem.last_element, BigUint::from(1u32));
 
-    let mut occurences: Vec<usize> = char_occurences.values().copied().collect();
+    let mut occurences: Vec<BigUint> = char_occurences.into_values().collect();
     occurences.sort_unstable();
-    return occurences[occurences.len() - 1] - occurences[0];
+    return &occurences[occurences.len() - 1] - &occurences[0];
 }
 
-fn part1(lines: &readfile::Lines) {
-    let result = iterate(lines, 10);
-    println!("Part 1: {}", result);
+pub struct Day14 {
+    chemistry: Chemistry,
+    steps: Option<usize>,
 }
 
-fn part2(lines: &readfile::Lines) {
-    let result = iterate(lines, 40);
-    println!("Part 2: {}", result);
+impl Solver for Day14 {
+    fn parse(lines: &readfile::Lines) -> Result<Day14, ParseError> {
+        Ok(Day14 {
+            chemistry: Chemistry::new(lines)?,
+            steps: None,
+        })
+    }
+
+    fn part1(&self) -> Answer {
+        let result = iterate(&self.chemistry, self.steps.unwrap_or(10));
+        result.into()
+    }
+
+    fn part2(&self) -> Answer {
+        let result = iterate(&self.chemistry, self.steps.unwrap_or(40));
+        result.into()
+    }
+
+    fn params() -> &'static [Param] {
+        &[Param {
+            name: "steps",
+            help: "number of insertion steps (10 in part 1, 40 in part 2)",
+        }]
+    }
+
+    fn configure(&mut self, params: &Params) -> Result<(), String> {
+        self.steps = params.get("steps")?;
+        Ok(())
+    }
+
+    /// A polymer of `size` elements (at least 2) out of up to 10 different ones, with a rule
+    /// for every pair of them like in the puzzle.
+    fn generate(rng: &mut Rng, size: usize) -> Option<String> {
+        let elements: Vec<char> = "BCFHKNOPSV".chars().take(size.clamp(2, 10)).collect();
+        let polymer: String = (0..size.max(2)).map(|_| *rng.choose(&elements)).collect();
+        let mut lines = vec![polymer, String::new()];
+        for a in &elements {
+            for b in &elements {
+                lines.push(format!("{}{} -> {}", a, b, rng.choose(&elements)));
+            }
+        }
+        Some(lines.join("\n"))
+    }
 }
 
-pub fn run() {
-    let lines = readfile::Lines::new("day14.txt");
-    part1(&lines);
-    part2(&lines);
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generate;
+
+    const EXAMPLE: &str = "\
+NNCB
+
+CH -> B
+HH -> N
+CB -> H
+NH -> C
+HB -> C
+HC -> B
+HN -> C
+NN -> C
+BH -> H
+NC -> B
+NB -> B
+BN -> B
+BB -> N
+BC -> B
+CC -> N
+CN -> C";
+
+    #[test]
+    fn example() {
+        let day = Day14::parse(&readfile::Lines::from(EXAMPLE)).unwrap();
+        assert_eq!(day.part1(), Answer::Number(1588));
+        assert_eq!(day.part2(), Answer::Number(2188189693529));
+    }
+
+    #[test]
+    fn matches_brute_force() {
+        generate::check_answers::<Day14>(
+            0..10,
+            |seed| 2 + seed as usize,
+            |day, input| {
+                // the whole polymer after 10 steps
+                let mut polymer: Vec<char> = input.lines().next().unwrap().chars().collect();
+                for _ in 0..10 {
+                    let mut next = vec![polymer[0]];
+                    for pair in polymer.windows(2) {
+                        next.push(day.chemistry.rules[&[pair[0], pair[1]]]);
+                        next.push(pair[1]);
+                    }
+                    polymer = next;
+                }
+                let mut counts: HashMap<char, usize> = HashMap::new();
+                for c in polymer {
+                    *counts.entry(c).or_insert(0) += 1;
+                }
+                let expected = counts.values().max().unwrap() - counts.values().min().unwrap();
+                [Some(expected.into()), None]
+            },
+        );
+    }
 }
@@ -1,180 +1,283 @@
+use crate::answer::Answer;
+use crate::error::ParseError;
+use crate::generate::Rng;
+use crate::grid::{Grid, Point};
+use crate::netpbm::Image;
 use crate::readfile;
-use std::cmp::Ordering;
-use std::collections::BinaryHeap;
-use std::collections::HashMap;
+use crate::search::{self, Path, Search};
+use crate::solver::{Param, Params, Solver};
+use crate::visualize::Animation;
+use colored::*;
 
-const PRINT_PATH: bool = false;
+/// The risk levels of the cave tiled `tiles` times in both directions, the risk increasing by
+/// one per tile and wrapping from 9 back to 1.
+pub fn tile(risks: &Grid<u32>, tiles: usize) -> Grid<u32> {
+    let (width, height) = (risks.width(), risks.height());
+    Grid::from_fn(tiles * width, tiles * height, |[x, y]| {
+        let risk = risks[[x % width, y % height]] + (x / width + y / height) as u32;
+        (risk - 1) % 9 + 1
+    })
+}
 
-type Point = [isize; 2];
+/// The points next to `p` with the risk of entering them.
+fn neighbours(risks: &Grid<u32>, p: Point) -> impl Iterator<Item = (Point, usize)> + '_ {
+    risks.neighbours4(p).map(|n| (n, risks[n] as usize))
+}
 
-struct Grid {
-    multiplier: usize,
-    costs: Vec<usize>,
-    len_x: usize,
-    len_y: usize,
+/// A path with the lowest total risk from the top left to the bottom right, with that risk.
+/// The path follows the point each point was reached from.
+pub fn run_dijkstra(risks: &Grid<u32>) -> Path<Point> {
+    let target: Point = [risks.width() - 1, risks.height() - 1];
+    let path = search::dijkstra([0, 0], |p| neighbours(risks, *p), |p| *p == target);
+    return path.expect("The bottom right is always reachable");
 }
 
-impl Grid {
-    pub fn new(lines: &readfile::Lines, multiplier: usize) -> Grid {
-        let all: Vec<&str> = lines.lines().collect();
-        let costs = all
-            .iter()
-            .flat_map(|l| l.chars().map(|c| c.to_digit(10).unwrap() as usize))
-            .collect();
-        return Grid {
-            multiplier,
-            costs,
-            len_x: all[0].len(),
-            len_y: all.len(),
-        };
-    }
-
-    pub fn get(&self, x: isize, y: isize) -> Option<usize> {
-        if x < 0
-            || y < 0
-            || x >= (self.multiplier * self.len_x) as isize
-            || y >= (self.multiplier * self.len_y) as isize
-        {
-            return None;
-        };
-
-        let add_x = x as usize / self.len_x;
-        let add_y = y as usize / self.len_y;
-        let mod_x = x as usize % self.len_x;
-        let mod_y = y as usize % self.len_y;
-
-        let pos = mod_y * self.len_x + mod_x;
-        if pos >= self.costs.len() {
-            panic!("This shouldn't happen");
+/// The lowest total risks from the top left, exact for the points settled before the bottom
+/// right was reached, and the path to it. `on_settled` is called with the distances so far
+/// for every settled point.
+fn find_distances<F>(risks: &Grid<u32>, mut on_settled: F) -> (Grid<usize>, Vec<Point>)
+where
+    F: FnMut(&Grid<usize>, Point),
+{
+    let mut dist: Grid<usize> = Grid::new(risks.width(), risks.height(), usize::MAX);
+    let target: Point = [risks.width() - 1, risks.height() - 1];
+    let mut search = Search::new([0, 0], |p: &Point| neighbours(risks, *p), |_| 0);
+    while let Some((p, cost)) = search.next() {
+        dist[p] = cost;
+        for n in risks.neighbours4(p) {
+            dist[n] = search.cost(&n).unwrap();
         }
-        let mut value = self.costs[pos] + add_x + add_y;
-        if value > 9 {
-            value = (value % 10) + 1;
+        on_settled(&dist, p);
+        if p == target {
+            break;
         }
-        return Some(value);
     }
+    return (dist, search.path(&target).unwrap());
 }
 
-#[derive(Copy, Clone, Eq, PartialEq)]
-struct DijkstraPointData {
-    distance: usize,
-    point: Point,
-    // prev: Option<Point>,
+/// The risk levels with the path in red on top, the other risks the darker the lower, so
+/// the detours around high risks stand out.
+fn render_path(risks: &Grid<u32>, path: &[Point]) -> String {
+    let mut on_path = Grid::new(risks.width(), risks.height(), false);
+    for p in path {
+        on_path[*p] = true;
+    }
+    risks.render(|p, risk| {
+        let digit = risk.to_string();
+        if on_path[p] {
+            digit.bright_red().bold().to_string()
+        } else if *risk <= 3 {
+            digit.bright_black().to_string()
+        } else {
+            digit
+        }
+    })
 }
 
-impl Ord for DijkstraPointData {
-    fn cmp(&self, other: &Self) -> Ordering {
-        other
-            .distance
-            .cmp(&self.distance)
-            .then_with(|| self.point.cmp(&other.point))
-    }
+/// The risk levels, the settled points in blue, the frontier of Dijkstra in yellow and the
+/// path in red.
+fn render(risks: &Grid<u32>, dist: &Grid<usize>, settled: &Grid<bool>, path: &[Point]) -> String {
+    risks.render(|p, risk| {
+        let risk = risk.to_string();
+        if path.contains(&p) {
+            risk.bright_red().bold().to_string()
+        } else if settled[p] {
+            risk.blue().to_string()
+        } else if dist[p] != usize::MAX {
+            risk.yellow().to_string()
+        } else {
+            risk.bright_black().to_string()
+        }
+    })
 }
 
-impl PartialOrd for DijkstraPointData {
-    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        Some(self.cmp(other))
-    }
+pub struct Day15 {
+    risks: Grid<u32>,
+    tiles: Option<usize>,
 }
 
-fn get_neighbors(all_points: &Grid, point: Point) -> Vec<Point> {
-    let mut neighbors: Vec<Point> = Vec::new();
-    let candidates: [Point; 4] = [
-        [point[0] + 1, point[1]],
-        [point[0] - 1, point[1]],
-        [point[0], point[1] + 1],
-        [point[0], point[1] - 1],
-    ];
-    for p in candidates {
-        if all_points.get(p[0], p[1]).is_some() {
-            neighbors.push(p);
-        }
+impl Solver for Day15 {
+    fn parse(lines: &readfile::Lines) -> Result<Day15, ParseError> {
+        Ok(Day15 {
+            risks: Grid::parse_digits(lines)?,
+            tiles: None,
+        })
     }
-    return neighbors;
-}
 
-fn run_dijkstra(all_points: &Grid) -> usize {
-    let mut dist: HashMap<Point, usize> = HashMap::new();
-    let mut heap: BinaryHeap<DijkstraPointData> = BinaryHeap::new();
-    let target: Point = [
-        (all_points.multiplier * all_points.len_x) as isize - 1,
-        (all_points.multiplier * all_points.len_y) as isize - 1,
-    ];
-
-    for y in 0..=target[1] {
-        for x in 0..=target[0] {
-            let p: Point = [x as isize, y as isize];
-            dist.insert(p, usize::MAX);
-        }
+    fn part1(&self) -> Answer {
+        run_dijkstra(&self.risks).cost.into()
     }
 
-    *dist.get_mut(&[0, 0]).unwrap() = 0;
-    heap.push(DijkstraPointData {
-        point: [0, 0],
-        distance: 0,
-    });
+    fn part2(&self) -> Answer {
+        let risks = tile(&self.risks, self.tiles.unwrap_or(5));
+        run_dijkstra(&risks).cost.into()
+    }
 
-    while let Some(data) = heap.pop() {
-        if data.point[0] == target[0] && data.point[1] == target[1] {
-            // return data.distance;
-            break;
+    fn params() -> &'static [Param] {
+        &[Param {
+            name: "tiles",
+            help: "the full map in part 2 is tiles x tiles copies of the input (5)",
+        }]
+    }
+
+    fn configure(&mut self, params: &Params) -> Result<(), String> {
+        match params.get("tiles")? {
+            Some(0) => Err(String::from("tiles must be at least 1")),
+            tiles => {
+                self.tiles = tiles;
+                Ok(())
+            }
         }
+    }
 
-        if data.distance > *dist.get(&data.point).unwrap() {
-            continue;
+    /// The full map of part 2 with the path in red, the higher the risk the darker.
+    fn image(&self) -> Option<Image> {
+        let risks = tile(&self.risks, self.tiles.unwrap_or(5));
+        let mut pixels = risks.map(|risk| {
+            let gray = (255 - 25 * risk) as u8;
+            [gray, gray, gray]
+        });
+        for p in run_dijkstra(&risks).states {
+            pixels[p] = [220, 20, 20];
         }
+        Some(Image::Pixmap(pixels))
+    }
+
+    /// A `size` x `size` map (at least 2 x 2) of random risks.
+    fn generate(rng: &mut Rng, size: usize) -> Option<String> {
+        let size = size.max(2);
+        let risks: Grid<usize> = Grid::from_fn(size, size, |_| rng.below(9) + 1);
+        Some(risks.render(|_, risk| risk.to_string()))
+    }
 
-        for n in get_neighbors(all_points, data.point) {
-            let next = DijkstraPointData {
-                point: n,
-                distance: data.distance + all_points.get(n[0], n[1]).unwrap(),
-            };
-            if next.distance < *dist.get(&n).unwrap() {
-                heap.push(next);
-                *dist.get_mut(&n).unwrap() = next.distance;
+    /// Dijkstra on the map of part 1, a frame per row worth of settled points, then the path
+    /// of part 2 on the full map.
+    fn visualize(&self, animation: &mut Animation) -> bool {
+        let risks = &self.risks;
+        let mut settled = Grid::new(risks.width(), risks.height(), false);
+        let mut count = 0;
+        let (dist, path) = find_distances(risks, |dist, p| {
+            settled[p] = true;
+            count += 1;
+            if count % risks.width() == 0 {
+                let caption = format!("{} points settled", count);
+                animation.frame(&caption, &render(risks, dist, &settled, &[]));
             }
+        });
+        let target = path[path.len() - 1];
+        let caption = format!("lowest total risk {}", dist[target]);
+        animation.frame(&caption, &render(risks, &dist, &settled, &path));
+
+        let tiles = self.tiles.unwrap_or(5);
+        let full_map = tile(risks, tiles);
+        let path = run_dijkstra(&full_map);
+        let caption = format!(
+            "lowest total risk {} on the map tiled {} x {}",
+            path.cost, tiles, tiles
+        );
+        animation.frame(&caption, &render_path(&full_map, &path.states));
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generate;
+
+    const EXAMPLE: &str = "\
+1163751742
+1381373672
+2136511328
+3694931569
+7463417111
+1319128137
+1359912421
+3125421639
+1293138521
+2311944581";
+
+    #[test]
+    fn example() {
+        let day = Day15::parse(&readfile::Lines::from(EXAMPLE)).unwrap();
+        assert_eq!(day.part1(), Answer::Number(40));
+        assert_eq!(day.part2(), Answer::Number(315));
+    }
+
+    /// Checks that `path` leads from the top left to the bottom right in single steps and that
+    /// its risk adds up to `cost`.
+    fn assert_valid_path(risks: &Grid<u32>, path: &Path<Point>) {
+        assert_eq!(path.states[0], [0, 0]);
+        assert_eq!(path.states.last(), Some(&[risks.width() - 1, risks.height() - 1]));
+        for step in path.states.windows(2) {
+            assert!(risks.neighbours4(step[0]).any(|n| n == step[1]), "{:?}", step);
         }
+        let risk: usize = path.states[1..].iter().map(|p| risks[*p] as usize).sum();
+        assert_eq!(risk, path.cost);
     }
 
-    if PRINT_PATH {
-        let mut p = target;
-        let mut path: Vec<Point> = vec![target];
-        loop {
-            if p[0] == 0 && p[1] == 0 {
-                break;
+    #[test]
+    fn path_has_the_lowest_risk() {
+        let risks = Grid::parse_digits(&readfile::Lines::from(EXAMPLE)).unwrap();
+        let path = run_dijkstra(&risks);
+        assert_eq!(path.cost, 40);
+        assert_valid_path(&risks, &path);
+
+        let full_map = tile(&risks, 5);
+        let path = run_dijkstra(&full_map);
+        assert_eq!(path.cost, 315);
+        assert_valid_path(&full_map, &path);
+    }
+
+    /// The lowest total risks by lowering the risk of every point from its neighbours until
+    /// nothing changes any more.
+    fn brute_force(risks: &Grid<u32>) -> usize {
+        let mut total: Grid<usize> = Grid::new(risks.width(), risks.height(), usize::MAX);
+        total[[0, 0]] = 0;
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for p in risks.points() {
+                for n in risks.neighbours4(p) {
+                    if total[n] != usize::MAX && total[n] + (risks[p] as usize) < total[p] {
+                        total[p] = total[n] + risks[p] as usize;
+                        changed = true;
+                    }
+                }
             }
-            let (best_neighbor, _) = get_neighbors(all_points, p)
-                .iter()
-                .map(|n| (*n, *dist.get(n).unwrap()))
-                .filter(|(_, d)| *d < dist[&p])
-                .min_by(|(_, a), (_, b)| a.cmp(b))
-                .expect(&format!("{},{}: {}", p[0], p[1], dist[&p])[..]);
-            path.push(best_neighbor);
-            p = best_neighbor;
-        }
-        path.reverse();
-        for p in path {
-            println!("{},{}: {}", p[0], p[1], dist[&p]);
         }
+        return total[[risks.width() - 1, risks.height() - 1]];
     }
 
-    return *dist.get(&target).unwrap();
-}
-
-fn part1(lines: &readfile::Lines) {
-    let grid = Grid::new(lines, 1);
-    let distance = run_dijkstra(&grid);
-    println!("Part 1: {}", distance);
-}
+    #[test]
+    fn matches_brute_force() {
+        generate::check_answers::<Day15>(
+            0..10,
+            |seed| 2 + seed as usize,
+            |day, _| {
+                let part1 = brute_force(&day.risks);
+                let part2 = brute_force(&tile(&day.risks, 5));
+                [Some(part1.into()), Some(part2.into())]
+            },
+        );
+    }
 
-fn part2(lines: &readfile::Lines) {
-    let grid = Grid::new(lines, 5);
-    let distance = run_dijkstra(&grid);
-    println!("Part 2: {}", distance);
-}
+    #[test]
+    fn visualize_ends_with_the_full_map() {
+        let day = Day15::parse(&readfile::Lines::from(EXAMPLE)).unwrap();
+        let mut animation = Animation::recording();
+        assert!(day.visualize(&mut animation));
+        let frames = animation.recorded();
+        let last = frames.last().unwrap();
+        assert!(last.starts_with("lowest total risk 315 on the map tiled 5 x 5\n"));
+        assert_eq!(last.lines().count(), 1 + 50);
+        assert!(frames[frames.len() - 2].starts_with("lowest total risk 40\n"));
+    }
 
-pub fn run() {
-    let lines = readfile::Lines::new("day15.txt");
-    part1(&lines);
-    part2(&lines);
+    #[test]
+    fn tiled_risk_wraps_around() {
+        let risks = tile(&Grid::parse_digits(&readfile::Lines::from("8")).unwrap(), 5);
+        assert_eq!(risks.row(0), &[8, 9, 1, 2, 3]);
+        assert_eq!(risks.get([5, 0]), None);
+    }
 }
@@ -1,19 +1,42 @@
+use crate::answer::Answer;
+use crate::error::ParseError;
+use crate::generate::Rng;
 use crate::readfile;
+use crate::solver::Solver;
 
-#[derive(Debug)]
-enum PacketType {
+#[derive(Debug, PartialEq)]
+pub enum PacketType {
     Literal(usize),
     Operator(Vec<Packet>),
 }
 
-#[derive(Debug)]
-struct Packet {
-    version: usize,
-    packet_type_id: usize,
-    packet_type: PacketType,
+/// A packet of the BITS transmission.
+#[derive(Debug, PartialEq)]
+pub struct Packet {
+    pub version: usize,
+    pub packet_type_id: usize,
+    pub packet_type: PacketType,
 }
 
 impl Packet {
+    /// Decodes the outermost packet of a hexadecimal transmission.
+    pub fn from_hex(hex: &str) -> Result<Packet, ParseError> {
+        decode(readfile::Line::from(hex))
+    }
+
+    /// The sum of the versions of this packet and all packets nested in it.
+    pub fn version_sum(&self) -> usize {
+        let mut packet_queue = vec![self];
+        let mut sum = 0;
+        while let Some(p) = packet_queue.pop() {
+            sum += p.version;
+            if let PacketType::Operator(subpackets) = &p.packet_type {
+                packet_queue.extend(subpackets);
+            }
+        }
+        return sum;
+    }
+
     pub fn value(&self) -> usize {
         match &self.packet_type {
             PacketType::Literal(v) => *v,
@@ -52,8 +75,8 @@ impl Packet {
     }
 }
 
-fn to_binary(c: char) -> &'static str {
-    match c {
+fn to_binary(c: char) -> Option<&'static str> {
+    let bits = match c {
         '0' => "0000",
         '1' => "0001",
         '2' => "0010",
@@ -70,109 +93,305 @@ fn to_binary(c: char) -> &'static str {
         'D' => "1101",
         'E' => "1110",
         'F' => "1111",
-        _ => "",
-    }
+        _ => return None,
+    };
+    return Some(bits);
 }
 
-fn hex_to_bits(hex_str: &str) -> Vec<char> {
+fn hex_to_bits(line: readfile::Line) -> Result<Vec<char>, ParseError> {
     let mut bits: Vec<char> = Vec::new();
-    for hex_char in hex_str.chars() {
-        bits.extend(to_binary(hex_char).chars());
+    for (i, hex_char) in line.text().chars().enumerate() {
+        let b = to_binary(hex_char).ok_or_else(|| {
+            line.error(i + 1, &format!("invalid hexadecimal digit '{}'", hex_char))
+        })?;
+        bits.extend(b.chars());
     }
-    return bits;
+    return Ok(bits);
 }
 
-fn bits_to_usize<'a, I>(iter: I) -> usize
+const TRUNCATED: &str = "transmission ends in the middle of a packet";
+
+fn bits_to_usize<'a, I>(iter: I, count: usize) -> Result<usize, &'static str>
 where
     I: Iterator<Item = &'a char>,
 {
-    return usize::from_str_radix(&String::from_iter(iter), 2).unwrap();
+    let bits = String::from_iter(iter.take(count));
+    if bits.len() < count {
+        return Err(TRUNCATED);
+    }
+    return Ok(usize::from_str_radix(&bits, 2).unwrap());
 }
 
-fn parse_literal<'a, I>(iter: &mut I) -> PacketType
+fn parse_literal<'a, I>(iter: &mut I) -> Result<PacketType, &'static str>
 where
     I: Iterator<Item = &'a char>,
 {
-    let mut value_bits: Vec<char> = Vec::new();
+    let mut value: usize = 0;
     let mut continue_bit = '1';
     while continue_bit == '1' {
-        continue_bit = *iter.next().unwrap();
-        value_bits.extend(iter.take(4));
+        continue_bit = *iter.next().ok_or(TRUNCATED)?;
+        value = value
+            .checked_mul(16)
+            .ok_or("literal value is too large")?
+            + bits_to_usize(&mut *iter, 4)?;
     }
-    let value = usize::from_str_radix(&String::from_iter(value_bits.iter()), 2).unwrap();
-    return PacketType::Literal(value);
+    return Ok(PacketType::Literal(value));
 }
 
 #[allow(clippy::needless_collect)]
-fn parse_operator<'a, I>(iter: &mut I) -> PacketType
+fn parse_operator<'a, I>(iter: &mut I) -> Result<PacketType, &'static str>
 where
     I: Iterator<Item = &'a char>,
 {
-    let length_type_id = *iter.next().unwrap();
+    let length_type_id = *iter.next().ok_or(TRUNCATED)?;
     let mut subpackets: Vec<Packet> = Vec::new();
     if length_type_id == '0' {
-        let total_subpacket_length = bits_to_usize(iter.take(15));
+        let total_subpacket_length = bits_to_usize(&mut *iter, 15)?;
         let subpacket_bits: Vec<&char> = iter.take(total_subpacket_length).collect();
+        if subpacket_bits.len() < total_subpacket_length {
+            return Err(TRUNCATED);
+        }
         let subpacket_iter = &mut subpacket_bits.into_iter();
-        while !subpacket_iter.is_empty() {
-            subpackets.push(parse_packet(subpacket_iter));
+        while subpacket_iter.len() > 0 {
+            subpackets.push(parse_packet(subpacket_iter)?);
         }
     } else {
-        let num_subpackets = bits_to_usize(iter.take(11));
+        let num_subpackets = bits_to_usize(&mut *iter, 11)?;
         for _ in 0..num_subpackets {
-            subpackets.push(parse_packet(iter));
+            subpackets.push(parse_packet(iter)?);
         }
     }
 
-    return PacketType::Operator(subpackets);
+    return Ok(PacketType::Operator(subpackets));
 }
 
-fn parse_packet<'a, I>(iter: &mut I) -> Packet
+fn parse_packet<'a, I>(iter: &mut I) -> Result<Packet, &'static str>
 where
     I: Iterator<Item = &'a char>,
 {
-    let version = bits_to_usize(iter.take(3));
-    let packet_type_id = bits_to_usize(iter.take(3));
+    let version = bits_to_usize(&mut *iter, 3)?;
+    let packet_type_id = bits_to_usize(&mut *iter, 3)?;
 
-    let packet_type: PacketType;
-    if packet_type_id == 4 {
-        packet_type = parse_literal(iter);
+    let packet_type = if packet_type_id == 4 {
+        parse_literal(iter)?
     } else {
-        packet_type = parse_operator(iter);
+        parse_operator(iter)?
+    };
+
+    match (packet_type_id, &packet_type) {
+        (0..=3, PacketType::Operator(subpackets)) if subpackets.is_empty() => {
+            return Err("operator packet without subpackets")
+        }
+        (5..=7, PacketType::Operator(subpackets)) if subpackets.len() != 2 => {
+            return Err("comparison packet needs exactly two subpackets")
+        }
+        _ => (),
     }
 
-    return Packet {
+    return Ok(Packet {
         version,
         packet_type_id,
         packet_type,
+    });
+}
+
+/// Decodes the outermost packet of `line`. Errors inside the transmission are reported at the
+/// end of the line, the bit stream does not track positions.
+fn decode(line: readfile::Line) -> Result<Packet, ParseError> {
+    let bits = hex_to_bits(line)?;
+    parse_packet(&mut bits.iter())
+        .map_err(|message| line.error(line.text().chars().count() + 1, message))
+}
+
+/// A random packet nested up to `depth` levels. Products are only used while the value stays
+/// small, so the values of the packets always fit.
+fn random_packet(rng: &mut Rng, depth: usize) -> Packet {
+    let version = rng.below(8);
+    if depth == 0 || rng.chance(1, 3) {
+        let digits = 1 + rng.below(4);
+        return Packet {
+            version,
+            packet_type_id: 4,
+            packet_type: PacketType::Literal(rng.below(1 << (4 * digits))),
+        };
+    }
+
+    let mut packet_type_id = *rng.choose(&[0, 1, 2, 3, 5, 6, 7]);
+    let count = if packet_type_id >= 5 { 2 } else { 1 + rng.below(3) };
+    let subpackets: Vec<Packet> = (0..count).map(|_| random_packet(rng, depth - 1)).collect();
+    if packet_type_id == 1 {
+        let product = subpackets.iter().try_fold(1usize, |p, s| p.checked_mul(s.value()));
+        if product.is_none_or(|p| p >= 1 << 40) {
+            packet_type_id = 0;
+        }
+    }
+    return Packet {
+        version,
+        packet_type_id,
+        packet_type: PacketType::Operator(subpackets),
     };
 }
 
-fn part1(lines: &readfile::Lines) {
-    let bits = hex_to_bits(lines.lines().next().unwrap());
-    let packet = parse_packet(&mut bits.iter());
+fn push_bits(bits: &mut String, value: usize, count: usize) {
+    bits.push_str(&format!("{:0width$b}", value, width = count));
+}
 
-    let mut packet_queue = vec![packet];
-    let mut sum = 0;
-    while !packet_queue.is_empty() {
-        let p = packet_queue.pop().unwrap();
-        sum += p.version;
-        if let PacketType::Operator(subpackets) = p.packet_type {
-            packet_queue.extend(subpackets);
+/// The bits of `packet`, the length type of operators is chosen at random.
+fn encode(rng: &mut Rng, packet: &Packet, bits: &mut String) {
+    push_bits(bits, packet.version, 3);
+    push_bits(bits, packet.packet_type_id, 3);
+    match &packet.packet_type {
+        PacketType::Literal(value) => {
+            let mut groups = vec![];
+            let mut v = *value;
+            loop {
+                groups.push(v & 0xf);
+                v >>= 4;
+                if v == 0 {
+                    break;
+                }
+            }
+            for (i, group) in groups.iter().rev().enumerate() {
+                push_bits(bits, (i + 1 < groups.len()) as usize, 1);
+                push_bits(bits, *group, 4);
+            }
+        }
+        PacketType::Operator(subpackets) => {
+            if rng.chance(1, 2) {
+                let mut nested = String::new();
+                for p in subpackets {
+                    encode(rng, p, &mut nested);
+                }
+                push_bits(bits, 0, 1);
+                push_bits(bits, nested.len(), 15);
+                bits.push_str(&nested);
+            } else {
+                push_bits(bits, 1, 1);
+                push_bits(bits, subpackets.len(), 11);
+                for p in subpackets {
+                    encode(rng, p, bits);
+                }
+            }
         }
     }
+}
 
-    println!("Part 1: {}", sum);
+/// The hexadecimal transmission of `packet`, padded with zeros.
+fn to_hex(rng: &mut Rng, packet: &Packet) -> String {
+    let mut bits = String::new();
+    encode(rng, packet, &mut bits);
+    while !bits.len().is_multiple_of(4) {
+        bits.push('0');
+    }
+    let digits = bits.as_bytes().chunks(4).map(|nibble| {
+        let nibble = std::str::from_utf8(nibble).unwrap();
+        format!("{:X}", usize::from_str_radix(nibble, 2).unwrap())
+    });
+    return digits.collect();
 }
 
-fn part2(lines: &readfile::Lines) {
-    let bits = hex_to_bits(lines.lines().next().unwrap());
-    let packet = parse_packet(&mut bits.iter());
-    println!("Part 2: {}", packet.value());
+pub struct Day16 {
+    packet: Packet,
+}
+
+impl Solver for Day16 {
+    fn parse(lines: &readfile::Lines) -> Result<Day16, ParseError> {
+        Ok(Day16 {
+            packet: decode(lines.first()?)?,
+        })
+    }
+
+    fn part1(&self) -> Answer {
+        self.packet.version_sum().into()
+    }
+
+    fn part2(&self) -> Answer {
+        self.packet.value().into()
+    }
+
+    /// A transmission of packets nested up to `size` (at most 8) levels deep.
+    fn generate(rng: &mut Rng, size: usize) -> Option<String> {
+        let packet = random_packet(rng, size.clamp(1, 8));
+        Some(to_hex(rng, &packet))
+    }
 }
 
-pub fn run() {
-    let lines = readfile::Lines::new("day16.txt");
-    part1(&lines);
-    part2(&lines);
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn version_sum(hex: &str) -> Answer {
+        Day16::parse(&readfile::Lines::from(hex)).unwrap().part1()
+    }
+
+    fn value(hex: &str) -> Answer {
+        Day16::parse(&readfile::Lines::from(hex)).unwrap().part2()
+    }
+
+    fn brute_force_version_sum(packet: &Packet) -> usize {
+        match &packet.packet_type {
+            PacketType::Literal(_) => packet.version,
+            PacketType::Operator(subpackets) => {
+                packet.version + subpackets.iter().map(brute_force_version_sum).sum::<usize>()
+            }
+        }
+    }
+
+    #[test]
+    fn encoded_packets_decode_to_the_same_packets() {
+        for seed in 0..100 {
+            let mut rng = Rng::new(seed);
+            let packet = random_packet(&mut rng, 1 + seed as usize % 6);
+            let hex = to_hex(&mut rng, &packet);
+            let day = Day16::parse(&readfile::Lines::from(hex.as_str())).unwrap();
+            assert_eq!(day.packet, packet, "seed {}", seed);
+            let expected = brute_force_version_sum(&packet);
+            assert_eq!(day.part1(), Answer::Number(expected as i64), "seed {}", seed);
+        }
+    }
+
+    #[test]
+    fn literal_packet() {
+        let packet = Packet::from_hex("D2FE28").unwrap();
+        assert_eq!(packet.version, 6);
+        assert!(matches!(packet.packet_type, PacketType::Literal(2021)));
+    }
+
+    #[test]
+    fn operator_packets() {
+        for hex in ["38006F45291200", "EE00D40C823060"] {
+            match Packet::from_hex(hex).unwrap().packet_type {
+                PacketType::Operator(subpackets) => assert!(subpackets.len() >= 2),
+                PacketType::Literal(_) => panic!("{} should be an operator packet", hex),
+            }
+        }
+    }
+
+    #[test]
+    fn version_sums() {
+        assert_eq!(version_sum("8A004A801A8002F478"), Answer::Number(16));
+        assert_eq!(version_sum("620080001611562C8802118E34"), Answer::Number(12));
+        assert_eq!(version_sum("C0015000016115A2E0802F182340"), Answer::Number(23));
+        assert_eq!(version_sum("A0016C880162017C3686B18A3D4780"), Answer::Number(31));
+    }
+
+    #[test]
+    fn values() {
+        assert_eq!(value("C200B40A82"), Answer::Number(3));
+        assert_eq!(value("04005AC33890"), Answer::Number(54));
+        assert_eq!(value("880086C3E88112"), Answer::Number(7));
+        assert_eq!(value("CE00C43D881120"), Answer::Number(9));
+        assert_eq!(value("D8005AC2A8F0"), Answer::Number(1));
+        assert_eq!(value("F600BC2D8F"), Answer::Number(0));
+        assert_eq!(value("9C005AC2F8F0"), Answer::Number(0));
+        assert_eq!(value("9C0141080250320F1802104A08"), Answer::Number(1));
+    }
+
+    #[test]
+    fn invalid_transmissions() {
+        let err = Packet::from_hex("D2FG28").unwrap_err();
+        assert_eq!(err.column, 4);
+        let err = Packet::from_hex("D2FE2").unwrap_err();
+        assert_eq!(err.message, "transmission ends in the middle of a packet");
+    }
 }
@@ -1,4 +1,8 @@
+use crate::answer::Answer;
+use crate::error::ParseError;
+use crate::generate::Rng;
 use crate::readfile;
+use crate::solver::Solver;
 use regex::Regex;
 
 struct TargetArea {
@@ -9,23 +13,24 @@ struct TargetArea {
 }
 
 impl TargetArea {
-  pub fn new(s: &str) -> TargetArea {
-    let re = Regex::new("target area: x=(-?\\d+)..(-?\\d+), y=(-?\\d+)..(-?\\d+)").unwrap();
-    let groups = re.captures(s).unwrap();
-    return TargetArea {
-      xmin: groups[1].parse().unwrap(),
-      xmax: groups[2].parse().unwrap(),
-      ymin: groups[3].parse().unwrap(),
-      ymax: groups[4].parse().unwrap(),
+  pub fn new(line: readfile::Line) -> Result<TargetArea, ParseError> {
+    let re = Regex::new("^target area: x=(-?\\d+)..(-?\\d+), y=(-?\\d+)..(-?\\d+)$").unwrap();
+    let groups = line.captures(&re)?;
+    let target = TargetArea {
+      xmin: line.parse_field(&groups[1])?,
+      xmax: line.parse_field(&groups[2])?,
+      ymin: line.parse_field(&groups[3])?,
+      ymax: line.parse_field(&groups[4])?,
     };
+    if target.xmin < 0 || target.xmin > target.xmax || target.ymin > target.ymax {
+      return Err(line.error(1, "expected a target area with 0 <= xmin <= xmax and ymin <= ymax"));
+    }
+    return Ok(target);
   }
 }
 
 #[derive(Copy, Clone)]
 struct SimulationResult {
-  vx_0: i32,
-  vy_0: i32,
-  steps: i32,
   max_y: i32,
 }
 
@@ -34,11 +39,9 @@ fn simulate(target: &TargetArea, vx_0: i32, vy_0: i32) -> Option<SimulationResul
   let mut vy = vy_0;
   let mut x = 0;
   let mut y = 0;
-  let mut step = 0;
   let mut max_y = 0;
 
   loop {
-    step += 1;
     x += vx;
     y += vy;
 
@@ -48,12 +51,7 @@ fn simulate(target: &TargetArea, vx_0: i32, vy_0: i32) -> Option<SimulationResul
 
     if x >= target.xmin && x <= target.xmax && y >= target.ymin && y <= target.ymax {
       // we are in the target area
-      return Some(SimulationResult {
-        vx_0,
-        vy_0,
-        steps: step,
-        max_y,
-      });
+      return Some(SimulationResult { max_y });
     } else if x > target.xmax || y < target.ymin {
       return None;
     }
@@ -84,29 +82,102 @@ fn fuzz(target: &TargetArea, min_x_guess: i32) -> (SimulationResult, usize) {
   return (best_simulation.unwrap(), valid_simulations);
 }
 
-fn part1(lines: &readfile::Lines) {
-  let target = TargetArea::new(lines.lines().next().unwrap());
+pub struct Day17 {
+  target: TargetArea,
+}
 
-  let fuzz_result = fuzz(&target, 0);
-  let SimulationResult {
-    max_y,
-    steps,
-    vx_0,
-    vy_0,
-  } = fuzz_result.0;
+impl Solver for Day17 {
+  fn parse(lines: &readfile::Lines) -> Result<Day17, ParseError> {
+    Ok(Day17 {
+      target: TargetArea::new(lines.first()?)?,
+    })
+  }
 
-  println!("{},{},{}", steps, vx_0, vy_0);
-  println!("Part 1: {}", max_y);
-}
+  fn part1(&self) -> Answer {
+    let fuzz_result = fuzz(&self.target, 0);
+    fuzz_result.0.max_y.into()
+  }
 
-fn part2(lines: &readfile::Lines) {
-  let target = TargetArea::new(lines.lines().next().unwrap());
-  let fuzz_result = fuzz(&target, 0);
-  println!("Part 2: {}", fuzz_result.1);
+  fn part2(&self) -> Answer {
+    let fuzz_result = fuzz(&self.target, 0);
+    fuzz_result.1.into()
+  }
+
+  /// A target area about `size` times 10 away. Like in the puzzle it is below the start and
+  /// some probe stops falling forward inside it.
+  fn generate(rng: &mut Rng, size: usize) -> Option<String> {
+    let distance = 10 * size.clamp(1, 40) as isize;
+    let xmin = rng.range(distance..=2 * distance);
+    let mut xmax = xmin + rng.range(5..=15);
+    // the probe stops at a triangular number
+    let stop = (1..).map(|v| v * (v + 1) / 2).find(|x| *x >= xmin).unwrap();
+    xmax = xmax.max(stop);
+    let ymin = -rng.range(distance / 2..=distance);
+    let ymax = (ymin + rng.range(3..=10)).min(-1);
+    Some(format!("target area: x={}..{}, y={}..{}", xmin, xmax, ymin, ymax))
+  }
 }
 
-pub fn run() {
-  let lines = readfile::Lines::new("day17.txt");
-  part1(&lines);
-  part2(&lines);
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::generate;
+
+  const EXAMPLE: &str = "target area: x=20..30, y=-10..-5";
+
+  #[test]
+  fn example() {
+    let day = Day17::parse(&readfile::Lines::from(EXAMPLE)).unwrap();
+    assert_eq!(day.part1(), Answer::Number(45));
+    assert_eq!(day.part2(), Answer::Number(112));
+  }
+
+  /// Counts the velocities for which x and y are in the target area after the same number
+  /// of steps, looking at both directions on their own.
+  fn brute_force(target: &TargetArea) -> (i32, usize) {
+    let max_steps = 2 * target.ymin.abs() + 2;
+    let hits = |v0: i32, drag: fn(i32) -> i32, range: (i32, i32)| -> Vec<bool> {
+      let (mut position, mut v) = (0, v0);
+      (0..=max_steps)
+        .map(|_| {
+          position += v;
+          v = drag(v);
+          range.0 <= position && position <= range.1
+        })
+        .collect()
+    };
+    let (mut highest, mut count) = (0, 0);
+    for vy in target.ymin..=-target.ymin {
+      let y_hits = hits(vy, |v| v - 1, (target.ymin, target.ymax));
+      for vx in 1..=target.xmax {
+        let x_hits = hits(vx, |v| (v - 1).max(0), (target.xmin, target.xmax));
+        if x_hits.iter().zip(&y_hits).any(|(x, y)| *x && *y) {
+          highest = highest.max(vy * (vy + 1) / 2);
+          count += 1;
+        }
+      }
+    }
+    return (highest, count);
+  }
+
+  #[test]
+  fn matches_brute_force() {
+    generate::check_answers::<Day17>(
+      0..10,
+      |seed| 1 + seed as usize,
+      |day, _| {
+        let (highest, count) = brute_force(&day.target);
+        [Some(highest.into()), Some(count.into())]
+      },
+    );
+  }
+
+  #[test]
+  fn single_shots() {
+    let target = TargetArea::new(readfile::Line::from(EXAMPLE)).unwrap();
+    assert!(simulate(&target, 7, 2).is_some());
+    assert!(simulate(&target, 6, 3).is_some());
+    assert!(simulate(&target, 9, 0).is_some());
+    assert!(simulate(&target, 17, -4).is_none());
+  }
 }
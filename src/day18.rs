@@ -1,4 +1,8 @@
+use crate::answer::Answer;
+use crate::error::ParseError;
+use crate::generate::Rng;
 use crate::readfile;
+use crate::solver::Solver;
 use indextree::{Arena, NodeId};
 use std::cell::RefCell;
 
@@ -42,13 +46,21 @@ impl std::fmt::Display for SnailfishNumber {
     }
 }
 
-struct Tree {
+/// A snailfish number, stored as a binary tree. Numbers are kept reduced.
+pub struct Tree {
     arena: Arena<RefCell<SnailfishNumber>>,
     root_node: Option<NodeId>,
 }
 
 impl Tree {
-    pub fn new(s: &str) -> Tree {
+    /// Parses and reduces a snailfish number like `[[1,2],3]`.
+    pub fn new(s: &str) -> Result<Tree, ParseError> {
+        check_syntax(readfile::Line::from(s))?;
+        Ok(Tree::from_checked(s))
+    }
+
+    /// A tree of a string that passed `check_syntax`.
+    fn from_checked(s: &str) -> Tree {
         let mut tree = Tree {
             arena: Arena::new(),
             root_node: None,
@@ -60,17 +72,14 @@ impl Tree {
     }
 
     fn parse_number(&mut self, s: &str) -> NodeId {
-        if s.chars().all(|c| c.is_digit(10)) {
+        if s.chars().all(|c| c.is_ascii_digit()) {
             // number literal
             return self
                 .arena
                 .new_node(RefCell::new(SnailfishNumber::new_value(s.parse().unwrap())));
         } else {
-            // pair
+            // pair, the brackets were checked by `check_syntax`
             let chars: Vec<char> = s.chars().collect();
-            if chars[0] != '[' || *chars.last().unwrap() != ']' {
-                panic!("Invalid snailfish number: {}", s);
-            }
             let mut level = 0;
             let mut comma_position = 0;
             for (i, c) in chars.iter().enumerate() {
@@ -81,10 +90,8 @@ impl Tree {
                     ']' => {
                         level -= 1;
                     }
-                    ',' => {
-                        if level == 1 {
-                            comma_position = i;
-                        }
+                    ',' if level == 1 => {
+                        comma_position = i;
                     }
                     _ => {}
                 }
@@ -162,7 +169,15 @@ impl Tree {
         }
     }
 
-    pub fn add(&mut self, other: &str) {
+    /// Adds the snailfish number `other` and reduces the sum.
+    pub fn add(&mut self, other: &str) -> Result<(), ParseError> {
+        check_syntax(readfile::Line::from(other))?;
+        self.add_checked(other);
+        Ok(())
+    }
+
+    /// Adds a string that passed `check_syntax`.
+    fn add_checked(&mut self, other: &str) {
         let node1 = self.root_node.unwrap();
         let node2 = self.parse_number(other);
 
@@ -300,6 +315,11 @@ impl Tree {
         }
     }
 
+    /// The magnitude of the whole number.
+    pub fn magnitude(&self) -> usize {
+        self.get_magnitude(self.root_node.unwrap())
+    }
+
     pub fn reduce(&mut self) {
         self.update_index();
         while self.reduce_dfs_explode(self.root_node.unwrap(), 0) || self.split() {
@@ -314,37 +334,261 @@ impl std::fmt::Display for Tree {
     }
 }
 
-fn part1(lines: &readfile::Lines) {
-    let mut lines_iter = lines.lines();
-    let mut tree = Tree::new(lines_iter.next().unwrap());
-    tree.update_index();
-    for l in lines_iter {
-        tree.add(l);
+/// Checks that `line` is a snailfish number, the parsing of `Tree` relies on a valid syntax.
+fn check_syntax(line: readfile::Line) -> Result<(), ParseError> {
+    let chars: Vec<char> = line.text().chars().collect();
+    let end = check_element(line, &chars, 0)?;
+    if end != chars.len() {
+        return Err(line.error(end + 1, "unexpected characters after the number"));
     }
-    println!("{}", tree.get_magnitude(tree.root_node.unwrap()));
+    return Ok(());
 }
 
-fn part2(lines: &readfile::Lines) {
-    let line_vec: Vec<&str> = lines.lines().collect();
-    let mut max = 0;
-    for ai in 0..line_vec.len() {
-        for bi in 0..line_vec.len() {
-            if ai == bi {
-                continue;
-            }
-            let mut t = Tree::new(line_vec[ai]);
-            t.add(line_vec[bi]);
-            let mag = t.get_magnitude(t.root_node.unwrap());
-            if mag > max {
-                max = mag;
+/// Checks a regular number or a pair starting at `index`, returning the index after it.
+fn check_element(line: readfile::Line, chars: &[char], index: usize) -> Result<usize, ParseError> {
+    let expect = |index: usize, c: char| {
+        if chars.get(index) == Some(&c) {
+            Ok(index + 1)
+        } else {
+            Err(line.error(index + 1, &format!("expected '{}'", c)))
+        }
+    };
+
+    match chars.get(index) {
+        Some('[') => {
+            let index = check_element(line, chars, index + 1)?;
+            let index = expect(index, ',')?;
+            let index = check_element(line, chars, index)?;
+            expect(index, ']')
+        }
+        Some(c) if c.is_ascii_digit() => {
+            let digits = chars[index..].iter().take_while(|c| c.is_ascii_digit()).count();
+            Ok(index + digits)
+        }
+        _ => Err(line.error(index + 1, "expected a number or a pair")),
+    }
+}
+
+/// A reduced snailfish number with pairs nested at most `depth` levels below.
+fn random_number(rng: &mut Rng, depth: usize) -> String {
+    if depth == 0 || rng.chance(1, 4) {
+        return rng.below(10).to_string();
+    }
+    let left = random_number(rng, depth - 1);
+    let right = random_number(rng, depth - 1);
+    format!("[{},{}]", left, right)
+}
+
+pub struct Day18 {
+    numbers: Vec<String>,
+}
+
+impl Solver for Day18 {
+    fn parse(lines: &readfile::Lines) -> Result<Day18, ParseError> {
+        let mut numbers = Vec::new();
+        for l in lines.numbered() {
+            check_syntax(l)?;
+            numbers.push(String::from(l.text()));
+        }
+        if numbers.is_empty() {
+            return Err(lines.error_at_end("expected at least one snailfish number"));
+        }
+        Ok(Day18 { numbers })
+    }
+
+    fn part1(&self) -> Answer {
+        let mut lines_iter = self.numbers.iter();
+        let mut tree = Tree::from_checked(lines_iter.next().unwrap());
+        tree.update_index();
+        for l in lines_iter {
+            tree.add_checked(l);
+        }
+        tree.magnitude().into()
+    }
+
+    fn part2(&self) -> Answer {
+        let line_vec = &self.numbers;
+        let mut max = 0;
+        for ai in 0..line_vec.len() {
+            for bi in 0..line_vec.len() {
+                if ai == bi {
+                    continue;
+                }
+                let mut t = Tree::from_checked(&line_vec[ai]);
+                t.add_checked(&line_vec[bi]);
+                let mag = t.magnitude();
+                if mag > max {
+                    max = mag;
+                }
             }
         }
+        max.into()
+    }
+
+    /// `size` snailfish numbers.
+    fn generate(rng: &mut Rng, size: usize) -> Option<String> {
+        let numbers: Vec<String> = (0..size.max(2))
+            .map(|_| {
+                let left = random_number(rng, 3);
+                let right = random_number(rng, 3);
+                format!("[{},{}]", left, right)
+            })
+            .collect();
+        Some(numbers.join("\n"))
     }
-    println!("{}", max);
 }
 
-pub fn run() {
-    let lines = readfile::Lines::new("day18.txt");
-    part1(&lines);
-    part2(&lines);
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generate;
+
+    const HOMEWORK: &str = "\
+[[[0,[5,8]],[[1,7],[9,6]]],[[4,[1,2]],[[1,4],2]]]
+[[[5,[2,8]],4],[5,[[9,9],0]]]
+[6,[[[6,2],[5,6]],[[7,6],[4,7]]]]
+[[[6,[0,7]],[0,9]],[4,[9,[9,0]]]]
+[[[7,[6,4]],[3,[1,3]]],[[[5,5],1],9]]
+[[6,[[7,3],[3,2]]],[[[3,8],[5,7]],4]]
+[[[[5,4],[7,7]],8],[[8,3],8]]
+[[9,3],[[9,9],[6,[4,9]]]]
+[[2,[[7,7],7]],[[5,8],[[9,3],[0,2]]]]
+[[[[5,2],5],[8,[3,7]]],[[5,[7,5]],[4,4]]]";
+
+    fn reduced(s: &str) -> String {
+        format!("{}", Tree::new(s).unwrap())
+    }
+
+    fn magnitude(s: &str) -> usize {
+        let tree = Tree::new(s).unwrap();
+        tree.magnitude()
+    }
+
+    #[test]
+    fn explode() {
+        assert_eq!(reduced("[[[[[9,8],1],2],3],4]"), "[[[[0,9],2],3],4]");
+        assert_eq!(reduced("[7,[6,[5,[4,[3,2]]]]]"), "[7,[6,[5,[7,0]]]]");
+        assert_eq!(reduced("[[6,[5,[4,[3,2]]]],1]"), "[[6,[5,[7,0]]],3]");
+        assert_eq!(
+            reduced("[[3,[2,[1,[7,3]]]],[6,[5,[4,[3,2]]]]]"),
+            "[[3,[2,[8,0]]],[9,[5,[7,0]]]]"
+        );
+    }
+
+    #[test]
+    fn add_with_explode_and_split() {
+        let mut tree = Tree::new("[[[[4,3],4],4],[7,[[8,4],9]]]").unwrap();
+        tree.add("[1,1]").unwrap();
+        assert_eq!(format!("{}", tree), "[[[[0,7],4],[[7,8],[6,0]]],[8,1]]");
+    }
+
+    #[test]
+    fn sum_of_list() {
+        let mut tree = Tree::new("[1,1]").unwrap();
+        for n in ["[2,2]", "[3,3]", "[4,4]", "[5,5]", "[6,6]"] {
+            tree.add(n).unwrap();
+        }
+        assert_eq!(format!("{}", tree), "[[[[5,0],[7,4]],[5,5]],[6,6]]");
+    }
+
+    #[test]
+    fn magnitudes() {
+        assert_eq!(magnitude("[[1,2],[[3,4],5]]"), 143);
+        assert_eq!(magnitude("[[[[0,7],4],[[7,8],[6,0]]],[8,1]]"), 1384);
+        assert_eq!(
+            magnitude("[[[[8,7],[7,7]],[[8,6],[7,7]]],[[[0,7],[6,6]],[8,7]]]"),
+            3488
+        );
+    }
+
+    #[test]
+    fn example() {
+        let day = Day18::parse(&readfile::Lines::from(HOMEWORK)).unwrap();
+        assert_eq!(day.part1(), Answer::Number(4140));
+        assert_eq!(day.part2(), Answer::Number(3993));
+    }
+
+    /// The regular numbers of a snailfish number with the number of pairs around them.
+    fn flatten(s: &str) -> Vec<(usize, usize)> {
+        let mut depth = 0;
+        let mut values = vec![];
+        for c in s.chars() {
+            match c {
+                '[' => depth += 1,
+                ']' => depth -= 1,
+                ',' => {}
+                _ => values.push((c.to_digit(10).unwrap() as usize, depth)),
+            }
+        }
+        values
+    }
+
+    fn brute_force_add(a: &[(usize, usize)], b: &[(usize, usize)]) -> Vec<(usize, usize)> {
+        let mut sum: Vec<(usize, usize)> = a.iter().chain(b).map(|&(v, d)| (v, d + 1)).collect();
+        loop {
+            if let Some(i) = sum.iter().position(|&(_, d)| d > 4) {
+                let (left, right) = (sum[i].0, sum[i + 1].0);
+                if i > 0 {
+                    sum[i - 1].0 += left;
+                }
+                if i + 2 < sum.len() {
+                    sum[i + 2].0 += right;
+                }
+                sum.splice(i..i + 2, [(0, 4)]);
+            } else if let Some(i) = sum.iter().position(|&(v, _)| v >= 10) {
+                let (v, d) = sum[i];
+                sum.splice(i..=i, [(v / 2, d + 1), (v.div_ceil(2), d + 1)]);
+            } else {
+                return sum;
+            }
+        }
+    }
+
+    fn brute_force_magnitude(number: &[(usize, usize)]) -> usize {
+        let mut number = number.to_vec();
+        while number.len() > 1 {
+            // the leftmost of the deepest regular numbers is the left half of a pair
+            let deepest = number.iter().map(|&(_, d)| d).max().unwrap();
+            let i = number.iter().position(|&(_, d)| d == deepest).unwrap();
+            let magnitude = 3 * number[i].0 + 2 * number[i + 1].0;
+            number.splice(i..i + 2, [(magnitude, deepest - 1)]);
+        }
+        number[0].0
+    }
+
+    #[test]
+    fn matches_brute_force() {
+        generate::check_answers::<Day18>(
+            0..30,
+            |seed| 2 + seed as usize % 6,
+            |day, _| {
+                let numbers: Vec<_> = day.numbers.iter().map(|n| flatten(n)).collect();
+
+                let sum = numbers[1..]
+                    .iter()
+                    .fold(numbers[0].clone(), |sum, n| brute_force_add(&sum, n));
+                let expected = brute_force_magnitude(&sum);
+
+                let largest = (0..numbers.len())
+                    .flat_map(|a| (0..numbers.len()).map(move |b| (a, b)))
+                    .filter(|(a, b)| a != b)
+                    .map(|(a, b)| brute_force_magnitude(&brute_force_add(&numbers[a], &numbers[b])))
+                    .max()
+                    .unwrap();
+                [Some(expected.into()), Some(largest.into())]
+            },
+        );
+    }
+
+    #[test]
+    fn invalid_syntax() {
+        for (number, column) in [("[1,2", 5), ("[1;2]", 3), ("[[1,2],3]]", 10), ("", 1)] {
+            let err = check_syntax(readfile::Line::from(number)).unwrap_err();
+            assert_eq!(err.column, column, "{}", number);
+        }
+        assert_eq!(Tree::new("[1,[2]]").err().unwrap().column, 6);
+        let mut tree = Tree::new("[1,2]").unwrap();
+        assert_eq!(tree.add("[3,").err().unwrap().column, 4);
+        assert_eq!(format!("{}", tree), "[1,2]");
+    }
 }
@@ -0,0 +1,499 @@
+use crate::answer::Answer;
+use crate::error::ParseError;
+use crate::generate::Rng;
+use crate::point3::{Point3, Rotation};
+use crate::readfile;
+use crate::solver::Solver;
+use std::collections::{HashMap, HashSet};
+
+/// Two scanners are aligned once this many beacons overlap.
+const MIN_OVERLAP: usize = 12;
+
+/// Scanners see the beacons at most this far away along each axis.
+const RANGE: isize = 1000;
+
+/// The beacons of one scanner, relative to it and in its orientation.
+struct Scanner {
+    beacons: Vec<Point3>,
+    /// The squared distances between all pairs of its beacons, which do not depend on the
+    /// position or orientation and rule out most scanners that cannot overlap.
+    fingerprint: HashMap<isize, usize>,
+}
+
+impl Scanner {
+    fn new(beacons: Vec<Point3>) -> Scanner {
+        let mut fingerprint = HashMap::new();
+        for (i, a) in beacons.iter().enumerate() {
+            for b in &beacons[i + 1..] {
+                *fingerprint.entry(a.squared_distance(b)).or_insert(0) += 1;
+            }
+        }
+        Scanner {
+            beacons,
+            fingerprint,
+        }
+    }
+
+    /// Whether enough distances are shared to overlap by `MIN_OVERLAP` beacons.
+    fn may_overlap(&self, other: &Scanner) -> bool {
+        let shared: usize = self
+            .fingerprint
+            .iter()
+            .map(|(d, count)| (*count).min(*other.fingerprint.get(d).unwrap_or(&0)))
+            .sum();
+        shared >= MIN_OVERLAP * (MIN_OVERLAP - 1) / 2
+    }
+}
+
+/// A scanner placed relative to scanner 0.
+struct Placement {
+    position: Point3,
+    /// The beacons of the scanner relative to scanner 0.
+    beacons: Vec<Point3>,
+}
+
+/// Finds where `scanner` is relative to the `known` beacons of a placed scanner: the rotation
+/// and offset for which at least `MIN_OVERLAP` beacons coincide.
+fn place(known: &[Point3], scanner: &Scanner, rotations: &[Rotation]) -> Option<Placement> {
+    for rotation in rotations {
+        let rotated: Vec<Point3> = scanner.beacons.iter().map(|b| rotation.apply(*b)).collect();
+        let mut offsets: HashMap<Point3, usize> = HashMap::new();
+        for a in known {
+            for b in &rotated {
+                let count = offsets.entry(*a - *b).or_insert(0);
+                *count += 1;
+                if *count >= MIN_OVERLAP {
+                    let position = *a - *b;
+                    return Some(Placement {
+                        position,
+                        beacons: rotated.iter().map(|b| *b + position).collect(),
+                    });
+                }
+            }
+        }
+    }
+    return None;
+}
+
+/// Places all scanners relative to scanner 0, starting from it and placing the others next to
+/// scanners already placed. `None` if some scanner does not overlap the others.
+fn place_all(scanners: &[Scanner]) -> Option<Vec<Placement>> {
+    let rotations = Rotation::all();
+    let mut placements: Vec<Option<Placement>> = scanners.iter().map(|_| None).collect();
+    placements[0] = Some(Placement {
+        position: Point3::ORIGIN,
+        beacons: scanners[0].beacons.clone(),
+    });
+    let mut queue = vec![0];
+    while let Some(i) = queue.pop() {
+        for j in 0..scanners.len() {
+            if placements[j].is_some() || !scanners[i].may_overlap(&scanners[j]) {
+                continue;
+            }
+            let known = &placements[i].as_ref().unwrap().beacons;
+            if let Some(placement) = place(known, &scanners[j], &rotations) {
+                placements[j] = Some(placement);
+                queue.push(j);
+            }
+        }
+    }
+    return placements.into_iter().collect();
+}
+
+fn parse_beacon(line: readfile::Line) -> Result<Point3, ParseError> {
+    let values = line
+        .text()
+        .split(',')
+        .map(|v| line.parse_field::<isize>(v))
+        .collect::<Result<Vec<isize>, _>>()?;
+    match values[..] {
+        [x, y, z] => Ok(Point3::new(x, y, z)),
+        _ => Err(line.error(1, "expected a beacon like 404,-588,-901")),
+    }
+}
+
+/// The scanners are placed while parsing, both parts need all of them. `None` if some scanner
+/// does not overlap the others.
+pub struct Day19 {
+    placements: Option<Vec<Placement>>,
+}
+
+impl Solver for Day19 {
+    fn parse(lines: &readfile::Lines) -> Result<Day19, ParseError> {
+        let mut scanners = vec![];
+        let mut beacons: Option<Vec<Point3>> = None;
+        for line in lines.numbered() {
+            if line.is_empty() {
+                match beacons.take() {
+                    Some(b) => scanners.push(Scanner::new(b)),
+                    None => return Err(line.error(1, "expected a scanner header")),
+                }
+            } else if line.text().starts_with("---") {
+                if beacons.is_some() {
+                    return Err(line.error(1, "expected an empty line between scanners"));
+                }
+                let expected = format!("--- scanner {} ---", scanners.len());
+                if line.text() != expected {
+                    return Err(line.error(1, &format!("expected '{}'", expected)));
+                }
+                beacons = Some(vec![]);
+            } else {
+                match &mut beacons {
+                    Some(b) => b.push(parse_beacon(line)?),
+                    None => return Err(line.error(1, "expected a scanner header")),
+                }
+            }
+        }
+        if let Some(b) = beacons {
+            scanners.push(Scanner::new(b));
+        }
+        if scanners.is_empty() {
+            return Err(lines.error_at_end("expected at least one scanner"));
+        }
+        Ok(Day19 {
+            placements: place_all(&scanners),
+        })
+    }
+
+    fn part1(&self) -> Answer {
+        let Some(placements) = &self.placements else {
+            return Answer::Unsolved;
+        };
+        let beacons: HashSet<&Point3> = placements.iter().flat_map(|p| &p.beacons).collect();
+        beacons.len().into()
+    }
+
+    fn part2(&self) -> Answer {
+        let Some(placements) = &self.placements else {
+            return Answer::Unsolved;
+        };
+        let mut max = 0;
+        for a in placements {
+            for b in placements {
+                max = max.max(a.position.manhattan_distance(&b.position));
+            }
+        }
+        max.into()
+    }
+
+    /// The reports of `size` scanners, see `random_region`.
+    fn generate(rng: &mut Rng, size: usize) -> Option<String> {
+        Some(random_region(rng, size).report(rng))
+    }
+}
+
+/// Beacons and the scanners around them, for generated inputs.
+struct Region {
+    beacons: HashSet<Point3>,
+    scanners: Vec<Point3>,
+}
+
+/// `size` scanners, each of them within range of an earlier one and sharing `MIN_OVERLAP`
+/// beacons with it, plus a few beacons that only one scanner sees.
+fn random_region(rng: &mut Rng, size: usize) -> Region {
+    let mut region = Region {
+        beacons: HashSet::new(),
+        scanners: vec![Point3::ORIGIN],
+    };
+    let random_point = |rng: &mut Rng, min: Point3, max: Point3| {
+        Point3::new(
+            rng.range(min.x..=max.x),
+            rng.range(min.y..=max.y),
+            rng.range(min.z..=max.z),
+        )
+    };
+    let offset = Point3::new(RANGE, RANGE, RANGE);
+    for i in 0..size.max(2) {
+        let position = region.scanners[i];
+        for _ in 0..8 {
+            let beacon = random_point(rng, position - offset, position + offset);
+            region.beacons.insert(beacon);
+        }
+        if i + 1 == size.max(2) {
+            break;
+        }
+
+        let near = *rng.choose(&region.scanners);
+        // far enough apart that scanners see few beacons of the others, like in the puzzle
+        let mut next = near;
+        for axis in 0..3 {
+            let step = rng.range(900..=1400);
+            next[axis] += if rng.chance(1, 2) { step } else { -step };
+        }
+        // the shared beacons lie where both scanners see
+        let mut min = Point3::ORIGIN;
+        let mut max = Point3::ORIGIN;
+        for axis in 0..3 {
+            min[axis] = near[axis].max(next[axis]) - RANGE;
+            max[axis] = near[axis].min(next[axis]) + RANGE;
+        }
+        for _ in 0..MIN_OVERLAP {
+            region.beacons.insert(random_point(rng, min, max));
+        }
+        region.scanners.push(next);
+    }
+    return region;
+}
+
+impl Region {
+    /// What each scanner sees, in a random orientation.
+    fn report(&self, rng: &mut Rng) -> String {
+        let rotations = Rotation::all();
+        let mut beacons: Vec<&Point3> = self.beacons.iter().collect();
+        beacons.sort();
+        let mut reports = vec![];
+        for (i, scanner) in self.scanners.iter().enumerate() {
+            let rotation = if i == 0 {
+                Rotation::IDENTITY
+            } else {
+                *rng.choose(&rotations)
+            };
+            let inverse = rotation.inverse();
+            let mut report = format!("--- scanner {} ---", i);
+            for beacon in &beacons {
+                let relative = **beacon - *scanner;
+                if (0..3).all(|axis| relative[axis].abs() <= RANGE) {
+                    report.push('\n');
+                    report.push_str(&inverse.apply(relative).to_string());
+                }
+            }
+            reports.push(report);
+        }
+        reports.join("\n\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "\
+--- scanner 0 ---
+404,-588,-901
+528,-643,409
+-838,591,734
+390,-675,-793
+-537,-823,-458
+-485,-357,347
+-345,-311,381
+-661,-816,-575
+-876,649,763
+-618,-824,-621
+553,345,-567
+474,580,667
+-447,-329,318
+-584,868,-557
+544,-627,-890
+564,392,-477
+455,729,728
+-892,524,684
+-689,845,-530
+423,-701,434
+7,-33,-71
+630,319,-379
+443,580,662
+-789,900,-551
+459,-707,401
+
+--- scanner 1 ---
+686,422,578
+605,423,415
+515,917,-361
+-336,658,858
+95,138,22
+-476,619,847
+-340,-569,-846
+567,-361,727
+-460,603,-452
+669,-402,600
+729,430,532
+-500,-761,534
+-322,571,750
+-466,-666,-811
+-429,-592,574
+-355,545,-477
+703,-491,-529
+-328,-685,520
+413,935,-424
+-391,539,-444
+586,-435,557
+-364,-763,-893
+807,-499,-711
+755,-354,-619
+553,889,-390
+
+--- scanner 2 ---
+649,640,665
+682,-795,504
+-784,533,-524
+-644,584,-595
+-588,-843,648
+-30,6,44
+-674,560,763
+500,723,-460
+609,671,-379
+-555,-800,653
+-675,-892,-343
+697,-426,-610
+578,704,681
+493,664,-388
+-671,-858,530
+-667,343,800
+571,-461,-707
+-138,-166,112
+-889,563,-600
+646,-828,498
+640,759,510
+-630,509,768
+-681,-892,-333
+673,-379,-804
+-742,-814,-386
+577,-820,562
+
+--- scanner 3 ---
+-589,542,597
+605,-692,669
+-500,565,-823
+-660,373,557
+-458,-679,-417
+-488,449,543
+-626,468,-788
+338,-750,-386
+528,-832,-391
+562,-778,733
+-938,-730,414
+543,643,-506
+-524,371,-870
+407,773,750
+-104,29,83
+378,-903,-323
+-778,-728,485
+426,699,580
+-438,-605,-362
+-469,-447,-387
+509,732,623
+647,635,-688
+-868,-804,481
+614,-800,639
+595,780,-596
+
+--- scanner 4 ---
+727,592,562
+-293,-554,779
+441,611,-461
+-714,465,-776
+-743,427,-804
+-660,-479,-426
+832,-632,460
+927,-485,-438
+408,393,-506
+466,436,-512
+110,16,151
+-258,-428,682
+-393,719,612
+-211,-452,876
+808,-476,-593
+-575,615,604
+-485,667,467
+-680,325,-822
+-627,-443,-432
+872,-547,-609
+833,512,582
+807,604,487
+839,-516,451
+891,-625,532
+-652,-548,-490
+30,-46,-14";
+
+    #[test]
+    fn example() {
+        let day = Day19::parse(&readfile::Lines::from(EXAMPLE)).unwrap();
+        assert_eq!(day.part1(), Answer::Number(79));
+        assert_eq!(day.part2(), Answer::Number(3621));
+        let positions: Vec<Point3> = day.placements.unwrap().iter().map(|p| p.position).collect();
+        assert_eq!(
+            positions,
+            vec![
+                Point3::ORIGIN,
+                Point3::new(68, -1246, -43),
+                Point3::new(1105, -1205, 1229),
+                Point3::new(-92, -2380, -20),
+                Point3::new(-20, -1133, 1061),
+            ]
+        );
+    }
+
+    #[test]
+    fn generated_regions() {
+        for seed in 0..6 {
+            let mut rng = Rng::new(seed);
+            let region = random_region(&mut rng, 2 + seed as usize % 4);
+            let input = region.report(&mut rng);
+            let day = Day19::parse(&readfile::Lines::from(input.as_str())).unwrap();
+
+            let expected = region.beacons.len();
+            assert_eq!(
+                day.part1(),
+                Answer::Number(expected as i64),
+                "seed {}",
+                seed
+            );
+            let expected = region
+                .scanners
+                .iter()
+                .flat_map(|a| region.scanners.iter().map(|b| a.manhattan_distance(b)))
+                .max()
+                .unwrap();
+            assert_eq!(
+                day.part2(),
+                Answer::Number(expected as i64),
+                "seed {}",
+                seed
+            );
+        }
+    }
+
+    #[test]
+    fn placement_undoes_the_rotation() {
+        let beacons: Vec<Point3> = (0..12)
+            .map(|i| Point3::new(i * 7 - 40, i * i - 30, 50 - i * 3))
+            .collect();
+        let rotations = Rotation::all();
+        let position = Point3::new(68, -1246, -43);
+        let rotation = rotations[17];
+        let seen: Vec<Point3> = beacons
+            .iter()
+            .map(|b| rotation.inverse().apply(*b - position))
+            .collect();
+        let placement = place(&beacons, &Scanner::new(seen), &rotations).unwrap();
+        assert_eq!(placement.position, position);
+        assert_eq!(placement.beacons, beacons);
+    }
+
+    #[test]
+    fn disconnected_scanners() {
+        let mut rng = Rng::new(1);
+        let mut input = random_region(&mut rng, 2).report(&mut rng);
+        // a scanner that sees a single beacon cannot share 12 with the others
+        input.push_str("\n\n--- scanner 2 ---\n1,2,3");
+        let day = Day19::parse(&readfile::Lines::from(input.as_str())).unwrap();
+        assert_eq!(day.part1(), Answer::Unsolved);
+        assert_eq!(day.part2(), Answer::Unsolved);
+    }
+
+    #[test]
+    fn invalid_reports() {
+        let input = "--- scanner 0 ---\n1,2,3\n\n--- scanner 2 ---\n4,5,6";
+        let err = Day19::parse(&readfile::Lines::from(input)).err().unwrap();
+        assert_eq!(
+            (err.line, err.message.as_str()),
+            (4, "expected '--- scanner 1 ---'")
+        );
+        let err = Day19::parse(&readfile::Lines::from("--- scanner 0 ---\n1,2"))
+            .err()
+            .unwrap();
+        assert_eq!((err.line, err.column), (2, 1));
+        let err = Day19::parse(&readfile::Lines::from("1,2,3")).err().unwrap();
+        assert_eq!(err.message, "expected a scanner header");
+    }
+}
@@ -1,59 +1,157 @@
+use crate::answer::Answer;
+use crate::error::ParseError;
+use crate::generate::Rng;
 use crate::readfile;
+use crate::solver::Solver;
 use lazy_static::lazy_static;
 use regex::Regex;
 
+#[derive(Copy, Clone)]
 enum Direction {
     Forward(u32),
     Down(u32),
     Up(u32),
 }
 
-fn parse_action(s: &str) -> Direction {
+fn parse_action(line: readfile::Line) -> Result<Direction, ParseError> {
     lazy_static! {
-        static ref RE: Regex = Regex::new("(\\w+) (\\d+)").unwrap();
+        static ref RE: Regex = Regex::new("^(\\w+) (\\d+)$").unwrap();
     }
-    let groups = RE.captures(s).unwrap();
-    let value: u32 = groups[2].parse().unwrap();
+    let groups = line.captures(&RE)?;
+    let value: u32 = line.parse_field(&groups[2])?;
     match &groups[1] {
-        "forward" => Direction::Forward(value),
-        "down" => Direction::Down(value),
-        "up" => Direction::Up(value),
-        _ => panic!("Invalid command {}", &groups[1])
+        "forward" => Ok(Direction::Forward(value)),
+        "down" => Ok(Direction::Down(value)),
+        "up" => Ok(Direction::Up(value)),
+        command => Err(line.error_at(command, &format!("invalid command '{}'", command))),
     }
 }
 
-fn part1(lines: &readfile::Lines) {
-    let mut x = 0;
-    let mut y = 0;
-    for line in lines.lines() {
-        match parse_action(line) {
-            Direction::Forward(v) => x += v,
-            Direction::Down(v) => y += v,
-            Direction::Up(v) => y -= v
+pub struct Day2 {
+    actions: Vec<Direction>,
+}
+
+impl Solver for Day2 {
+    fn parse(lines: &readfile::Lines) -> Result<Day2, ParseError> {
+        Ok(Day2 {
+            actions: lines.numbered().map(parse_action).collect::<Result<_, _>>()?,
+        })
+    }
+
+    fn part1(&self) -> Answer {
+        let mut x: u64 = 0;
+        let mut y: u64 = 0;
+        for action in &self.actions {
+            match *action {
+                Direction::Forward(v) => x += v as u64,
+                Direction::Down(v) => y += v as u64,
+                Direction::Up(v) => y -= v as u64
+            }
         }
+        (x * y).into()
     }
-    println!("Part 1: {}", x * y);
-}
 
-fn part2(lines: &readfile::Lines) {
-    let mut x = 0;
-    let mut y = 0;
-    let mut aim = 0;
-    for line in lines.lines() {
-        match parse_action(line) {
-            Direction::Forward(v) => {
-                x += v;
-                y += aim * v;
-            },
-            Direction::Down(v) => aim += v,
-            Direction::Up(v) => aim -= v
+    fn part2(&self) -> Answer {
+        let mut x: u64 = 0;
+        let mut y: u64 = 0;
+        let mut aim: u64 = 0;
+        for action in &self.actions {
+            match *action {
+                Direction::Forward(v) => {
+                    x += v as u64;
+                    y += aim * v as u64;
+                },
+                Direction::Down(v) => aim += v as u64,
+                Direction::Up(v) => aim -= v as u64
+            }
         }
+        (x * y).into()
+    }
+
+    /// `size` commands with values up to 9 that never go above the surface.
+    fn generate(rng: &mut Rng, size: usize) -> Option<String> {
+        let mut depth = 0;
+        let commands: Vec<String> = (0..size.max(1))
+            .map(|_| {
+                let value = rng.range(1..=9);
+                match rng.below(3) {
+                    0 => format!("forward {}", value),
+                    1 if depth >= value => {
+                        depth -= value;
+                        format!("up {}", value)
+                    }
+                    _ => {
+                        depth += value;
+                        format!("down {}", value)
+                    }
+                }
+            })
+            .collect();
+        Some(commands.join("\n"))
     }
-    println!("Part 1: {}", x * y);
 }
 
-pub fn run() {
-    let lines = readfile::Lines::new("day2.txt");
-    part1(&lines);
-    part2(&lines);
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generate;
+
+    const EXAMPLE: &str = "\
+forward 5
+down 5
+forward 8
+up 3
+down 8
+forward 2";
+
+    #[test]
+    fn example() {
+        let day = Day2::parse(&readfile::Lines::from(EXAMPLE)).unwrap();
+        assert_eq!(day.part1(), Answer::Number(150));
+        assert_eq!(day.part2(), Answer::Number(900));
+    }
+
+    /// Part 2 summed the other way around: every change of the aim counts for the distance
+    /// moved forward after it.
+    #[test]
+    fn matches_reordered_sums() {
+        generate::check_answers::<Day2>(
+            0..20,
+            |seed| 1 + seed as usize * 5,
+            |day, _| {
+                let forward = |a: &Direction| match a {
+                    Direction::Forward(v) => *v as i64,
+                    _ => 0,
+                };
+                let forward_after =
+                    |i: usize| -> i64 { day.actions[i..].iter().map(forward).sum() };
+                let (mut depth, mut depth2) = (0, 0);
+                for (i, action) in day.actions.iter().enumerate() {
+                    match *action {
+                        Direction::Forward(_) => {}
+                        Direction::Down(v) => {
+                            depth += v as i64;
+                            depth2 += v as i64 * forward_after(i);
+                        }
+                        Direction::Up(v) => {
+                            depth -= v as i64;
+                            depth2 -= v as i64 * forward_after(i);
+                        }
+                    }
+                }
+                let x = forward_after(0);
+                [
+                    Some(Answer::Number(x * depth)),
+                    Some(Answer::Number(x * depth2)),
+                ]
+            },
+        );
+    }
+
+    #[test]
+    fn invalid_command() {
+        let err = Day2::parse(&readfile::Lines::from("forward 5\nbackward 2")).err().unwrap();
+        assert_eq!((err.line, err.column), (2, 1));
+        assert_eq!(err.message, "invalid command 'backward'");
+    }
 }
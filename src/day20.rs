@@ -1,160 +1,253 @@
+use crate::answer::Answer;
+use crate::error::ParseError;
+use crate::generate::Rng;
+use crate::grid::Grid;
+use crate::netpbm;
 use crate::readfile;
-use std::collections::HashSet;
-
-type Point = [isize; 2];
+use crate::solver::{Param, Params, Solver};
+use crate::visualize::Animation;
 
+#[derive(Clone)]
 struct Image {
-    are_outside_pixels_on: u8,
-    light_pixels: HashSet<Point>,
-    min_x: isize,
-    max_x: isize,
-    min_y: isize,
-    max_y: isize,
+    /// The pixels of the infinite image outside of `pixels` are all the same.
+    are_outside_pixels_on: bool,
+    pixels: Grid<bool>,
 }
 
 impl Image {
-    pub fn enhance(&self, algorithm: &[u8]) -> Image {
-        let mut enhanced = HashSet::new();
-
-        for y in self.min_y - 1..=self.max_y + 1 {
-            for x in self.min_x - 1..=self.max_x + 1 {
-                let pixel_value = self.get_pixel_value(x, y);
-                if algorithm[pixel_value] == 1 {
-                    enhanced.insert([x, y]);
-                }
-            }
-        }
-
-        let are_outside_pixels_on = if self.are_outside_pixels_on == 1 {
+    /// Applies the algorithm once, the image grows by one pixel on every side.
+    pub fn enhance(&self, algorithm: &[bool]) -> Image {
+        let width = self.pixels.width() + 2;
+        let height = self.pixels.height() + 2;
+        let pixels = Grid::from_fn(width, height, |[x, y]| {
+            // the point in the new image is one pixel down and right of the old one
+            algorithm[self.get_pixel_value(x as isize - 1, y as isize - 1)]
+        });
+
+        let are_outside_pixels_on = if self.are_outside_pixels_on {
             algorithm[511]
         } else {
             algorithm[0]
         };
-        if are_outside_pixels_on == 1 {
-            // top and bottom
-            for y in [self.min_y - 2, self.max_y + 2] {
-                for x in self.min_x - 2..=self.max_x + 2 {
-                    enhanced.insert([x, y]);
-                }
-            }
-
-            // left and right
-            for y in self.min_y - 2..=self.max_y + 2 {
-                for x in [self.min_x - 2, self.max_x + 2] {
-                    enhanced.insert([x, y]);
-                }
-            }
-        }
 
         return Image {
             are_outside_pixels_on,
-            light_pixels: enhanced,
-            min_x: self.min_x - 1,
-            max_x: self.max_x + 1,
-            min_y: self.min_y - 1,
-            max_y: self.max_y + 1,
+            pixels,
         };
     }
 
+    pub fn render(&self) -> String {
+        self.pixels.render(|_, on| String::from(if *on { "#" } else { "." }))
+    }
+
+    pub fn count_light_pixels(&self) -> usize {
+        self.pixels.values().filter(|on| **on).count()
+    }
+
     fn get_pixel_value(&self, x: isize, y: isize) -> usize {
-        let mut i = 8;
-        let mut value: u16 = 0;
+        let mut value = 0;
         for cy in y - 1..=y + 1 {
             for cx in x - 1..=x + 1 {
-                let px = if self.is_pixel_on(cx, cy) { 1 } else { 0 };
-                value |= px << i;
-                i -= 1;
+                value = (value << 1) | self.is_pixel_on(cx, cy) as usize;
             }
         }
-
-        return value as usize;
+        return value;
     }
 
     fn is_pixel_on(&self, x: isize, y: isize) -> bool {
-        if x < self.min_x || x > self.max_x || y < self.min_y || y > self.max_y {
-            return self.are_outside_pixels_on == 1;
+        if x < 0 || y < 0 {
+            return self.are_outside_pixels_on;
         }
-        return self.light_pixels.contains(&[x, y]);
+        return match self.pixels.get([x as usize, y as usize]) {
+            Some(on) => *on,
+            None => self.are_outside_pixels_on,
+        };
     }
 }
 
 impl std::fmt::Debug for Image {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::result::Result<(), std::fmt::Error> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         writeln!(
             f,
-            "({} .. {}) ({} .. {}) | outside pixels: {}",
-            self.min_x, self.max_x, self.min_y, self.max_y, self.are_outside_pixels_on
-        )
-        .expect("Fail");
-        for y in self.min_y - 1..=self.max_y + 1 {
-            for x in self.min_x - 1..=self.max_x + 1 {
-                if self.light_pixels.contains(&[x, y]) {
-                    write!(f, "#").expect("Fail");
-                } else {
-                    write!(f, ".").expect("Fail");
-                }
-            }
-            writeln!(f).expect("Fail");
-        }
-        Ok(())
+            "{}x{} | outside pixels: {}",
+            self.pixels.width(),
+            self.pixels.height(),
+            self.are_outside_pixels_on
+        )?;
+        writeln!(f, "{}", self.render())
     }
 }
 
-fn parse_input(lines: &readfile::Lines) -> (Vec<u8>, Image) {
-    let mut line_iter = lines.lines();
+fn parse_pixel(line: readfile::Line, index: usize, c: char) -> Result<bool, ParseError> {
+    match c {
+        '#' => Ok(true),
+        '.' => Ok(false),
+        _ => Err(line.error(index + 1, &format!("expected '#' or '.', found '{}'", c))),
+    }
+}
 
-    let algorithm = line_iter
-        .next()
-        .unwrap()
+fn parse_input(lines: &readfile::Lines) -> Result<(Vec<bool>, Image), ParseError> {
+    let first = lines.first()?;
+    let algorithm: Vec<bool> = first
+        .text()
         .chars()
-        .map(|c| if c == '#' { 1 } else { 0 })
-        .collect();
-
-    line_iter.next();
-
-    let mut y: isize = 0;
-    let mut max_x: isize = 0;
-    let mut light_pixels: HashSet<Point> = HashSet::new();
-    for l in line_iter {
-        if max_x == 0 {
-            max_x = l.chars().count() as isize - 1;
-        }
-        for (x, c) in l.chars().enumerate() {
-            if c == '#' {
-                light_pixels.insert([x as isize, y]);
-            }
-        }
-        y += 1;
+        .enumerate()
+        .map(|(i, c)| parse_pixel(first, i, c))
+        .collect::<Result<_, _>>()?;
+    if algorithm.len() != 512 {
+        return Err(first.error(1, "expected an enhancement algorithm of 512 pixels"));
     }
+
     let image = Image {
-        are_outside_pixels_on: 0,
-        light_pixels,
-        min_x: 0,
-        max_x,
-        min_y: 0,
-        max_y: y - 1,
+        are_outside_pixels_on: false,
+        pixels: Grid::parse_after(lines, 2, parse_pixel)?,
     };
 
-    return (algorithm, image);
+    return Ok((algorithm, image));
+}
+
+pub struct Day20 {
+    algorithm: Vec<bool>,
+    image: Image,
+    steps: Option<usize>,
 }
 
-fn part1(lines: &readfile::Lines) {
-    let (algorithm, image) = parse_input(lines);
-    let enhanced = image.enhance(&algorithm).enhance(&algorithm);
-    println!("Part 1: {}", enhanced.light_pixels.len());
+impl Day20 {
+    fn count_light_pixels(&self, steps: usize) -> usize {
+        let mut enhanced = self.image.clone();
+        for _ in 0..steps {
+            enhanced = enhanced.enhance(&self.algorithm);
+        }
+        enhanced.count_light_pixels()
+    }
 }
 
-fn part2(lines: &readfile::Lines) {
-    let (algorithm, image) = parse_input(lines);
-    let mut enhanced = image;
-    for _ in 0..50 {
-        enhanced = enhanced.enhance(&algorithm);
+impl Solver for Day20 {
+    fn parse(lines: &readfile::Lines) -> Result<Day20, ParseError> {
+        let (algorithm, image) = parse_input(lines)?;
+        Ok(Day20 {
+            algorithm,
+            image,
+            steps: None,
+        })
+    }
+
+    fn part1(&self) -> Answer {
+        self.count_light_pixels(self.steps.unwrap_or(2)).into()
+    }
+
+    fn part2(&self) -> Answer {
+        self.count_light_pixels(self.steps.unwrap_or(50)).into()
+    }
+
+    fn params() -> &'static [Param] {
+        &[Param {
+            name: "steps",
+            help: "number of enhancements (2 in part 1, 50 in part 2)",
+        }]
+    }
+
+    fn configure(&mut self, params: &Params) -> Result<(), String> {
+        self.steps = params.get("steps")?;
+        Ok(())
+    }
+
+    /// The image after the enhancements of part 2, light pixels are white.
+    fn image(&self) -> Option<netpbm::Image> {
+        let mut enhanced = self.image.clone();
+        for _ in 0..self.steps.unwrap_or(50) {
+            enhanced = enhanced.enhance(&self.algorithm);
+        }
+        Some(netpbm::Image::Bitmap(enhanced.pixels.map(|on| !on)))
+    }
+
+    /// The enhancements of part 2.
+    fn visualize(&self, animation: &mut Animation) -> bool {
+        let mut enhanced = self.image.clone();
+        let caption = format!("{} light pixels", enhanced.count_light_pixels());
+        animation.frame(&caption, &enhanced.render());
+        for step in 1..=self.steps.unwrap_or(50) {
+            enhanced = enhanced.enhance(&self.algorithm);
+            let caption = format!(
+                "enhancement {}: {} light pixels{}",
+                step,
+                enhanced.count_light_pixels(),
+                if enhanced.are_outside_pixels_on {
+                    " and infinitely many outside"
+                } else {
+                    ""
+                }
+            );
+            animation.frame(&caption, &enhanced.render());
+        }
+        true
+    }
+
+    /// A random algorithm and a random `size` x `size` image (at least 1 x 1).
+    fn generate(rng: &mut Rng, size: usize) -> Option<String> {
+        let pixel = |on: bool| if on { '#' } else { '.' };
+        let algorithm: String = (0..512).map(|_| pixel(rng.chance(1, 2))).collect();
+        let size = size.max(1);
+        let image = Grid::from_fn(size, size, |_| rng.chance(1, 2));
+        let image = image.render(|_, on| pixel(*on).to_string());
+        Some(format!("{}\n\n{}", algorithm, image))
     }
-    println!("Part 2: {}", enhanced.light_pixels.len());
 }
 
-pub fn run() {
-    let lines = readfile::Lines::new("day20.txt");
-    part1(&lines);
-    part2(&lines);
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generate;
+
+    const EXAMPLE: &str = "\
+..#.#..#####.#.#.#.###.##.....###.##.#..###.####..#####..#....#..#..##..###..######.###...####..#..#####..##..#.#####...##.#.#..#.##..#.#......#.###.######.###.####...#.##.##..#..#..#####.....#.#....###..#.##......#.....#..#..#..##..#...##.######.####.####.#.#...#.......#..#.#.#...####.##.#......#..#...##.#.##..#...##.#.##..###.#......#.#.......#.#.#.####.###.##...#.....####.#..#..#.##.#....##..#.####....##...##..#...#......#.#.......#.......##..####..#...#.#.#...##..#.#..###..#####........#..####......#..#
+
+#..#.
+#....
+##..#
+..#..
+..###";
+
+    #[test]
+    fn example() {
+        let day = Day20::parse(&readfile::Lines::from(EXAMPLE)).unwrap();
+        assert_eq!(day.algorithm.len(), 512);
+        assert_eq!(day.part1(), Answer::Number(35));
+        assert_eq!(day.part2(), Answer::Number(3351));
+    }
+
+    /// Whether the pixel is on after `steps` enhancements, straight from the pixels around it
+    /// in the step before.
+    fn brute_force_pixel(day: &Day20, steps: usize, x: isize, y: isize) -> bool {
+        if steps == 0 {
+            let pixel = day.image.pixels.get([x as usize, y as usize]);
+            return x >= 0 && y >= 0 && pixel == Some(&true);
+        }
+        let mut index = 0;
+        for dy in -1..=1 {
+            for dx in -1..=1 {
+                index = (index << 1) | brute_force_pixel(day, steps - 1, x + dx, y + dy) as usize;
+            }
+        }
+        return day.algorithm[index];
+    }
+
+    #[test]
+    fn matches_brute_force() {
+        generate::check_answers::<Day20>(
+            0..10,
+            |seed| 1 + seed as usize,
+            |day, _| {
+                let (width, height) = (day.image.pixels.width(), day.image.pixels.height());
+                // the image grows by one pixel on every side per step
+                let mut expected = 0;
+                for y in -2..height as isize + 2 {
+                    for x in -2..width as isize + 2 {
+                        expected += brute_force_pixel(day, 2, x, y) as usize;
+                    }
+                }
+                [Some(expected.into()), None]
+            },
+        );
+    }
 }
@@ -1,4 +1,12 @@
-#[derive(Clone, Copy)]
+use crate::answer::Answer;
+use crate::error::ParseError;
+use crate::generate::Rng;
+use crate::readfile;
+use crate::solver::{Param, Params, Solver};
+use regex::Regex;
+use std::collections::HashMap;
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
 struct Player {
     position: u8,
     score: usize,
@@ -41,31 +49,26 @@ impl Dice {
     }
 }
 
-fn part1() {
-    let player1 = Player::new(6);
-    let player2 = Player::new(4);
-    let mut players = [player1, player2];
-
-    let mut dice = Dice::new(100);
-
-    let mut current_player_index = 0;
-    loop {
-        let player = &mut players[current_player_index];
-        let dice_value = dice.roll() + dice.roll() + dice.roll();
-        player.move_ahead(dice_value);
-        if current_player_index == 0 {
-            current_player_index = 1;
-        } else {
-            current_player_index = 0
+fn parse_starting_positions(lines: &readfile::Lines) -> Result<[u8; 2], ParseError> {
+    let re = Regex::new("^Player (\\d+) starting position: (\\d+)$").unwrap();
+    let mut positions = [None; 2];
+    for l in lines.numbered() {
+        let groups = l.captures(&re)?;
+        let player: usize = l.parse_field(&groups[1])?;
+        let position: u8 = l.parse_field(&groups[2])?;
+        if player != 1 && player != 2 {
+            return Err(l.error_at(&groups[1], "expected player 1 or 2"));
         }
-
-        if player.score >= 1000 {
-            break;
+        if !(1..=10).contains(&position) {
+            return Err(l.error_at(&groups[2], "expected a position between 1 and 10"));
         }
+        // the board is numbered 1..=10, internally we use 0..10
+        positions[player - 1] = Some(position - 1);
+    }
+    match positions {
+        [Some(p1), Some(p2)] => Ok([p1, p2]),
+        _ => Err(lines.error_at_end("expected starting positions for both players")),
     }
-
-    let loosing_player = &players[current_player_index];
-    println!("Part 1: {}", loosing_player.score * dice.rolled);
 }
 
 fn get_factor(dice_sum: usize) -> usize {
@@ -81,45 +84,180 @@ fn get_factor(dice_sum: usize) -> usize {
     }
 }
 
-fn simulate_single(mut active_player: Player, other_player: Player, dice_sum: usize) -> [usize; 2] {
-    let mut active_player_wins = 0;
-    let mut other_player_wins = 0;
-
-    active_player.move_ahead(dice_sum);
-    let factor = get_factor(dice_sum);
-    if active_player.score >= 21 {
-        active_player_wins += factor;
-    } else {
-        let wins = simulate(other_player, active_player);
-        let factor = get_factor(dice_sum);
-        active_player_wins += factor * wins[1];
-        other_player_wins += factor * wins[0];
+fn count_wins(player1: Player, player2: Player) -> [usize; 2] {
+    // number of universes in which the game is in a given state
+    let mut universes: HashMap<[Player; 2], usize> = HashMap::new();
+    universes.insert([player1, player2], 1);
+    let mut wins = [0, 0];
+    let mut current_player_index = 0;
+    while !universes.is_empty() {
+        let mut next_universes: HashMap<[Player; 2], usize> = HashMap::new();
+        for (players, count) in universes {
+            for dice_sum in 3..=9 {
+                let mut players = players;
+                let player = &mut players[current_player_index];
+                player.move_ahead(dice_sum);
+                let n = count * get_factor(dice_sum);
+                if player.score >= 21 {
+                    wins[current_player_index] += n;
+                } else {
+                    *next_universes.entry(players).or_insert(0) += n;
+                }
+            }
+        }
+        universes = next_universes;
+        current_player_index = 1 - current_player_index;
     }
+    return wins;
+}
 
-    return [active_player_wins, other_player_wins];
+pub struct Day21 {
+    starting_positions: [u8; 2],
 }
 
-fn simulate(active_player: Player, other_player: Player) -> [usize; 2] {
-    let mut active_player_wins = 0;
-    let mut other_player_wins = 0;
+impl Solver for Day21 {
+    fn parse(lines: &readfile::Lines) -> Result<Day21, ParseError> {
+        Ok(Day21 {
+            starting_positions: parse_starting_positions(lines)?,
+        })
+    }
+
+    fn part1(&self) -> Answer {
+        let player1 = Player::new(self.starting_positions[0]);
+        let player2 = Player::new(self.starting_positions[1]);
+        let mut players = [player1, player2];
+
+        let mut dice = Dice::new(100);
 
-    for dice_sum in 3..=9 {
-        let wins = simulate_single(active_player, other_player, dice_sum);
-        active_player_wins += wins[0];
-        other_player_wins += wins[1];
+        let mut current_player_index = 0;
+        loop {
+            let player = &mut players[current_player_index];
+            let dice_value = dice.roll() + dice.roll() + dice.roll();
+            player.move_ahead(dice_value);
+            if current_player_index == 0 {
+                current_player_index = 1;
+            } else {
+                current_player_index = 0
+            }
+
+            if player.score >= 1000 {
+                break;
+            }
+        }
+
+        let loosing_player = &players[current_player_index];
+        (loosing_player.score * dice.rolled).into()
     }
 
-    return [active_player_wins, other_player_wins];
-}
+    fn part2(&self) -> Answer {
+        let player1 = Player::new(self.starting_positions[0]);
+        let player2 = Player::new(self.starting_positions[1]);
+        let [p1_wins, p2_wins] = count_wins(player1, player2);
+        p1_wins.max(p2_wins).into()
+    }
+
+    fn params() -> &'static [Param] {
+        &[
+            Param {
+                name: "player1",
+                help: "starting position of player 1 (from the input)",
+            },
+            Param {
+                name: "player2",
+                help: "starting position of player 2 (from the input)",
+            },
+        ]
+    }
 
-fn part2() {
-    let player1 = Player::new(6);
-    let player2 = Player::new(4);
-    let [p1_wins, p2_wins] = simulate(player1, player2);
-    println!("Part 2: {}", p1_wins.max(p2_wins));
+    fn configure(&mut self, params: &Params) -> Result<(), String> {
+        for (i, name) in ["player1", "player2"].iter().enumerate() {
+            match params.get::<u8>(name)? {
+                Some(position) if (1..=10).contains(&position) => {
+                    self.starting_positions[i] = position - 1
+                }
+                Some(_) => return Err(format!("{} must be between 1 and 10", name)),
+                None => {}
+            }
+        }
+        Ok(())
+    }
+
+    /// Random starting positions, the size is ignored.
+    fn generate(rng: &mut Rng, _size: usize) -> Option<String> {
+        let lines: Vec<String> = (1..=2)
+            .map(|player| format!("Player {} starting position: {}", player, rng.range(1..=10)))
+            .collect();
+        Some(lines.join("\n"))
+    }
 }
 
-pub fn run() {
-    part1();
-    part2();
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generate;
+
+    const EXAMPLE: &str = "\
+Player 1 starting position: 4
+Player 2 starting position: 8";
+
+    #[test]
+    fn example() {
+        let day = Day21::parse(&readfile::Lines::from(EXAMPLE)).unwrap();
+        assert_eq!(day.part1(), Answer::Number(739785));
+        assert_eq!(day.part2(), Answer::Number(444356092776315));
+    }
+
+    /// The wins of the player to move and of the other one, trying every roll of the three
+    /// dice on its own.
+    fn brute_force_wins(
+        positions: [usize; 2],
+        scores: [usize; 2],
+        known: &mut HashMap<([usize; 2], [usize; 2]), [usize; 2]>,
+    ) -> [usize; 2] {
+        if let Some(wins) = known.get(&(positions, scores)) {
+            return *wins;
+        }
+        let mut wins = [0, 0];
+        for rolls in 0..27 {
+            let sum = rolls % 3 + rolls / 3 % 3 + rolls / 9 + 3;
+            let position = (positions[0] + sum - 1) % 10 + 1;
+            let score = scores[0] + position;
+            if score >= 21 {
+                wins[0] += 1;
+            } else {
+                let [other, own] =
+                    brute_force_wins([positions[1], position], [scores[1], score], known);
+                wins[0] += own;
+                wins[1] += other;
+            }
+        }
+        known.insert((positions, scores), wins);
+        return wins;
+    }
+
+    #[test]
+    fn matches_brute_force() {
+        generate::check_answers::<Day21>(
+            0..10,
+            |_| 0,
+            |day, _| {
+                let mut positions = day.starting_positions.map(|p| p as usize + 1);
+
+                let mut scores = [0, 0];
+                let mut rolled = 0;
+                while scores[0] < 1000 && scores[1] < 1000 {
+                    let player = rolled / 3 % 2;
+                    let sum: usize = (rolled..rolled + 3).map(|r| r % 100 + 1).sum();
+                    positions[player] = (positions[player] + sum - 1) % 10 + 1;
+                    scores[player] += positions[player];
+                    rolled += 3;
+                }
+                let part1 = scores[0].min(scores[1]) * rolled;
+
+                let positions = day.starting_positions.map(|p| p as usize + 1);
+                let wins = brute_force_wins(positions, [0, 0], &mut HashMap::new());
+                [Some(part1.into()), Some(wins[0].max(wins[1]).into())]
+            },
+        );
+    }
 }
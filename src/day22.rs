@@ -1,84 +1,485 @@
+use crate::answer::Answer;
+use crate::error::ParseError;
+use crate::generate::Rng;
+use crate::point3::Point3;
 use crate::readfile;
+use crate::solver::Solver;
 use lazy_static::lazy_static;
 use regex::Regex;
-use std::collections::HashSet;
 
-type Point = [isize; 3];
+/// A box of cubes, `min` and `max` are the corners included in it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Cuboid {
+    pub min: Point3,
+    pub max: Point3,
+}
+
+impl Cuboid {
+    pub fn contains(&self, p: Point3) -> bool {
+        (0..3).all(|i| (self.min[i]..=self.max[i]).contains(&p[i]))
+    }
+
+    pub fn intersection(&self, other: &Cuboid) -> Option<Cuboid> {
+        let mut intersection = *self;
+        for i in 0..3 {
+            intersection.min[i] = self.min[i].max(other.min[i]);
+            intersection.max[i] = self.max[i].min(other.max[i]);
+            if intersection.min[i] > intersection.max[i] {
+                return None;
+            }
+        }
+        return Some(intersection);
+    }
+
+    /// The number of cubes.
+    pub fn volume(&self) -> usize {
+        (0..3)
+            .map(|i| (self.max[i] - self.min[i] + 1) as usize)
+            .product()
+    }
+}
 
 struct Operation {
     target_state: bool,
-    x_min: isize,
-    x_max: isize,
-    y_min: isize,
-    y_max: isize,
-    z_min: isize,
-    z_max: isize,
+    cuboid: Cuboid,
 }
 
 impl Operation {
-    pub fn new(s: &str) -> Operation {
+    pub fn new(line: readfile::Line) -> Result<Operation, ParseError> {
         lazy_static! {
             static ref RE: Regex = Regex::new(
-                "(on|off) x=(-?\\d+)..(-?\\d+),y=(-?\\d+)..(-?\\d+),z=(-?\\d+)..(-?\\d+)"
+                "^(on|off) x=(-?\\d+)..(-?\\d+),y=(-?\\d+)..(-?\\d+),z=(-?\\d+)..(-?\\d+)$"
             )
             .unwrap();
         }
-        let groups = RE.captures(s).unwrap();
-        let x1: isize = groups[2].parse().unwrap();
-        let x2: isize = groups[3].parse().unwrap();
-        let y1: isize = groups[4].parse().unwrap();
-        let y2: isize = groups[5].parse().unwrap();
-        let z1: isize = groups[6].parse().unwrap();
-        let z2: isize = groups[7].parse().unwrap();
-        return Operation {
+        let groups = line.captures(&RE)?;
+        let x1: isize = line.parse_field(&groups[2])?;
+        let x2: isize = line.parse_field(&groups[3])?;
+        let y1: isize = line.parse_field(&groups[4])?;
+        let y2: isize = line.parse_field(&groups[5])?;
+        let z1: isize = line.parse_field(&groups[6])?;
+        let z2: isize = line.parse_field(&groups[7])?;
+        return Ok(Operation {
             target_state: &groups[1] == "on",
-            x_min: x1.min(x2),
-            x_max: x1.max(x2),
-            y_min: y1.min(y2),
-            y_max: y1.max(y2),
-            z_min: z1.min(z2),
-            z_max: z1.max(z2),
-        };
+            cuboid: Cuboid {
+                min: Point3::new(x1.min(x2), y1.min(y2), z1.min(z2)),
+                max: Point3::new(x1.max(x2), y1.max(y2), z1.max(z2)),
+            },
+        });
     }
 
-    pub fn perform(&self, state: &mut HashSet<Point>) {
+    /// Whether the cuboid is inside the initialization area of -50..=50 on all axes.
+    fn is_in_initialization_area(&self) -> bool {
         let range = -50..=50;
-        let is_contained = [
-            self.x_min, self.x_max, self.y_min, self.y_max, self.z_min, self.z_max,
-        ]
-        .iter()
-        .all(|v| range.contains(v));
-        if !is_contained {
+        (0..3).all(|axis| {
+            range.contains(&self.cuboid.min[axis]) && range.contains(&self.cuboid.max[axis])
+        })
+    }
+}
+
+/// A box of cells of the compressed coordinates, `min` is included and `max` is not.
+#[derive(Clone, Copy)]
+struct Cells {
+    min: [usize; 3],
+    max: [usize; 3],
+}
+
+impl Cells {
+    fn is_empty(&self) -> bool {
+        (0..3).any(|i| self.min[i] >= self.max[i])
+    }
+
+    fn overlaps(&self, other: &Cells) -> bool {
+        (0..3).all(|i| self.min[i] < other.max[i] && other.min[i] < self.max[i])
+    }
+
+    fn is_inside(&self, other: &Cells) -> bool {
+        (0..3).all(|i| other.min[i] <= self.min[i] && self.max[i] <= other.max[i])
+    }
+
+    /// The box of cells halved along every axis, bit `i` of the index selects the upper half
+    /// along axis `i`. A single cell along an axis ends up in the upper half.
+    fn octants(&self) -> [Cells; 8] {
+        std::array::from_fn(|k| {
+            let mut octant = *self;
+            for i in 0..3 {
+                let middle = (self.min[i] + self.max[i]) / 2;
+                if k >> i & 1 == 0 {
+                    octant.max[i] = middle;
+                } else {
+                    octant.min[i] = middle;
+                }
+            }
+            octant
+        })
+    }
+}
+
+/// A node of the octree, all on, all off or split into octants.
+enum Node {
+    Leaf(bool),
+    Split(Box<[Node; 8]>),
+}
+
+impl Node {
+    /// Switches the cells of `target` within `cells`, the box this node covers. Octants that
+    /// end up in the same state are merged again.
+    fn set(&mut self, cells: Cells, target: &Cells, state: bool) {
+        if cells.is_empty() || !cells.overlaps(target) {
+            return;
+        }
+        match self {
+            Node::Leaf(current) if *current == state => return,
+            _ if cells.is_inside(target) => {
+                *self = Node::Leaf(state);
+                return;
+            }
+            Node::Leaf(current) => {
+                let current = *current;
+                *self = Node::Split(Box::new(std::array::from_fn(|_| Node::Leaf(current))));
+            }
+            Node::Split(_) => {}
+        }
+        let Node::Split(children) = self else {
             return;
+        };
+        let octants = cells.octants();
+        for (child, octant) in children.iter_mut().zip(octants) {
+            child.set(octant, target, state);
         }
 
-        for x in self.x_min..=self.x_max {
-            for y in self.y_min..=self.y_max {
-                for z in self.z_min..=self.z_max {
-                    if self.target_state {
-                        state.insert([x, y, z]);
-                    } else {
-                        state.remove(&[x, y, z]);
-                    }
+        let mut states = children
+            .iter()
+            .zip(octants)
+            .filter(|(_, octant)| !octant.is_empty())
+            .map(|(child, _)| match child {
+                Node::Leaf(state) => Some(*state),
+                Node::Split(_) => None,
+            });
+        if let Some(Some(first)) = states.next() {
+            if states.all(|state| state == Some(first)) {
+                *self = Node::Leaf(first);
+            }
+        }
+    }
+}
+
+/// The cubes that are on, as an octree over the compressed coordinates. The bounds of the
+/// cuboids of the reboot steps cut every axis into ranges of cubes that are always switched
+/// together, so a cell stands for a box of cubes. A grid of the cells would need hundreds of
+/// millions of them for the real input, the octree only splits where cells differ.
+pub struct Reactor {
+    /// The first coordinate of every range along each axis, followed by the end of the last.
+    bounds: [Vec<isize>; 3],
+    root: Node,
+}
+
+impl Reactor {
+    /// An empty reactor with the cells for the cuboids of `operations`.
+    fn new(operations: &[&Operation]) -> Reactor {
+        let bounds = std::array::from_fn(|i| {
+            let mut bounds: Vec<isize> = operations
+                .iter()
+                .flat_map(|op| [op.cuboid.min[i], op.cuboid.max[i] + 1])
+                .collect();
+            bounds.sort_unstable();
+            bounds.dedup();
+            bounds
+        });
+        Reactor {
+            bounds,
+            root: Node::Leaf(false),
+        }
+    }
+
+    /// All cells, the box covered by the root.
+    fn all_cells(&self) -> Cells {
+        Cells {
+            min: [0; 3],
+            max: std::array::from_fn(|i| self.bounds[i].len().saturating_sub(1)),
+        }
+    }
+
+    /// The cubes of a non-empty box of cells.
+    fn cuboid(&self, cells: &Cells) -> Cuboid {
+        let corner = |index: [usize; 3], offset: isize| {
+            let [x, y, z] = std::array::from_fn(|i| self.bounds[i][index[i]] + offset);
+            Point3::new(x, y, z)
+        };
+        Cuboid {
+            min: corner(cells.min, 0),
+            max: corner(cells.max, -1),
+        }
+    }
+
+    /// Applies a reboot step, its cuboid has to be one the reactor was created for.
+    fn apply(&mut self, op: &Operation) {
+        let index =
+            |i: usize, coordinate: isize| self.bounds[i].binary_search(&coordinate).unwrap();
+        let target = Cells {
+            min: std::array::from_fn(|i| index(i, op.cuboid.min[i])),
+            max: std::array::from_fn(|i| index(i, op.cuboid.max[i] + 1)),
+        };
+        let cells = self.all_cells();
+        self.root.set(cells, &target, op.target_state);
+    }
+
+    pub fn is_on(&self, p: Point3) -> bool {
+        let mut index = [0; 3];
+        for i in 0..3 {
+            match self.bounds[i].partition_point(|b| *b <= p[i]) {
+                0 => return false,
+                n if n == self.bounds[i].len() => return false,
+                n => index[i] = n - 1,
+            }
+        }
+        let mut node = &self.root;
+        let mut cells = self.all_cells();
+        loop {
+            match node {
+                Node::Leaf(state) => return *state,
+                Node::Split(children) => {
+                    let k: usize = (0..3)
+                        .map(|i| usize::from(index[i] >= (cells.min[i] + cells.max[i]) / 2) << i)
+                        .sum();
+                    node = &children[k];
+                    cells = cells.octants()[k];
                 }
             }
         }
     }
+
+    /// The lit cubes inside `region` as disjoint cuboids, everywhere without a region.
+    fn lit(&self, region: Option<&Cuboid>) -> Vec<Cuboid> {
+        let mut lit = vec![];
+        let mut pending = vec![(&self.root, self.all_cells())];
+        while let Some((node, cells)) = pending.pop() {
+            if cells.is_empty() {
+                continue;
+            }
+            let cuboid = match region {
+                Some(region) => match self.cuboid(&cells).intersection(region) {
+                    Some(intersection) => intersection,
+                    None => continue,
+                },
+                None => self.cuboid(&cells),
+            };
+            match node {
+                Node::Leaf(true) => lit.push(cuboid),
+                Node::Leaf(false) => {}
+                Node::Split(children) => pending.extend(children.iter().zip(cells.octants())),
+            }
+        }
+        return lit;
+    }
+
+    /// The number of cubes that are on.
+    pub fn count(&self) -> usize {
+        self.lit(None).iter().map(Cuboid::volume).sum()
+    }
+
+    /// The number of cubes that are on inside `region`.
+    pub fn count_in(&self, region: &Cuboid) -> usize {
+        self.lit(Some(region)).iter().map(Cuboid::volume).sum()
+    }
+
+    /// The lit cubes as disjoint cuboids, in no particular order.
+    pub fn cuboids(&self) -> Vec<Cuboid> {
+        self.lit(None)
+    }
+}
+
+pub struct Day22 {
+    operations: Vec<Operation>,
 }
 
-fn part1(lines: &readfile::Lines) {
-    let operations: Vec<Operation> = lines.lines().map(Operation::new).collect();
-    let mut state: HashSet<Point> = HashSet::new();
-    for op in operations {
-        op.perform(&mut state);
+impl Day22 {
+    /// The reactor after all reboot steps.
+    pub fn reactor(&self) -> Reactor {
+        self.reboot(|_| true)
+    }
+
+    fn reboot<F: Fn(&Operation) -> bool>(&self, include: F) -> Reactor {
+        let operations: Vec<&Operation> = self.operations.iter().filter(|op| include(op)).collect();
+        let mut reactor = Reactor::new(&operations);
+        for op in operations {
+            reactor.apply(op);
+        }
+        return reactor;
     }
-    println!("Part 1: {}", state.len());
 }
 
-fn part2(_lines: &readfile::Lines) {}
+impl Solver for Day22 {
+    fn parse(lines: &readfile::Lines) -> Result<Day22, ParseError> {
+        Ok(Day22 {
+            operations: lines.numbered().map(Operation::new).collect::<Result<_, _>>()?,
+        })
+    }
 
-pub fn run() {
-    let lines = readfile::Lines::new("day22.txt");
-    part1(&lines);
-    part2(&lines);
+    fn part1(&self) -> Answer {
+        let reactor = self.reboot(Operation::is_in_initialization_area);
+        reactor.count().into()
+    }
+
+    fn part2(&self) -> Answer {
+        self.reactor().count().into()
+    }
+
+    /// `size` reboot steps with cuboids of up to 12 cubes per side around the initialization
+    /// area, some of them reaching outside of it.
+    fn generate(rng: &mut Rng, size: usize) -> Option<String> {
+        let mut steps = vec![];
+        for i in 0..size.max(1) {
+            // start with a cube that is on, turning off cubes of an empty reactor is dull
+            let state = if i == 0 || rng.chance(2, 3) { "on" } else { "off" };
+            let ranges: Vec<String> = ["x", "y", "z"]
+                .iter()
+                .map(|axis| {
+                    let min = rng.range(-60..=50);
+                    format!("{}={}..{}", axis, min, min + rng.range(0..=11))
+                })
+                .collect();
+            steps.push(format!("{} {}", state, ranges.join(",")));
+        }
+        Some(steps.join("\n"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generate;
+    use std::collections::HashSet;
+
+    const SMALL_EXAMPLE: &str = "\
+on x=10..12,y=10..12,z=10..12
+on x=11..13,y=11..13,z=11..13
+off x=9..11,y=9..11,z=9..11
+on x=10..10,y=10..10,z=10..10";
+
+    #[test]
+    fn small_example() {
+        let day = Day22::parse(&readfile::Lines::from(SMALL_EXAMPLE)).unwrap();
+        assert_eq!(day.part1(), Answer::Number(39));
+    }
+
+    /// The number of cubes whose last step among `operations` turned them on.
+    fn brute_force_count(operations: &[&Operation]) -> usize {
+        let mut touched = HashSet::new();
+        for op in operations {
+            let Cuboid { min, max } = op.cuboid;
+            for x in min.x..=max.x {
+                for y in min.y..=max.y {
+                    for z in min.z..=max.z {
+                        touched.insert(Point3::new(x, y, z));
+                    }
+                }
+            }
+        }
+        touched
+            .into_iter()
+            .filter(|&p| {
+                let last = operations.iter().rev().find(|op| op.cuboid.contains(p));
+                last.unwrap().target_state
+            })
+            .count()
+    }
+
+    #[test]
+    fn matches_brute_force() {
+        generate::check_answers::<Day22>(
+            0..30,
+            |seed| 1 + seed as usize % 10,
+            |day, _| {
+                let all: Vec<&Operation> = day.operations.iter().collect();
+                let initialization: Vec<&Operation> = day
+                    .operations
+                    .iter()
+                    .filter(|op| op.is_in_initialization_area())
+                    .collect();
+                let part1 = brute_force_count(&initialization);
+                [Some(part1.into()), Some(brute_force_count(&all).into())]
+            },
+        );
+    }
+
+    #[test]
+    fn reactor_matches_brute_force() {
+        for seed in 0..30 {
+            let mut rng = Rng::new(seed);
+            let input = Day22::generate(&mut rng, 1 + seed as usize % 10).unwrap();
+            let day = Day22::parse(&readfile::Lines::from(input.as_str())).unwrap();
+            let reactor = day.reactor();
+            let brute_force_count_in = |region: &Cuboid| {
+                let clipped: Vec<Operation> = day
+                    .operations
+                    .iter()
+                    .filter_map(|op| {
+                        let cuboid = op.cuboid.intersection(region)?;
+                        let target_state = op.target_state;
+                        Some(Operation { target_state, cuboid })
+                    })
+                    .collect();
+                brute_force_count(&clipped.iter().collect::<Vec<_>>())
+            };
+            for _ in 0..20 {
+                let mut random_point = |range: std::ops::RangeInclusive<isize>| {
+                    Point3::new(
+                        rng.range(range.clone()),
+                        rng.range(range.clone()),
+                        rng.range(range),
+                    )
+                };
+                let min = random_point(-60..=60);
+                let size = random_point(0..=15);
+                let region = Cuboid {
+                    min,
+                    max: min + size,
+                };
+                let expected = brute_force_count_in(&region);
+                assert_eq!(reactor.count_in(&region), expected, "seed {}", seed);
+                let expected = brute_force_count_in(&Cuboid { min, max: min }) == 1;
+                assert_eq!(reactor.is_on(min), expected, "seed {}", seed);
+            }
+        }
+    }
+
+    #[test]
+    fn reactor_queries() {
+        let day = Day22::parse(&readfile::Lines::from(SMALL_EXAMPLE)).unwrap();
+        let reactor = day.reactor();
+        assert!(reactor.is_on(Point3::new(10, 10, 10)));
+        assert!(!reactor.is_on(Point3::new(11, 11, 11)));
+        assert!(reactor.is_on(Point3::new(13, 13, 13)));
+        let corner = Cuboid {
+            min: Point3::new(12, 12, 12),
+            max: Point3::new(13, 13, 13),
+        };
+        assert_eq!(reactor.count_in(&corner), 8);
+
+        let cuboids = reactor.cuboids();
+        for (i, a) in cuboids.iter().enumerate() {
+            assert!(cuboids[i + 1..].iter().all(|b| a.intersection(b).is_none()));
+        }
+        assert_eq!(cuboids.iter().map(Cuboid::volume).sum::<usize>(), 39);
+    }
+
+    #[test]
+    fn overlapping_cuboids_are_counted_once() {
+        let day = Day22::parse(&readfile::Lines::from(SMALL_EXAMPLE)).unwrap();
+        assert_eq!(day.part2(), Answer::Number(39));
+        let input = "\
+on x=-100000..100000,y=0..9,z=0..9
+on x=0..100000,y=0..9,z=0..9
+off x=-5..4,y=5..14,z=-100..100";
+        let day = Day22::parse(&readfile::Lines::from(input)).unwrap();
+        assert_eq!(day.part1(), Answer::Number(0));
+        assert_eq!(day.part2(), Answer::Number(200001 * 100 - 10 * 5 * 10));
+    }
+
+    #[test]
+    fn ignores_cuboids_outside_initialization_area() {
+        let day = Day22::parse(&readfile::Lines::from("on x=-54..-49,y=0..0,z=0..0")).unwrap();
+        assert_eq!(day.part1(), Answer::Number(0));
+    }
 }
@@ -0,0 +1,530 @@
+use crate::answer::Answer;
+use crate::error::ParseError;
+use crate::generate::Rng;
+use crate::readfile;
+use crate::search::{self, Path};
+use crate::solver::Solver;
+use crate::visualize::Animation;
+use std::fmt;
+
+const EMPTY: u8 = b'.';
+const HALLWAY_LENGTH: usize = 11;
+const ROOMS: usize = 4;
+const MAX_DEPTH: usize = 4;
+
+/// The rows folded into the burrow for part 2, between the two rows of the input.
+const FOLDED_ROWS: [&[u8; ROOMS]; 2] = [b"DCBA", b"DBAC"];
+
+/// The hallway positions right outside a room, amphipods never stop there.
+fn entrance(room: usize) -> usize {
+    2 + 2 * room
+}
+
+/// The energy one step of an amphipod `A` to `D` takes.
+fn energy(amphipod: u8) -> usize {
+    10usize.pow((amphipod - b'A') as u32)
+}
+
+/// The room the amphipod belongs in.
+fn home(amphipod: u8) -> usize {
+    (amphipod - b'A') as usize
+}
+
+/// Where an amphipod is, rooms count their places from the top.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Place {
+    Hallway(usize),
+    Room(usize, usize),
+}
+
+impl fmt::Display for Place {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Place::Hallway(x) => write!(f, "hallway {}", x + 1),
+            Place::Room(room, _) => write!(f, "room {}", room + 1),
+        }
+    }
+}
+
+/// The amphipods as `A` to `D`, `.` for empty places.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+struct Burrow {
+    hallway: [u8; HALLWAY_LENGTH],
+    rooms: [[u8; MAX_DEPTH]; ROOMS],
+    depth: usize,
+}
+
+impl Burrow {
+    fn parse(lines: &readfile::Lines) -> Result<Burrow, ParseError> {
+        let l: Vec<readfile::Line> = lines.numbered().collect();
+        let expect = |index: usize, text: &str| match l.get(index) {
+            Some(line) if line.text().trim_end() == text => Ok(()),
+            Some(line) => Err(line.error(1, &format!("expected '{}'", text))),
+            None => Err(lines.error_at_end(&format!("expected '{}'", text))),
+        };
+        expect(0, "#############")?;
+        let hallway_line = l
+            .get(1)
+            .ok_or_else(|| lines.error_at_end("expected the hallway"))?;
+        let hallway: Vec<u8> = hallway_line.text().bytes().collect();
+        if hallway.len() != HALLWAY_LENGTH + 2 || hallway[0] != b'#' || hallway[12] != b'#' {
+            return Err(hallway_line.error(1, "expected a hallway like #...........#"));
+        }
+        let mut burrow = Burrow {
+            hallway: [EMPTY; HALLWAY_LENGTH],
+            rooms: [[EMPTY; MAX_DEPTH]; ROOMS],
+            depth: l.len().saturating_sub(3),
+        };
+        for (x, c) in hallway[1..=HALLWAY_LENGTH].iter().enumerate() {
+            burrow.hallway[x] = parse_place(*hallway_line, x + 2, *c)?;
+        }
+
+        if burrow.depth != 2 && burrow.depth != MAX_DEPTH {
+            return Err(lines.error_at_end("expected 2 or 4 rows of rooms"));
+        }
+        for slot in 0..burrow.depth {
+            let line = l[2 + slot];
+            let (outside, inside) = if slot == 0 {
+                ("###", "###")
+            } else {
+                ("  #", "#")
+            };
+            let mut expected = String::from(outside);
+            for room in 0..ROOMS {
+                let c = line
+                    .text()
+                    .as_bytes()
+                    .get(outside.len() + 2 * room)
+                    .copied();
+                let c = c.ok_or_else(|| line.error(1, "expected a row of rooms"))?;
+                let column = outside.len() + 2 * room + 1;
+                burrow.rooms[room][slot] = parse_place(line, column, c)?;
+                if c == EMPTY && slot > 0 && burrow.rooms[room][slot - 1] != EMPTY {
+                    return Err(line.error(column, "expected no empty place below an amphipod"));
+                }
+                expected.push(c as char);
+                expected.push('#');
+            }
+            expected.pop();
+            expected.push_str(inside);
+            if line.text().trim_end() != expected {
+                return Err(line.error(1, &format!("expected a row of rooms like {}", expected)));
+            }
+        }
+        expect(2 + burrow.depth, "  #########")?;
+
+        for amphipod in b'A'..=b'D' {
+            let places = burrow.hallway.iter().chain(burrow.rooms.iter().flatten());
+            if places.filter(|c| **c == amphipod).count() != burrow.depth {
+                let message = format!("expected {} amphipods of each type", burrow.depth);
+                return Err(lines.error_at_end(&message));
+            }
+        }
+        return Ok(burrow);
+    }
+
+    /// The burrow of part 2, with the two folded rows in the middle of the rooms.
+    fn unfold(&self) -> Burrow {
+        let mut unfolded = self.clone();
+        for (room, cells) in unfolded.rooms.iter_mut().enumerate() {
+            *cells = [
+                self.rooms[room][0],
+                FOLDED_ROWS[0][room],
+                FOLDED_ROWS[1][room],
+                self.rooms[room][1],
+            ];
+        }
+        unfolded.depth = MAX_DEPTH;
+        return unfolded;
+    }
+
+    fn room(&self, room: usize) -> &[u8] {
+        &self.rooms[room][..self.depth]
+    }
+
+    fn get(&self, place: Place) -> u8 {
+        match place {
+            Place::Hallway(x) => self.hallway[x],
+            Place::Room(room, slot) => self.rooms[room][slot],
+        }
+    }
+
+    fn set(&mut self, place: Place, value: u8) {
+        match place {
+            Place::Hallway(x) => self.hallway[x] = value,
+            Place::Room(room, slot) => self.rooms[room][slot] = value,
+        }
+    }
+
+    /// Whether only amphipods belonging there are in the room, so others may enter.
+    fn is_ready(&self, room: usize) -> bool {
+        self.room(room)
+            .iter()
+            .all(|c| *c == EMPTY || home(*c) == room)
+    }
+
+    fn is_organized(&self) -> bool {
+        (0..ROOMS).all(|room| {
+            self.room(room)
+                .iter()
+                .all(|c| *c != EMPTY && home(*c) == room)
+        })
+    }
+
+    /// Whether the hallway between `from` and `to` is empty, not looking at `from` itself.
+    fn is_clear(&self, from: usize, to: usize) -> bool {
+        let between = if from < to {
+            from + 1..=to
+        } else {
+            to..=from - 1
+        };
+        self.hallway[between].iter().all(|c| *c == EMPTY)
+    }
+
+    /// The burrow after moving the amphipod at `from` to `to`, with the energy used.
+    fn moved(&self, from: Place, to: Place) -> (Burrow, usize) {
+        let amphipod = self.get(from);
+        let steps = |place: Place| match place {
+            Place::Hallway(x) => (x, 0),
+            Place::Room(room, slot) => (entrance(room), slot + 1),
+        };
+        let ((from_x, from_depth), (to_x, to_depth)) = (steps(from), steps(to));
+        let distance = from_x.abs_diff(to_x) + from_depth + to_depth;
+        let mut next = self.clone();
+        next.set(from, EMPTY);
+        next.set(to, amphipod);
+        return (next, distance * energy(amphipod));
+    }
+
+    /// The moves the rules allow. An amphipod that can go home right away is always moved
+    /// first, as that never makes the solution worse.
+    fn moves(&self) -> Vec<(Burrow, usize)> {
+        for x in 0..HALLWAY_LENGTH {
+            let amphipod = self.hallway[x];
+            if amphipod == EMPTY {
+                continue;
+            }
+            let room = home(amphipod);
+            if self.is_ready(room) && self.is_clear(x, entrance(room)) {
+                let slot = self.room(room).iter().rposition(|c| *c == EMPTY).unwrap();
+                return vec![self.moved(Place::Hallway(x), Place::Room(room, slot))];
+            }
+        }
+
+        let mut moves = vec![];
+        for room in 0..ROOMS {
+            if self.is_ready(room) {
+                continue;
+            }
+            let slot = self.room(room).iter().position(|c| *c != EMPTY).unwrap();
+            for x in 0..HALLWAY_LENGTH {
+                let is_entrance = (0..ROOMS).any(|r| entrance(r) == x);
+                if !is_entrance && self.is_clear(entrance(room), x) && self.hallway[x] == EMPTY {
+                    moves.push(self.moved(Place::Room(room, slot), Place::Hallway(x)));
+                }
+            }
+        }
+        return moves;
+    }
+
+    /// A lower bound of the energy still needed: every amphipod that has to move walks
+    /// straight home, ignoring the others in its way.
+    fn min_energy(&self) -> usize {
+        let mut total = 0;
+        for (x, amphipod) in self.hallway.iter().enumerate() {
+            if *amphipod != EMPTY {
+                total += (x.abs_diff(entrance(home(*amphipod))) + 1) * energy(*amphipod);
+            }
+        }
+        for room in 0..ROOMS {
+            for (slot, amphipod) in self.room(room).iter().enumerate() {
+                if *amphipod == EMPTY {
+                    continue;
+                }
+                let below = &self.room(room)[slot + 1..];
+                let blocks_others = below.iter().any(|c| *c != EMPTY && home(*c) != room);
+                let steps = if home(*amphipod) != room {
+                    slot + 1 + entrance(room).abs_diff(entrance(home(*amphipod))) + 1
+                } else if blocks_others {
+                    // out of the way and back
+                    slot + 1 + 2 + 1
+                } else {
+                    0
+                };
+                total += steps * energy(*amphipod);
+            }
+        }
+        return total;
+    }
+
+    /// The cheapest way to move all amphipods into their rooms, `None` if amphipods in the
+    /// hallway block each other for good.
+    fn organize(&self) -> Option<Path<Burrow>> {
+        search::astar(
+            self.clone(),
+            Burrow::moves,
+            Burrow::min_energy,
+            Burrow::is_organized,
+        )
+    }
+
+    /// The energy of [`Burrow::organize`] as the answer.
+    fn answer(&self) -> Answer {
+        match self.organize() {
+            Some(path) => path.cost.into(),
+            None => Answer::Unsolved,
+        }
+    }
+}
+
+fn parse_place(line: readfile::Line, column: usize, c: u8) -> Result<u8, ParseError> {
+    match c {
+        b'A'..=b'D' | EMPTY => Ok(c),
+        _ => Err(line.error(
+            column,
+            &format!("expected A to D or '.', found '{}'", c as char),
+        )),
+    }
+}
+
+/// The amphipod that moved between two burrows and where from and to, `None` if they differ
+/// by more than one move.
+fn find_move(before: &Burrow, after: &Burrow) -> Option<(u8, Place, Place)> {
+    let places = (0..HALLWAY_LENGTH).map(Place::Hallway).chain(
+        (0..ROOMS).flat_map(|room| (0..before.depth).map(move |slot| Place::Room(room, slot))),
+    );
+    let changed: Vec<Place> = places.filter(|p| before.get(*p) != after.get(*p)).collect();
+    let (from, to) = match changed[..] {
+        [a, b] if after.get(a) == EMPTY => (a, b),
+        [a, b] => (b, a),
+        _ => return None,
+    };
+    return Some((before.get(from), from, to));
+}
+
+impl fmt::Display for Burrow {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "#############")?;
+        writeln!(f, "#{}#", String::from_utf8_lossy(&self.hallway))?;
+        for slot in 0..self.depth {
+            let row: Vec<String> = (0..ROOMS)
+                .map(|room| (self.rooms[room][slot] as char).to_string())
+                .collect();
+            if slot == 0 {
+                writeln!(f, "###{}###", row.join("#"))?;
+            } else {
+                writeln!(f, "  #{}#", row.join("#"))?;
+            }
+        }
+        write!(f, "  #########")
+    }
+}
+
+pub struct Day23 {
+    burrow: Burrow,
+}
+
+impl Solver for Day23 {
+    fn parse(lines: &readfile::Lines) -> Result<Day23, ParseError> {
+        Ok(Day23 {
+            burrow: Burrow::parse(lines)?,
+        })
+    }
+
+    fn part1(&self) -> Answer {
+        self.burrow.answer()
+    }
+
+    /// Burrows that already have 4 rows are organized as they are.
+    fn part2(&self) -> Answer {
+        let burrow = if self.burrow.depth == 2 {
+            self.burrow.unfold()
+        } else {
+            self.burrow.clone()
+        };
+        burrow.answer()
+    }
+
+    /// The cheapest sequence of moves for the burrow of the input, a frame per move.
+    fn visualize(&self, animation: &mut Animation) -> bool {
+        let Some(path) = self.burrow.organize() else {
+            animation.frame(
+                "the amphipods cannot be organized",
+                &self.burrow.to_string(),
+            );
+            return true;
+        };
+        animation.frame("start", &self.burrow.to_string());
+        let mut total = 0;
+        for (i, pair) in path.states.windows(2).enumerate() {
+            // the burrows of a path are a single move apart
+            let Some((amphipod, from, to)) = find_move(&pair[0], &pair[1]) else {
+                break;
+            };
+            let distance = pair[0].moved(from, to).1;
+            total += distance;
+            let caption = format!(
+                "move {}: {} from {} to {}, {} energy ({} in total)",
+                i + 1,
+                amphipod as char,
+                from,
+                to,
+                distance,
+                total
+            );
+            animation.frame(&caption, &pair[1].to_string());
+        }
+        true
+    }
+
+    /// The amphipods of two rows in random rooms and an empty hallway, the size is ignored.
+    fn generate(rng: &mut Rng, _size: usize) -> Option<String> {
+        let mut amphipods = *b"AABBCCDD";
+        rng.shuffle(&mut amphipods);
+        let mut burrow = Burrow {
+            hallway: [EMPTY; HALLWAY_LENGTH],
+            rooms: [[EMPTY; MAX_DEPTH]; ROOMS],
+            depth: 2,
+        };
+        for (i, amphipod) in amphipods.into_iter().enumerate() {
+            burrow.rooms[i % ROOMS][i / ROOMS] = amphipod;
+        }
+        Some(burrow.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generate;
+
+    const EXAMPLE: &str = "\
+#############
+#...........#
+###B#C#B#D###
+  #A#D#C#A#
+  #########";
+
+    #[test]
+    fn example() {
+        let day = Day23::parse(&readfile::Lines::from(EXAMPLE)).unwrap();
+        assert_eq!(day.part1(), Answer::Number(12521));
+        assert_eq!(day.part2(), Answer::Number(44169));
+    }
+
+    #[test]
+    fn unfolded_burrow_has_four_rows() {
+        let day = Day23::parse(&readfile::Lines::from(EXAMPLE)).unwrap();
+        let unfolded = day.burrow.unfold().to_string();
+        assert_eq!(
+            unfolded,
+            "\
+#############
+#...........#
+###B#C#B#D###
+  #D#C#B#A#
+  #D#B#A#C#
+  #A#D#C#A#
+  #########"
+        );
+        let reparsed = Day23::parse(&readfile::Lines::from(unfolded.as_str())).unwrap();
+        assert_eq!(reparsed.part1(), Answer::Number(44169));
+    }
+
+    #[test]
+    fn visualize_prints_the_moves() {
+        let day = Day23::parse(&readfile::Lines::from(EXAMPLE)).unwrap();
+        let mut animation = Animation::recording();
+        assert!(day.visualize(&mut animation));
+        let frames = animation.recorded();
+        assert_eq!(frames[0], format!("start\n{}", EXAMPLE));
+        let last = frames.last().unwrap();
+        assert!(last.lines().next().unwrap().ends_with("(12521 in total)"));
+        assert!(last.ends_with("###A#B#C#D###\n  #A#B#C#D#\n  #########"));
+    }
+
+    #[test]
+    fn min_energy_is_a_lower_bound() {
+        let day = Day23::parse(&readfile::Lines::from(EXAMPLE)).unwrap();
+        let path = day.burrow.organize().unwrap();
+        let mut remaining = path.cost;
+        for pair in path.states.windows(2) {
+            assert!(pair[0].min_energy() <= remaining);
+            let (_, from, to) = find_move(&pair[0], &pair[1]).unwrap();
+            remaining -= pair[0].moved(from, to).1;
+        }
+        assert_eq!(remaining, 0);
+    }
+
+    /// Every move the rules allow, without moving amphipods home first.
+    fn all_moves(burrow: &Burrow) -> Vec<(Burrow, usize)> {
+        let mut moves = vec![];
+        for x in 0..HALLWAY_LENGTH {
+            let amphipod = burrow.hallway[x];
+            if amphipod == EMPTY {
+                continue;
+            }
+            let room = home(amphipod);
+            if burrow.is_ready(room) && burrow.is_clear(x, entrance(room)) {
+                let slot = burrow.room(room).iter().rposition(|c| *c == EMPTY).unwrap();
+                moves.push(burrow.moved(Place::Hallway(x), Place::Room(room, slot)));
+            }
+        }
+        for room in 0..ROOMS {
+            let Some(slot) = burrow.room(room).iter().position(|c| *c != EMPTY) else {
+                continue;
+            };
+            if burrow.is_ready(room) {
+                continue;
+            }
+            for x in [0, 1, 3, 5, 7, 9, 10] {
+                if burrow.hallway[x] == EMPTY && burrow.is_clear(entrance(room), x) {
+                    moves.push(burrow.moved(Place::Room(room, slot), Place::Hallway(x)));
+                }
+            }
+        }
+        return moves;
+    }
+
+    #[test]
+    fn matches_dijkstra() {
+        generate::check_answers::<Day23>(
+            0..4,
+            |_| 0,
+            |day, _| {
+                let path = search::dijkstra(day.burrow.clone(), all_moves, Burrow::is_organized);
+                [Some(path.unwrap().cost.into()), None]
+            },
+        );
+    }
+
+    #[test]
+    fn blocked_hallway() {
+        let blocked = "\
+#############
+#.....D.A...#
+###.#B#C#.###
+  #A#B#C#D#
+  #########";
+        let day = Day23::parse(&readfile::Lines::from(blocked)).unwrap();
+        assert_eq!(day.part1(), Answer::Unsolved);
+        assert_eq!(day.part2(), Answer::Unsolved);
+        let mut animation = Animation::recording();
+        assert!(day.visualize(&mut animation));
+        assert_eq!(
+            animation.recorded(),
+            vec![format!("the amphipods cannot be organized\n{}", blocked)]
+        );
+    }
+
+    #[test]
+    fn invalid_burrows() {
+        let parse = |s: &str| Day23::parse(&readfile::Lines::from(s)).err().unwrap();
+        let err = parse(&EXAMPLE.replace("#A#D", "#E#D"));
+        assert_eq!((err.line, err.column), (4, 4));
+        let err = parse(&EXAMPLE.replace("#A#D", "#A#A"));
+        assert_eq!(err.message, "expected 2 amphipods of each type");
+        let err = parse("#############\n#...........#\n  #########");
+        assert_eq!(err.message, "expected 2 or 4 rows of rooms");
+    }
+}
@@ -1,31 +1,90 @@
+use crate::answer::Answer;
+use crate::error::ParseError;
 use crate::readfile;
+use crate::solver::Solver;
+use lazy_static::lazy_static;
 use regex::Regex;
 use std::collections::{HashSet, VecDeque};
 
-type Registers = [HashSet<isize>; 4];
+/// The possible values of the w, x, y and z registers.
+pub type Registers = [HashSet<isize>; 4];
 
-fn get_register_index(register: &str) -> usize {
+#[derive(Clone, Copy)]
+pub enum Operand {
+    Register(usize),
+    Value(isize),
+}
+
+/// A single ALU instruction, registers are indices into [`Registers`].
+#[derive(Clone, Copy)]
+pub enum Instruction {
+    Inp(usize),
+    Add(usize, Operand),
+    Mul(usize, Operand),
+    Div(usize, Operand),
+    Mod(usize, Operand),
+    Eql(usize, Operand),
+}
+
+fn get_register_index(register: &str) -> Option<usize> {
     match register {
-        "w" => 0,
-        "x" => 1,
-        "y" => 2,
-        "z" => 3,
-        _ => panic!("Invalid register: {}", register),
+        "w" => Some(0),
+        "x" => Some(1),
+        "y" => Some(2),
+        "z" => Some(3),
+        _ => None,
+    }
+}
+
+fn parse_instruction(line: readfile::Line) -> Result<Instruction, ParseError> {
+    lazy_static! {
+        static ref RE: Regex = Regex::new("^(\\w+) (\\S+)(?: (\\S+))?$").unwrap();
     }
+    let groups = line.captures(&RE)?;
+    let register = |part: &str| {
+        get_register_index(part)
+            .ok_or_else(|| line.error_at(part, &format!("invalid register '{}'", part)))
+    };
+    let r1 = register(&groups[2])?;
+    let op2 = match groups.get(3) {
+        Some(m) => match get_register_index(m.as_str()) {
+            Some(r) => Some(Operand::Register(r)),
+            None => Some(Operand::Value(line.parse_field(m.as_str())?)),
+        },
+        None => None,
+    };
+
+    let instruction = match (&groups[1], op2) {
+        ("inp", None) => Instruction::Inp(r1),
+        ("add", Some(op2)) => Instruction::Add(r1, op2),
+        ("mul", Some(op2)) => Instruction::Mul(r1, op2),
+        ("div", Some(op2)) => Instruction::Div(r1, op2),
+        ("mod", Some(op2)) => Instruction::Mod(r1, op2),
+        ("eql", Some(op2)) => Instruction::Eql(r1, op2),
+        ("inp", Some(_)) | ("add" | "mul" | "div" | "mod" | "eql", None) => {
+            return Err(line.error(1, &format!("wrong number of operands for '{}'", &groups[1])));
+        }
+        (op, _) => return Err(line.error(1, &format!("invalid operation '{}'", op))),
+    };
+    return Ok(instruction);
 }
 
-fn perform_operation<F>(registers: &mut Registers, op1: &str, op2: &str, f: F)
+pub fn parse_program(lines: &readfile::Lines) -> Result<Vec<Instruction>, ParseError> {
+    lines.numbered().map(parse_instruction).collect()
+}
+
+fn perform_operation<F>(registers: &mut Registers, op1: usize, op2: Operand, f: F)
 where
     F: Fn(isize, isize) -> isize,
 {
-    let r1 = &registers[get_register_index(op1)];
+    let r1 = &registers[op1];
     let mut temp_hs = HashSet::new();
-    let r2: &HashSet<isize>;
-    if ["w", "x", "y", "z"].contains(&op2) {
-        r2 = &registers[get_register_index(op2)];
-    } else {
-        temp_hs.insert(op2.parse().unwrap());
-        r2 = &temp_hs;
+    let r2: &HashSet<isize> = match op2 {
+        Operand::Register(r) => &registers[r],
+        Operand::Value(v) => {
+            temp_hs.insert(v);
+            &temp_hs
+        }
     };
 
     let mut result: HashSet<isize> = HashSet::new();
@@ -35,11 +94,11 @@ where
         }
     }
 
-    registers[get_register_index(op1)] = result;
+    registers[op1] = result;
 }
 
-fn run_program(lines: &readfile::Lines, inputs: Vec<HashSet<isize>>) -> Registers {
-    let re = Regex::new("(\\w+) ([wxyz]|-?\\d+) ?([wxyz]|-?\\d+)?").unwrap();
+/// Runs an ALU program on sets of possible inputs, tracking every value a register can hold.
+pub fn run_program(program: &[Instruction], inputs: Vec<HashSet<isize>>) -> Registers {
     let mut registers: Registers = [
         HashSet::from_iter([0]),
         HashSet::from_iter([0]),
@@ -48,99 +107,167 @@ fn run_program(lines: &readfile::Lines, inputs: Vec<HashSet<isize>>) -> Register
     ];
     let mut input_index = 0;
 
-    for l in lines.lines() {
-        let groups = re.captures(l).unwrap();
-        if &groups[1] == "inp" {
-            registers[get_register_index(&groups[2])] = inputs[input_index].clone();
-            input_index += 1;
-        } else {
-            let f: fn(isize, isize) -> isize = match &groups[1] {
-                "add" => |a, b| a + b,
-                "mul" => |a, b| a * b,
-                "div" => |a, b| a / b,
-                "mod" => |a, b| a % b,
-                "eql" => |a, b| {
-                    if a == b {
-                        1
-                    } else {
-                        0
-                    }
-                },
-                _ => panic!("Invalid operation: {}", &groups[1]),
-            };
-            perform_operation(&mut registers, &groups[2], &groups[3], f);
+    for instruction in program {
+        match *instruction {
+            Instruction::Inp(r) => {
+                registers[r] = inputs[input_index].clone();
+                input_index += 1;
+            }
+            Instruction::Add(r, op2) => perform_operation(&mut registers, r, op2, |a, b| a + b),
+            Instruction::Mul(r, op2) => perform_operation(&mut registers, r, op2, |a, b| a * b),
+            Instruction::Div(r, op2) => perform_operation(&mut registers, r, op2, |a, b| a / b),
+            Instruction::Mod(r, op2) => perform_operation(&mut registers, r, op2, |a, b| a % b),
+            Instruction::Eql(r, op2) => perform_operation(&mut registers, r, op2, |a, b| {
+                if a == b {
+                    1
+                } else {
+                    0
+                }
+            }),
         }
     }
 
     return registers;
 }
 
-fn part1(lines: &readfile::Lines) {
-    let mut found_numbers: Vec<isize> = vec![];
-    for x in 0..14 {
-        for i in (0..=9).rev() {
-            if i == 0 {
-                panic!("Could not find value for {}", x);
-            }
-            let mut inputs: Vec<HashSet<isize>> = vec![];
-            for n in &found_numbers {
-                inputs.push(HashSet::from_iter([*n]));
-            }
-            inputs.push(HashSet::from_iter([i]));
-            while inputs.len() < 14 {
-                inputs.push(HashSet::from_iter(1..=9));
-            }
-            let result = run_program(lines, inputs);
-            if result[3].contains(&0) {
-                found_numbers.push(i);
-                break;
+pub struct Day24 {
+    program: Vec<Instruction>,
+}
+
+impl Solver for Day24 {
+    fn parse(lines: &readfile::Lines) -> Result<Day24, ParseError> {
+        Ok(Day24 {
+            program: parse_program(lines)?,
+        })
+    }
+
+    /// Unsolved if the program accepts no model number.
+    fn part1(&self) -> Answer {
+        let mut found_numbers: Vec<isize> = vec![];
+        for _ in 0..14 {
+            for i in (0..=9).rev() {
+                if i == 0 {
+                    return Answer::Unsolved;
+                }
+                let mut inputs: Vec<HashSet<isize>> = vec![];
+                for n in &found_numbers {
+                    inputs.push(HashSet::from_iter([*n]));
+                }
+                inputs.push(HashSet::from_iter([i]));
+                while inputs.len() < 14 {
+                    inputs.push(HashSet::from_iter(1..=9));
+                }
+                let result = run_program(&self.program, inputs);
+                if result[3].contains(&0) {
+                    found_numbers.push(i);
+                    break;
+                }
             }
         }
-    }
-    println!(
-        "{}",
         found_numbers
             .iter()
             .map(|v| v.to_string())
             .collect::<Vec<String>>()
             .join("")
-    );
-}
+            .into()
+    }
 
-fn part2(lines: &readfile::Lines) {
-    let mut found_numbers: VecDeque<isize> = VecDeque::new();
-    for x in (0..14).rev() {
-        for i in 1..=10 {
-            if i == 10 {
-                panic!("Could not find value for {}", x);
-            }
-            let mut inputs: Vec<HashSet<isize>> = vec![];
-            while inputs.len() < 14 - 1 - found_numbers.len() {
-                inputs.push(HashSet::from_iter(1..=9));
-            }
-            inputs.push(HashSet::from_iter([i]));
-            for n in &found_numbers {
-                inputs.push(HashSet::from_iter([*n]));
-            }
-            let result = run_program(lines, inputs);
-            if result[3].contains(&0) {
-                found_numbers.push_front(i);
-                break;
+    /// Unsolved if the program accepts no model number.
+    fn part2(&self) -> Answer {
+        let mut found_numbers: VecDeque<isize> = VecDeque::new();
+        for _ in (0..14).rev() {
+            for i in 1..=10 {
+                if i == 10 {
+                    return Answer::Unsolved;
+                }
+                let mut inputs: Vec<HashSet<isize>> = vec![];
+                while inputs.len() < 14 - 1 - found_numbers.len() {
+                    inputs.push(HashSet::from_iter(1..=9));
+                }
+                inputs.push(HashSet::from_iter([i]));
+                for n in &found_numbers {
+                    inputs.push(HashSet::from_iter([*n]));
+                }
+                let result = run_program(&self.program, inputs);
+                if result[3].contains(&0) {
+                    found_numbers.push_front(i);
+                    break;
+                }
             }
         }
-    }
-    println!(
-        "{}",
         found_numbers
             .iter()
             .map(|v| v.to_string())
             .collect::<Vec<String>>()
             .join("")
-    );
+            .into()
+    }
 }
 
-pub fn run() {
-    let lines = readfile::Lines::new("day24.txt");
-    part1(&lines);
-    part2(&lines);
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn program(s: &str) -> Vec<Instruction> {
+        parse_program(&readfile::Lines::from(s)).unwrap()
+    }
+
+    fn single(values: &[isize]) -> Vec<HashSet<isize>> {
+        values.iter().map(|v| HashSet::from_iter([*v])).collect()
+    }
+
+    #[test]
+    fn binary_conversion() {
+        let binary = program(
+            "\
+inp w
+add z w
+mod z 2
+div w 2
+add y w
+mod y 2
+div w 2
+add x w
+mod x 2
+div w 2
+mod w 2",
+        );
+        let registers = run_program(&binary, single(&[13]));
+        let bits: Vec<isize> = registers
+            .iter()
+            .map(|r| *r.iter().next().unwrap())
+            .collect();
+        assert_eq!(bits, vec![1, 1, 0, 1]);
+    }
+
+    #[test]
+    fn three_times_larger() {
+        let compare = program("inp z\ninp x\nmul z 3\neql z x");
+        assert!(run_program(&compare, single(&[3, 9]))[3].contains(&1));
+        assert!(run_program(&compare, single(&[3, 8]))[3].contains(&0));
+    }
+
+    #[test]
+    fn registers_track_all_possible_values() {
+        let negate = program("inp x\nmul x -1");
+        let registers = run_program(&negate, vec![HashSet::from_iter(1..=3)]);
+        assert_eq!(registers[1], HashSet::from_iter([-1, -2, -3]));
+    }
+
+    #[test]
+    fn no_model_number_is_valid() {
+        let day = Day24 {
+            program: program("inp w\nadd z w"),
+        };
+        assert_eq!(day.part1(), Answer::Unsolved);
+        assert_eq!(day.part2(), Answer::Unsolved);
+    }
+
+    #[test]
+    fn invalid_instructions() {
+        let parse = |s: &str| parse_program(&readfile::Lines::from(s)).err().unwrap();
+        assert_eq!(parse("inp w\nadd q 1").column, 5);
+        assert_eq!(parse("inp w x").message, "wrong number of operands for 'inp'");
+        assert_eq!(parse("sub w 1").message, "invalid operation 'sub'");
+    }
 }
@@ -0,0 +1,252 @@
+use crate::answer::Answer;
+use crate::error::ParseError;
+use crate::generate::Rng;
+use crate::grid::Grid;
+use crate::readfile;
+use crate::solver::Solver;
+use crate::visualize::Animation;
+use colored::*;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Cell {
+    Empty,
+    East,
+    South,
+}
+
+type Seafloor = Grid<Cell>;
+
+/// Herds that still move after this many steps are taken to never stop, the ones of the
+/// puzzle stop after a few hundred.
+const MAX_STEPS: usize = 10_000;
+
+fn parse_cell(line: readfile::Line, i: usize, c: char) -> Result<Cell, ParseError> {
+    match c {
+        '.' => Ok(Cell::Empty),
+        '>' => Ok(Cell::East),
+        'v' => Ok(Cell::South),
+        _ => Err(line.error(i + 1, &format!("expected '>', 'v' or '.', found '{}'", c))),
+    }
+}
+
+/// Moves every cucumber of `herd` whose next place, wrapping around the edges, was empty
+/// before any of them moved. Marks the places they moved to in `moved`.
+fn move_herd(seafloor: &Seafloor, herd: Cell, moved: &mut Grid<bool>) -> Seafloor {
+    let mut next = seafloor.clone();
+    for (p, cell) in seafloor.iter() {
+        if *cell != herd {
+            continue;
+        }
+        let target = match herd {
+            Cell::East => [(p[0] + 1) % seafloor.width(), p[1]],
+            _ => [p[0], (p[1] + 1) % seafloor.height()],
+        };
+        if seafloor[target] == Cell::Empty {
+            next[p] = Cell::Empty;
+            next[target] = herd;
+            moved[target] = true;
+        }
+    }
+    return next;
+}
+
+/// One step, the east-facing herd moves first. The grid tells which cucumbers moved.
+fn step(seafloor: &Seafloor) -> (Seafloor, Grid<bool>) {
+    let mut moved = Grid::new(seafloor.width(), seafloor.height(), false);
+    let seafloor = move_herd(seafloor, Cell::East, &mut moved);
+    let seafloor = move_herd(&seafloor, Cell::South, &mut moved);
+    return (seafloor, moved);
+}
+
+/// The first step in which no cucumber moves, `None` if they still move after `MAX_STEPS`.
+fn steps_until_stopped(seafloor: &Seafloor) -> Option<usize> {
+    let mut seafloor = seafloor.clone();
+    for steps in 1..=MAX_STEPS {
+        let (next, moved) = step(&seafloor);
+        if moved.values().all(|m| !m) {
+            return Some(steps);
+        }
+        seafloor = next;
+    }
+    return None;
+}
+
+/// The cucumbers that just moved are bright, the others dim.
+fn render(seafloor: &Seafloor, moved: &Grid<bool>) -> String {
+    seafloor.render(|p, cell| {
+        let c = match cell {
+            Cell::Empty => return String::from("."),
+            Cell::East => ">".green(),
+            Cell::South => "v".cyan(),
+        };
+        if moved[p] {
+            c.bold().to_string()
+        } else {
+            c.dimmed().to_string()
+        }
+    })
+}
+
+pub struct Day25 {
+    seafloor: Seafloor,
+}
+
+impl Solver for Day25 {
+    fn parse(lines: &readfile::Lines) -> Result<Day25, ParseError> {
+        Ok(Day25 {
+            seafloor: Grid::parse(lines, parse_cell)?,
+        })
+    }
+
+    fn part1(&self) -> Answer {
+        match steps_until_stopped(&self.seafloor) {
+            Some(steps) => steps.into(),
+            None => Answer::Unsolved,
+        }
+    }
+
+    /// The last day has a single puzzle.
+    fn part2(&self) -> Answer {
+        Answer::Unsolved
+    }
+
+    /// The herds until they stop or for `MAX_STEPS`, the cucumbers that moved in a step are
+    /// bright.
+    fn visualize(&self, animation: &mut Animation) -> bool {
+        let mut seafloor = self.seafloor.clone();
+        let mut moved = Grid::new(seafloor.width(), seafloor.height(), false);
+        animation.frame("initial state", &render(&seafloor, &moved));
+        let mut steps = 0;
+        while steps < MAX_STEPS && (steps == 0 || moved.values().any(|m| *m)) {
+            steps += 1;
+            (seafloor, moved) = step(&seafloor);
+            let count = moved.values().filter(|m| **m).count();
+            let caption = format!("step {}: {} moved", steps, count);
+            animation.frame(&caption, &render(&seafloor, &moved));
+        }
+        true
+    }
+
+    /// A `size` x `size` seafloor (at least 2 x 2), a bit more than half of it cucumbers like
+    /// in the puzzle. Seafloors on which the herds never stop are thrown away.
+    fn generate(rng: &mut Rng, size: usize) -> Option<String> {
+        let size = size.max(2);
+        loop {
+            let seafloor = Grid::from_fn(size, size, |_| match rng.below(20) {
+                0..=5 => Cell::East,
+                6..=11 => Cell::South,
+                _ => Cell::Empty,
+            });
+            if steps_until_stopped(&seafloor).is_some() {
+                return Some(seafloor.render(|_, cell| {
+                    String::from(match cell {
+                        Cell::Empty => ".",
+                        Cell::East => ">",
+                        Cell::South => "v",
+                    })
+                }));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generate;
+
+    const EXAMPLE: &str = "\
+v...>>.vv>
+.vv>>.vv..
+>>.>v>...v
+>>v>>.>.v.
+v>v.vv.v..
+>.>>..v...
+.vv..>.>v.
+v.v..>>v.v
+....v..v.>";
+
+    #[test]
+    fn example() {
+        let day = Day25::parse(&readfile::Lines::from(EXAMPLE)).unwrap();
+        assert_eq!(day.part1(), Answer::Number(58));
+    }
+
+    #[test]
+    fn east_herd_moves_first_and_wraps() {
+        let seafloor = Grid::parse(&readfile::Lines::from("...>>>>>..."), parse_cell).unwrap();
+        let (seafloor, _) = step(&seafloor);
+        let (seafloor, _) = step(&seafloor);
+        let text =
+            seafloor.render(|_, cell| String::from(if *cell == Cell::East { ">" } else { "." }));
+        assert_eq!(text, "...>>>.>.>.");
+
+        let lines = readfile::Lines::from("..........\n.>v....v..\n.......>..\n..........");
+        let (seafloor, moved) = step(&Grid::parse(&lines, parse_cell).unwrap());
+        // the first cucumber facing east is blocked by one facing south that has not moved yet
+        assert_eq!(seafloor[[1, 1]], Cell::East);
+        assert_eq!(seafloor[[2, 2]], Cell::South);
+        assert_eq!(seafloor[[7, 2]], Cell::South);
+        assert_eq!(seafloor[[8, 2]], Cell::East);
+        assert_eq!(moved.values().filter(|m| **m).count(), 3);
+    }
+
+    /// The steps on rows of characters, every cucumber of a herd moves if the place in front
+    /// of it was empty before the herd moved.
+    fn brute_force(input: &str) -> usize {
+        let mut rows: Vec<Vec<u8>> = input.lines().map(|l| l.bytes().collect()).collect();
+        let (width, height) = (rows[0].len(), rows.len());
+        let mut steps = 0;
+        loop {
+            steps += 1;
+            let mut moved = false;
+            for (herd, dx, dy) in [(b'>', 1, 0), (b'v', 0, 1)] {
+                let before = rows.clone();
+                for y in 0..height {
+                    for x in 0..width {
+                        let (tx, ty) = ((x + dx) % width, (y + dy) % height);
+                        if before[y][x] == herd && before[ty][tx] == b'.' {
+                            rows[y][x] = b'.';
+                            rows[ty][tx] = herd;
+                            moved = true;
+                        }
+                    }
+                }
+            }
+            if !moved {
+                return steps;
+            }
+        }
+    }
+
+    #[test]
+    fn matches_brute_force() {
+        generate::check_answers::<Day25>(
+            0..10,
+            |seed| 2 + 3 * seed as usize,
+            |_, input| [Some(brute_force(input).into()), None],
+        );
+    }
+
+    #[test]
+    fn herds_that_never_stop() {
+        let day = Day25::parse(&readfile::Lines::from(">.")).unwrap();
+        assert_eq!(day.part1(), Answer::Unsolved);
+        let mut animation = Animation::recording();
+        assert!(day.visualize(&mut animation));
+        assert_eq!(animation.recorded().len(), MAX_STEPS + 1);
+    }
+
+    #[test]
+    fn visualize_shows_every_step() {
+        let day = Day25::parse(&readfile::Lines::from(EXAMPLE)).unwrap();
+        let mut animation = Animation::recording();
+        assert!(day.visualize(&mut animation));
+        let frames = animation.recorded();
+        assert_eq!(frames.len(), 59);
+        assert!(frames[0].starts_with("initial state\n"));
+        assert!(frames[57].starts_with("step 57: "));
+        assert!(!frames[57].starts_with("step 57: 0 moved"));
+        assert!(frames[58].starts_with("step 58: 0 moved\n"));
+    }
+}
@@ -1,7 +1,11 @@
+use crate::answer::Answer;
+use crate::error::ParseError;
+use crate::generate::Rng;
 use crate::readfile;
+use crate::solver::Solver;
 
-fn get_length(lines: &readfile::Lines) -> usize {
-    lines.lines().next().unwrap().len()
+fn get_length(lines: &[String]) -> usize {
+    lines[0].len()
 }
 
 fn occurences(lines: &mut dyn std::iter::Iterator<Item = &str>, length: usize) -> Vec<[u32; 2]> {
@@ -10,70 +14,180 @@ fn occurences(lines: &mut dyn std::iter::Iterator<Item = &str>, length: usize) -
         occ.push([0, 0]);
     }
 
+    // parsing only accepts the bits '0' and '1'
     for line in lines {
         for (i, c) in line.chars().enumerate() {
-            match c {
-                '0' => occ[i][0] += 1,
-                '1' => occ[i][1] += 1,
-                _ => println!("Invalid char {}", c),
-            }
+            let bit = if c == '1' { 1 } else { 0 };
+            occ[i][bit] += 1;
         }
     }
 
     occ
 }
 
-fn part1(lines: &readfile::Lines) {
-    let length = get_length(lines);
-    let occ = occurences(&mut lines.lines(), length);
-    let mut gamma: u32 = 0;
-    for v in occ.iter() {
-        gamma <<= 1;
-        if v[1] > v[0] {
-            gamma += 1;
-        }
-    }
-    let epsilon = (!gamma) & (u32::pow(2, occ.len().try_into().unwrap()) - 1);
-    println!("Part 1: {}", gamma * epsilon);
-}
-
-fn get_rating<C>(lines: &readfile::Lines, bit_criteria: C) -> &str
+fn get_rating<C>(lines: &[String], bit_criteria: C) -> &str
 where
     C: Fn(u32, u32) -> char,
 {
     let length = get_length(lines);
-    let mut relevant_lines: Vec<&str> = lines.lines().collect();
+    let mut relevant_lines: Vec<&str> = lines.iter().map(|l| l.as_str()).collect();
     let mut index: usize = 0;
     while relevant_lines.len() > 1 {
         let mut iter = relevant_lines.clone().into_iter();
         let occ = occurences(&mut iter, length);
         let target = bit_criteria(occ[index][0], occ[index][1]);
-        relevant_lines = relevant_lines
-            .into_iter()
-            .filter(|l| {
+        // a bit that all remaining numbers share cannot tell them apart, it is skipped
+        if occ[index][0] > 0 && occ[index][1] > 0 {
+            relevant_lines.retain(|l| {
                 let chars: Vec<char> = l.chars().collect();
                 chars[index] == target
-            })
-            .collect();
+            });
+        }
         index += 1;
     }
     relevant_lines[0]
 }
 
-fn part2(lines: &readfile::Lines) {
-    let oxygen_crit = |zeros: u32, ones: u32| if ones >= zeros { '1' } else { '0' };
-    let oxygen_rating_str = get_rating(lines, oxygen_crit);
-    let oxygen_rating = isize::from_str_radix(oxygen_rating_str, 2).unwrap();
+pub struct Day3 {
+    numbers: Vec<String>,
+}
+
+impl Solver for Day3 {
+    fn parse(lines: &readfile::Lines) -> Result<Day3, ParseError> {
+        let mut numbers: Vec<String> = Vec::new();
+        for l in lines.numbered() {
+            let mut chars = l.text().chars().enumerate();
+            if let Some((i, c)) = chars.find(|(_, c)| *c != '0' && *c != '1') {
+                return Err(l.error(i + 1, &format!("invalid bit '{}'", c)));
+            }
+            if let Some(first) = numbers.first() {
+                if l.text().len() != first.len() {
+                    return Err(l.error(1, &format!("expected {} bits", first.len())));
+                }
+            }
+            numbers.push(String::from(l.text()));
+        }
+        if numbers.is_empty() {
+            return Err(lines.error_at_end("expected at least one number"));
+        }
+        Ok(Day3 { numbers })
+    }
+
+    fn part1(&self) -> Answer {
+        let length = get_length(&self.numbers);
+        let occ = occurences(&mut self.numbers.iter().map(|l| l.as_str()), length);
+        let mut gamma: u32 = 0;
+        for v in occ.iter() {
+            gamma <<= 1;
+            if v[1] > v[0] {
+                gamma += 1;
+            }
+        }
+        let epsilon = (!gamma) & (u32::pow(2, occ.len().try_into().unwrap()) - 1);
+        (gamma * epsilon).into()
+    }
+
+    fn part2(&self) -> Answer {
+        let oxygen_crit = |zeros: u32, ones: u32| if ones >= zeros { '1' } else { '0' };
+        let oxygen_rating_str = get_rating(&self.numbers, oxygen_crit);
+        let oxygen_rating = isize::from_str_radix(oxygen_rating_str, 2).unwrap();
 
-    let co2_crit = |zeros: u32, ones: u32| if zeros <= ones { '0' } else { '1' };
-    let co2_rating_str = get_rating(lines, co2_crit);
-    let co2_rating = isize::from_str_radix(co2_rating_str, 2).unwrap();
+        let co2_crit = |zeros: u32, ones: u32| if zeros <= ones { '0' } else { '1' };
+        let co2_rating_str = get_rating(&self.numbers, co2_crit);
+        let co2_rating = isize::from_str_radix(co2_rating_str, 2).unwrap();
 
-    println!("Part 2: {}", oxygen_rating * co2_rating);
+        (oxygen_rating * co2_rating).into()
+    }
+
+    /// `size` distinct numbers (at least 2), with enough bits to leave some numbers out.
+    fn generate(rng: &mut Rng, size: usize) -> Option<String> {
+        let count = size.max(2);
+        let bits = (2 * count).ilog2() as usize + 2;
+        let mut values: Vec<usize> = (0..1 << bits).collect();
+        rng.shuffle(&mut values);
+        let numbers: Vec<String> = values[..count]
+            .iter()
+            .map(|v| format!("{:0width$b}", v, width = bits))
+            .collect();
+        Some(numbers.join("\n"))
+    }
 }
 
-pub fn run() {
-    let lines = readfile::Lines::new("day3.txt");
-    part1(&lines);
-    part2(&lines);
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generate;
+
+    const EXAMPLE: &str = "\
+00100
+11110
+10110
+10111
+10101
+01111
+00111
+11100
+10000
+11001
+00010
+01010";
+
+    #[test]
+    fn example() {
+        let day = Day3::parse(&readfile::Lines::from(EXAMPLE)).unwrap();
+        assert_eq!(day.part1(), Answer::Number(198));
+        assert_eq!(day.part2(), Answer::Number(230));
+    }
+
+    /// The rating found by keeping the numbers with the `keep` bit at each position, where
+    /// `keep` decides from the numbers of zeros and ones.
+    fn brute_force_rating(
+        numbers: &[usize],
+        bits: usize,
+        keep: fn(usize, usize) -> usize,
+    ) -> usize {
+        let mut numbers = numbers.to_vec();
+        for i in (0..bits).rev() {
+            if numbers.len() == 1 {
+                break;
+            }
+            let ones = numbers.iter().filter(|n| (*n >> i) & 1 == 1).count();
+            if ones == 0 || ones == numbers.len() {
+                continue;
+            }
+            let bit = keep(numbers.len() - ones, ones);
+            numbers.retain(|n| (n >> i) & 1 == bit);
+        }
+        return numbers[0];
+    }
+
+    #[test]
+    fn matches_brute_force() {
+        generate::check_answers::<Day3>(
+            0..20,
+            |seed| 2 + seed as usize * 3,
+            |day, _| {
+                let bits = day.numbers[0].len();
+                let numbers: Vec<usize> = day
+                    .numbers
+                    .iter()
+                    .map(|n| usize::from_str_radix(n, 2).unwrap())
+                    .collect();
+
+                let mut gamma = 0;
+                for i in 0..bits {
+                    let ones = numbers.iter().filter(|n| (*n >> i) & 1 == 1).count();
+                    if 2 * ones > numbers.len() {
+                        gamma |= 1 << i;
+                    }
+                }
+                let epsilon = !gamma & ((1 << bits) - 1);
+
+                let oxygen =
+                    brute_force_rating(&numbers, bits, |zeros, ones| (ones >= zeros) as usize);
+                let co2 = brute_force_rating(&numbers, bits, |zeros, ones| (ones < zeros) as usize);
+                [Some((gamma * epsilon).into()), Some((oxygen * co2).into())]
+            },
+        );
+    }
 }
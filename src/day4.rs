@@ -1,26 +1,32 @@
+use crate::answer::Answer;
+use crate::error::ParseError;
+use crate::generate::Rng;
 use crate::readfile;
+use crate::solver::Solver;
 
+#[derive(Clone)]
 struct Board {
     nums: [[u8; 5]; 5],
     marked: [[bool; 5]; 5],
 }
 
 impl Board {
-    fn new(lines: &[&str], range: std::ops::Range<usize>) -> Board {
+    fn new(lines: &[readfile::Line]) -> Result<Board, ParseError> {
         let mut nums: [[u8; 5]; 5] = [[0; 5]; 5];
-        for (local_index, i) in range.enumerate() {
-            let values: [u8; 5] = lines[i]
+        for (local_index, line) in lines.iter().enumerate() {
+            let values = line
+                .text()
                 .split_whitespace()
-                .map(|v| v.parse::<u8>().unwrap())
-                .collect::<Vec<u8>>()
+                .map(|v| line.parse_field::<u8>(v))
+                .collect::<Result<Vec<u8>, _>>()?;
+            nums[local_index] = values
                 .try_into()
-                .unwrap();
-            nums[local_index] = values;
+                .map_err(|_| line.error(1, "expected 5 numbers"))?;
         }
-        return Board {
+        return Ok(Board {
             nums,
             marked: [[false; 5]; 5],
-        };
+        });
     }
 
     fn mark_value(self: &mut Board, value: u8) {
@@ -60,15 +66,21 @@ impl Board {
     }
 }
 
+#[derive(Clone)]
 struct Game {
     values: Vec<u8>,
     boards: Vec<Board>,
 }
 
 impl Game {
-    fn new(lines: &readfile::Lines) -> Game {
-        let l: Vec<&str> = lines.lines().collect();
-        let values = l[0].split(',').map(|v| v.parse::<u8>().unwrap()).collect();
+    fn new(lines: &readfile::Lines) -> Result<Game, ParseError> {
+        let l: Vec<readfile::Line> = lines.numbered().collect();
+        let first = lines.first()?;
+        let values = first
+            .text()
+            .split(',')
+            .map(|v| first.parse_field::<u8>(v))
+            .collect::<Result<_, _>>()?;
         let mut game = Game {
             boards: Vec::new(),
             values,
@@ -76,12 +88,18 @@ impl Game {
 
         let mut index = 1;
         while index < l.len() {
+            if !l[index].is_empty() {
+                return Err(l[index].error(1, "expected an empty line between boards"));
+            }
             index += 1;
-            game.boards.push(Board::new(&l, index..index + 5));
+            if index + 5 > l.len() {
+                return Err(lines.error_at_end("incomplete board, expected 5 rows"));
+            }
+            game.boards.push(Board::new(&l[index..index + 5])?);
             index += 5;
         }
 
-        game
+        Ok(game)
     }
 }
 
@@ -97,42 +115,158 @@ fn get_score(board: &Board, value: u8) -> u32 {
     return sum * u32::from(value);
 }
 
-fn part1(lines: &readfile::Lines) {
-    let mut game = Game::new(lines);
-    for value in game.values {
-        for board in &mut game.boards {
-            board.mark_value(value);
-            if board.has_won() {
-                println!("Part 1: {}", get_score(board, value));
-                return;
+pub struct Day4 {
+    game: Game,
+}
+
+impl Solver for Day4 {
+    fn parse(lines: &readfile::Lines) -> Result<Day4, ParseError> {
+        Ok(Day4 {
+            game: Game::new(lines)?,
+        })
+    }
+
+    /// Unsolved if no board wins with the drawn numbers.
+    fn part1(&self) -> Answer {
+        let mut game = self.game.clone();
+        for value in game.values {
+            for board in &mut game.boards {
+                board.mark_value(value);
+                if board.has_won() {
+                    return get_score(board, value).into();
+                }
             }
         }
+        Answer::Unsolved
     }
-}
 
-fn part2(lines: &readfile::Lines) {
-    let mut game = Game::new(lines);
-    let total_boards = game.boards.len();
-    let mut won_board_indices: Vec<usize> = Vec::new();
-    for value in game.values {
-        for (i, board) in game.boards.iter_mut().enumerate() {
-            if won_board_indices.iter().any(|bi| *bi == i) {
-                continue;
-            }
-            board.mark_value(value);
-            if board.has_won() {
-                won_board_indices.push(i);
-                if won_board_indices.len() == total_boards {
-                    println!("Part 2: {}", get_score(board, value));
-                    return;
+    /// Unsolved if some board never wins.
+    fn part2(&self) -> Answer {
+        let mut game = self.game.clone();
+        let total_boards = game.boards.len();
+        let mut won_board_indices: Vec<usize> = Vec::new();
+        for value in game.values {
+            for (i, board) in game.boards.iter_mut().enumerate() {
+                if won_board_indices.contains(&i) {
+                    continue;
+                }
+                board.mark_value(value);
+                if board.has_won() {
+                    won_board_indices.push(i);
+                    if won_board_indices.len() == total_boards {
+                        return get_score(board, value).into();
+                    }
                 }
             }
         }
+        Answer::Unsolved
+    }
+
+    /// `size` boards with distinct numbers below 100, all numbers are drawn.
+    fn generate(rng: &mut Rng, size: usize) -> Option<String> {
+        let mut values: Vec<usize> = (0..100).collect();
+        rng.shuffle(&mut values);
+        let values: Vec<String> = values.iter().map(|v| v.to_string()).collect();
+        let mut input = values.join(",");
+        for _ in 0..size.max(1) {
+            let mut numbers: Vec<usize> = (0..100).collect();
+            rng.shuffle(&mut numbers);
+            input.push('\n');
+            for row in numbers[..25].chunks(5) {
+                let row: Vec<String> = row.iter().map(|n| format!("{:>2}", n)).collect();
+                input.push('\n');
+                input.push_str(&row.join(" "));
+            }
+        }
+        Some(input)
     }
 }
 
-pub fn run() {
-    let lines = readfile::Lines::new("day4.txt");
-    part1(&lines);
-    part2(&lines);
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generate;
+
+    const EXAMPLE: &str = "\
+7,4,9,5,11,17,23,2,0,14,21,24,10,16,13,6,15,25,12,22,18,20,8,19,3,26,1
+
+22 13 17 11  0
+ 8  2 23  4 24
+21  9 14 16  7
+ 6 10  3 18  5
+ 1 12 20 15 19
+
+ 3 15  0  2 22
+ 9 18 13 17  5
+19  8  7 25 23
+20 11 10 24  4
+14 21 16 12  6
+
+14 21 17 24  4
+10 16 15  9 19
+18  8 23 26 20
+22 11 13  6  5
+ 2  0 12  3  7";
+
+    #[test]
+    fn example() {
+        let day = Day4::parse(&readfile::Lines::from(EXAMPLE)).unwrap();
+        assert_eq!(day.part1(), Answer::Number(4512));
+        assert_eq!(day.part2(), Answer::Number(1924));
+    }
+
+    #[test]
+    fn no_board_wins() {
+        let (_, boards) = EXAMPLE.split_once('\n').unwrap();
+        let input = format!("7,4,9,5\n{}", boards);
+        let day = Day4::parse(&readfile::Lines::from(input.as_str())).unwrap();
+        assert_eq!(day.part1(), Answer::Unsolved);
+        assert_eq!(day.part2(), Answer::Unsolved);
+    }
+
+    /// The number of drawn values after which `board` has a complete row or column, and its
+    /// score at that point.
+    fn brute_force_win(board: &Board, values: &[u8]) -> Option<(usize, u32)> {
+        let lines = (0..5).flat_map(|i| {
+            [
+                (0..5).map(|j| board.nums[i][j]).collect::<Vec<u8>>(),
+                (0..5).map(|j| board.nums[j][i]).collect::<Vec<u8>>(),
+            ]
+        });
+        let turn_of = |n: &u8| values.iter().position(|v| v == n);
+        // a line is complete after its last number, never if one is not drawn
+        let turn = lines
+            .filter_map(|line| line.iter().map(turn_of).collect::<Option<Vec<usize>>>())
+            .map(|turns| turns.into_iter().max().unwrap())
+            .min()?;
+        let drawn = &values[..=turn];
+        let unmarked: u32 = board
+            .nums
+            .iter()
+            .flatten()
+            .filter(|n| !drawn.contains(n))
+            .map(|n| *n as u32)
+            .sum();
+        Some((turn, unmarked * values[turn] as u32))
+    }
+
+    #[test]
+    fn matches_brute_force() {
+        generate::check_answers::<Day4>(
+            0..50,
+            |seed| 1 + seed as usize % 8,
+            |day, _| {
+                let wins: Vec<(usize, u32)> = day
+                    .game
+                    .boards
+                    .iter()
+                    .map(|b| brute_force_win(b, &day.game.values).unwrap())
+                    .collect();
+                // ties go to the first board for the first winner, to the last for the last one
+                let (_, first) = wins.iter().min_by_key(|(turn, _)| *turn).unwrap();
+                let (_, last) = wins.iter().max_by_key(|(turn, _)| *turn).unwrap();
+                [Some((*first).into()), Some((*last).into())]
+            },
+        );
+    }
 }
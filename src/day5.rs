@@ -1,4 +1,10 @@
+use crate::answer::Answer;
+use crate::error::ParseError;
+use crate::generate::Rng;
+use crate::grid::Grid;
+use crate::netpbm::Image;
 use crate::readfile;
+use crate::solver::Solver;
 use regex::Regex;
 use std::cmp::Ordering;
 use std::collections::HashMap;
@@ -22,27 +28,19 @@ impl Line {
     }
 }
 
-fn abs_diff(a: u16, b: u16) -> u16 {
-    if a > b {
-        a - b
-    } else {
-        b - a
-    }
-}
-
-fn parse_lines(str_lines: &readfile::Lines) -> Vec<Line> {
-    let reg: Regex = Regex::new("(\\d+),(\\d+) -> (\\d+),(\\d+)").unwrap();
+fn parse_lines(str_lines: &readfile::Lines) -> Result<Vec<Line>, ParseError> {
+    let reg: Regex = Regex::new("^(\\d+),(\\d+) -> (\\d+),(\\d+)$").unwrap();
     let mut lines = Vec::new();
-    for line in str_lines.lines() {
-        let groups = reg.captures(line).unwrap();
+    for line in str_lines.numbered() {
+        let groups = line.captures(&reg)?;
         lines.push(Line {
-            x1: groups[1].parse().unwrap(),
-            y1: groups[2].parse().unwrap(),
-            x2: groups[3].parse().unwrap(),
-            y2: groups[4].parse().unwrap(),
+            x1: line.parse_field(&groups[1])?,
+            y1: line.parse_field(&groups[2])?,
+            x2: line.parse_field(&groups[3])?,
+            y2: line.parse_field(&groups[4])?,
         });
     }
-    return lines;
+    return Ok(lines);
 }
 
 fn line_to_points(line: &Line) -> Vec<Point> {
@@ -61,16 +59,19 @@ fn line_to_points(line: &Line) -> Vec<Point> {
     };
 
     let steps = match step_x {
-        0 => abs_diff(line.y1, line.y2) + 1,
-        _ => abs_diff(line.x1, line.x2) + 1,
+        0 => line.y1.abs_diff(line.y2) + 1,
+        _ => line.x1.abs_diff(line.x2) + 1,
     };
 
     let mut x = line.x1;
     let mut y = line.y1;
-    for _ in 0..steps {
+    for i in 0..steps {
+        // only step after the first point, lines may end at coordinate 0
+        if i > 0 {
+            x = x.checked_add_signed(step_x).unwrap();
+            y = y.checked_add_signed(step_y).unwrap();
+        }
         points.push(Point { x, y });
-        x = x.checked_add_signed(step_x).unwrap();
-        y = y.checked_add_signed(step_y).unwrap();
     }
 
     return points;
@@ -95,9 +96,145 @@ fn get_intersecting_point_count(lines: &[Line], include_diagonals: bool) -> usiz
     return points.values().filter(|v| **v > 1).count();
 }
 
-pub fn run() {
-    let str_lines = readfile::Lines::new("day5.txt");
-    let lines = parse_lines(&str_lines);
-    println!("Part 1: {}", get_intersecting_point_count(&lines, false));
-    println!("Part 2: {}", get_intersecting_point_count(&lines, true));
+/// The number of lines at each point, including the diagonal ones.
+fn vent_map(lines: &[Line]) -> Grid<u16> {
+    let width = lines.iter().map(|l| l.x1.max(l.x2)).max().unwrap_or(0) as usize + 1;
+    let height = lines.iter().map(|l| l.y1.max(l.y2)).max().unwrap_or(0) as usize + 1;
+    let mut map = Grid::new(width, height, 0);
+    for l in lines {
+        for p in line_to_points(l) {
+            map[[p.x as usize, p.y as usize]] += 1;
+        }
+    }
+    return map;
+}
+
+pub struct Day5 {
+    lines: Vec<Line>,
+}
+
+impl Solver for Day5 {
+    fn parse(lines: &readfile::Lines) -> Result<Day5, ParseError> {
+        Ok(Day5 {
+            lines: parse_lines(lines)?,
+        })
+    }
+
+    fn part1(&self) -> Answer {
+        get_intersecting_point_count(&self.lines, false).into()
+    }
+
+    fn part2(&self) -> Answer {
+        get_intersecting_point_count(&self.lines, true).into()
+    }
+
+    /// `size` horizontal, vertical and diagonal lines with coordinates up to `size` (at least
+    /// 10), so that they overlap.
+    fn generate(rng: &mut Rng, size: usize) -> Option<String> {
+        let max = size.max(10) as isize;
+        let lines: Vec<String> = (0..size.max(1))
+            .map(|_| {
+                let (x1, y1) = (rng.range(0..=max), rng.range(0..=max));
+                let (x2, y2) = match rng.below(3) {
+                    0 => (rng.range(0..=max), y1),
+                    1 => (x1, rng.range(0..=max)),
+                    _ => {
+                        let (sx, sy) = (*rng.choose(&[-1, 1]), *rng.choose(&[-1, 1]));
+                        let room = |v: isize, s: isize| if s > 0 { max - v } else { v };
+                        let length = rng.range(0..=room(x1, sx).min(room(y1, sy)));
+                        (x1 + sx * length, y1 + sy * length)
+                    }
+                };
+                format!("{},{} -> {},{}", x1, y1, x2, y2)
+            })
+            .collect();
+        Some(lines.join("\n"))
+    }
+
+    /// A heat map of the vents, the more lines overlap the brighter.
+    fn image(&self) -> Option<Image> {
+        Some(Image::graymap(vent_map(&self.lines)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generate;
+
+    const EXAMPLE: &str = "\
+0,9 -> 5,9
+8,0 -> 0,8
+9,4 -> 3,4
+2,2 -> 2,1
+7,0 -> 7,4
+6,4 -> 2,0
+0,9 -> 2,9
+3,4 -> 1,4
+0,0 -> 8,8
+5,5 -> 8,2";
+
+    #[test]
+    fn example() {
+        let day = Day5::parse(&readfile::Lines::from(EXAMPLE)).unwrap();
+        assert_eq!(day.part1(), Answer::Number(5));
+        assert_eq!(day.part2(), Answer::Number(12));
+    }
+
+    #[test]
+    fn heat_map() {
+        let day = Day5::parse(&readfile::Lines::from(EXAMPLE)).unwrap();
+        let map = vent_map(&day.lines);
+        assert_eq!((map.width(), map.height()), (10, 10));
+        assert_eq!(map.row(4), &[0, 1, 1, 2, 3, 1, 3, 2, 1, 1]);
+        assert_eq!(map.values().filter(|v| **v > 1).count(), 12);
+    }
+
+    /// Counts the points covered by more than one line, testing every point against every line.
+    fn brute_force(lines: &[Line], include_diagonals: bool) -> usize {
+        let covers = |l: &Line, x: isize, y: isize| {
+            let [x1, y1, x2, y2] = [l.x1, l.y1, l.x2, l.y2].map(|v| v as isize);
+            let length = (x2 - x1).abs().max((y2 - y1).abs());
+            let (sx, sy) = ((x2 - x1).signum(), (y2 - y1).signum());
+            (0..=length).any(|k| x1 + k * sx == x && y1 + k * sy == y)
+        };
+        let mut count = 0;
+        for x in 0..=20 {
+            for y in 0..=20 {
+                let n = lines
+                    .iter()
+                    .filter(|l| include_diagonals || !l.is_diagonal())
+                    .filter(|l| covers(l, x, y))
+                    .count();
+                if n > 1 {
+                    count += 1;
+                }
+            }
+        }
+        return count;
+    }
+
+    #[test]
+    fn matches_brute_force() {
+        generate::check_answers::<Day5>(
+            0..30,
+            |seed| 5 + seed as usize % 16,
+            |day, _| {
+                let expected = |diagonals| brute_force(&day.lines, diagonals).into();
+                [Some(expected(false)), Some(expected(true))]
+            },
+        );
+    }
+
+    #[test]
+    fn diagonal_line_points() {
+        let line = Line {
+            x1: 9,
+            y1: 7,
+            x2: 7,
+            y2: 9,
+        };
+        let points: Vec<[u16; 2]> = line_to_points(&line).iter().map(|p| [p.x, p.y]).collect();
+        assert_eq!(points, vec![[9, 7], [8, 8], [7, 9]]);
+    }
 }
@@ -1,47 +1,137 @@
+use crate::answer::Answer;
+use crate::error::ParseError;
+use crate::generate::Rng;
 use crate::readfile;
+use crate::solver::{Param, Params, Solver};
+use num::BigUint;
 
-type State = [u64; 9];
+/// The number of fish with each timer. The population grows exponentially, after about 440
+/// days it no longer fits into a u64.
+type State = [BigUint; 9];
 
-fn parse_state(line: &str) -> State {
-    let mut state: State = [0; 9];
-    let timers = line.split(',').map(|v| v.parse::<usize>().unwrap());
-    for t in timers {
-        // let's just ignore that an easy access out of bounds is possible here
-        state[t] += 1;
+fn parse_state(line: readfile::Line) -> Result<State, ParseError> {
+    let mut state: State = Default::default();
+    for v in line.text().split(',') {
+        let t: usize = line.parse_field(v)?;
+        if t >= state.len() {
+            return Err(line.error_at(v, &format!("timer {} is out of range 0..=8", t)));
+        }
+        state[t] += 1u32;
     }
-    return state;
+    return Ok(state);
 }
 
 fn tick(state: &mut State) {
-    let num_fish_to_add = state[0];
-    for i in 1..9 {
-        state[i - 1] = state[i];
-        state[i] = 0;
-    }
-    state[6] += num_fish_to_add;
-    state[8] += num_fish_to_add;
+    // the fish with timer 0 move to 8 as new fish, their parents restart at 6
+    state.rotate_left(1);
+    let parents = state[8].clone();
+    state[6] += parents;
 }
 
-fn part1(lines: &readfile::Lines) {
-    let mut state = parse_state(lines.lines().next().unwrap());
-    for _ in 0..80 {
-        tick(&mut state);
-    }
-    let num_fish: u64 = state.into_iter().sum();
-    println!("Part 1: {}", num_fish);
+fn count_fish(state: &State) -> Answer {
+    state.iter().sum::<BigUint>().into()
+}
+
+pub struct Day6 {
+    state: State,
+    days: Option<usize>,
 }
 
-fn part2(lines: &readfile::Lines) {
-    let mut state = parse_state(lines.lines().next().unwrap());
-    for _ in 0..256 {
-        tick(&mut state);
+impl Solver for Day6 {
+    fn parse(lines: &readfile::Lines) -> Result<Day6, ParseError> {
+        Ok(Day6 {
+            state: parse_state(lines.first()?)?,
+            days: None,
+        })
+    }
+
+    fn part1(&self) -> Answer {
+        let mut state = self.state.clone();
+        for _ in 0..self.days.unwrap_or(80) {
+            tick(&mut state);
+        }
+        count_fish(&state)
+    }
+
+    fn part2(&self) -> Answer {
+        let mut state = self.state.clone();
+        for _ in 0..self.days.unwrap_or(256) {
+            tick(&mut state);
+        }
+        count_fish(&state)
+    }
+
+    fn params() -> &'static [Param] {
+        &[Param {
+            name: "days",
+            help: "number of simulated days (80 in part 1, 256 in part 2)",
+        }]
+    }
+
+    fn configure(&mut self, params: &Params) -> Result<(), String> {
+        self.days = params.get("days")?;
+        Ok(())
+    }
+
+    /// `size` fish with timers from 1 to 5, like in the puzzle.
+    fn generate(rng: &mut Rng, size: usize) -> Option<String> {
+        let timers: Vec<String> = (0..size.max(1))
+            .map(|_| rng.range(1..=5).to_string())
+            .collect();
+        Some(timers.join(","))
     }
-    let num_fish: u64 = state.into_iter().sum();
-    println!("Part 2: {}", num_fish);
 }
 
-pub fn run() {
-    let lines = readfile::Lines::new("day6.txt");
-    part1(&lines);
-    part2(&lines);
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generate;
+
+    const EXAMPLE: &str = "3,4,3,1,2";
+
+    #[test]
+    fn example() {
+        let day = Day6::parse(&readfile::Lines::from(EXAMPLE)).unwrap();
+        assert_eq!(day.part1(), Answer::Number(5934));
+        assert_eq!(day.part2(), Answer::Number(26984457539));
+    }
+
+    #[test]
+    fn tick_resets_timers() {
+        let mut state = parse_state(readfile::Line::from(EXAMPLE)).unwrap();
+        for _ in 0..18 {
+            tick(&mut state);
+        }
+        assert_eq!(count_fish(&state), Answer::Number(26));
+    }
+
+    #[test]
+    fn matches_brute_force() {
+        generate::check_answers::<Day6>(
+            0..10,
+            |seed| 1 + seed as usize,
+            |_, input| {
+                // every fish on its own
+                let mut fish: Vec<usize> = input.split(',').map(|t| t.parse().unwrap()).collect();
+                for _ in 0..80 {
+                    let born = fish.iter().filter(|t| **t == 0).count();
+                    for t in fish.iter_mut() {
+                        *t = if *t == 0 { 6 } else { *t - 1 };
+                    }
+                    fish.extend(std::iter::repeat_n(8, born));
+                }
+                [Some(fish.len().into()), None]
+            },
+        );
+    }
+
+    #[test]
+    fn counts_beyond_i64() {
+        let mut day = Day6::parse(&readfile::Lines::from(EXAMPLE)).unwrap();
+        let mut params = Params::default();
+        params.insert("days=1000").unwrap();
+        day.configure(&params).unwrap();
+        let expected = "379589061144698259131825683795505058481";
+        assert_eq!(day.part2(), Answer::Text(String::from(expected)));
+    }
 }
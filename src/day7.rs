@@ -1,9 +1,14 @@
+use crate::answer::Answer;
+use crate::error::ParseError;
+use crate::generate::Rng;
 use crate::readfile;
+use crate::solver::Solver;
 
-fn parse(s: &str) -> Vec<usize> {
-    s.split(',')
-        .map(|v| v.parse::<usize>().unwrap())
-        .collect::<Vec<usize>>()
+fn parse(line: readfile::Line) -> Result<Vec<usize>, ParseError> {
+    line.text()
+        .split(',')
+        .map(|v| line.parse_field::<usize>(v))
+        .collect::<Result<Vec<usize>, _>>()
 }
 
 fn calculate_optimum(positions: &[usize], fuel_fn: impl Fn(usize, usize) -> usize) -> usize {
@@ -19,22 +24,75 @@ fn calculate_optimum(positions: &[usize], fuel_fn: impl Fn(usize, usize) -> usiz
     return min_fuel;
 }
 
-fn part1(positions: &[usize]) {
-    let min_fuel = calculate_optimum(positions, |p, t| p.abs_diff(t));
-    println!("Part 1: {}", min_fuel);
+pub struct Day7 {
+    positions: Vec<usize>,
 }
 
-fn part2(positions: &[usize]) {
-    let min_fuel = calculate_optimum(positions, |p, t| {
-        let d = p.abs_diff(t);
-        (d * (d + 1)) / 2
-    });
-    println!("Part 2: {}", min_fuel);
+impl Solver for Day7 {
+    fn parse(lines: &readfile::Lines) -> Result<Day7, ParseError> {
+        Ok(Day7 {
+            positions: parse(lines.first()?)?,
+        })
+    }
+
+    fn part1(&self) -> Answer {
+        let min_fuel = calculate_optimum(&self.positions, |p, t| p.abs_diff(t));
+        min_fuel.into()
+    }
+
+    fn part2(&self) -> Answer {
+        let min_fuel = calculate_optimum(&self.positions, |p, t| {
+            let d = p.abs_diff(t);
+            (d * (d + 1)) / 2
+        });
+        min_fuel.into()
+    }
+
+    /// `size` crabs at positions up to twice their number, like in the puzzle.
+    fn generate(rng: &mut Rng, size: usize) -> Option<String> {
+        let max = 2 * size.max(1) as isize;
+        let positions: Vec<String> = (0..size.max(1))
+            .map(|_| rng.range(0..=max).to_string())
+            .collect();
+        Some(positions.join(","))
+    }
 }
 
-pub fn run() {
-    let lines = readfile::Lines::new("day7.txt");
-    let positions = parse(lines.lines().next().unwrap());
-    part1(&positions);
-    part2(&positions);
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generate;
+
+    const EXAMPLE: &str = "16,1,2,0,4,2,7,1,2,14";
+
+    #[test]
+    fn example() {
+        let day = Day7::parse(&readfile::Lines::from(EXAMPLE)).unwrap();
+        assert_eq!(day.part1(), Answer::Number(37));
+        assert_eq!(day.part2(), Answer::Number(168));
+    }
+
+    /// The cheapest position is the median for a constant fuel rate, and within half a step
+    /// of the mean for an increasing rate.
+    #[test]
+    fn matches_median_and_mean() {
+        generate::check_answers::<Day7>(
+            0..20,
+            |seed| 1 + seed as usize * 7,
+            |day, _| {
+                let mut positions = day.positions.clone();
+                positions.sort_unstable();
+                let median = positions[positions.len() / 2];
+                let part1: usize = positions.iter().map(|p| p.abs_diff(median)).sum();
+
+                let fuel = |target: usize| -> usize {
+                    let d = |p: &usize| p.abs_diff(target);
+                    positions.iter().map(|p| d(p) * (d(p) + 1) / 2).sum()
+                };
+                let mean = positions.iter().sum::<usize>() / positions.len();
+                let part2 = fuel(mean).min(fuel(mean + 1));
+                [Some(part1.into()), Some(part2.into())]
+            },
+        );
+    }
 }
@@ -1,4 +1,8 @@
+use crate::answer::Answer;
+use crate::error::ParseError;
+use crate::generate::Rng;
 use crate::readfile;
+use crate::solver::Solver;
 use std::collections::HashMap;
 
 /*
@@ -32,16 +36,6 @@ macro_rules! bit {
     };
 }
 
-fn part1(lines: &readfile::Lines) {
-    let count = lines
-        .lines()
-        .map(|l| l.split(" | ").nth(1).unwrap())
-        .flat_map(|l| l.split_whitespace())
-        .filter(|g| g.len() == 2 || g.len() == 4 || g.len() == 3 || g.len() == 7)
-        .count();
-    println!("Part 1: {}", count);
-}
-
 fn check_without_mask(segment_bitmask: u8, index: usize, test: char, input: &str) -> bool {
     ((segment_bitmask & bit!(index)) != 0) == (input.contains(test))
 }
@@ -64,17 +58,15 @@ fn check_with_mask(segment_bitmask: u8, index: usize, test: char, input: &str) -
     length 8: 8 -> SEG8
 */
 fn is_possible(index: usize, test: char, input: &str) -> bool {
-    let retval = match input.len() {
+    // parsing only accepts patterns of 2 to 7 segments, all 7 are lit for 8 only
+    match input.len() {
         2 => check_without_mask(SEG1, index, test, input),
         3 => check_without_mask(SEG7, index, test, input),
         4 => check_without_mask(SEG4, index, test, input),
         5 => check_with_mask(SEG2 & SEG3 & SEG5, index, test, input),
         6 => check_with_mask(SEG0 & SEG6 & SEG9, index, test, input),
-        7 => true,
-        _ => panic!("Invalid input length '{}'", input),
-    };
-    // println!("{}/{}: {} -> {}", index, test, input, retval);
-    return retval;
+        _ => true,
+    }
 }
 
 #[allow(clippy::needless_range_loop)]
@@ -89,10 +81,11 @@ fn clean_possibilities(possibilities: &mut [Vec<char>; 7]) {
             }
         }
         for i in 0..possibilities.len() {
-            let vec = &possibilities[i];
+            let vec = &mut possibilities[i];
             if vec.len() > 1 {
-                let removed_elements = possibilities[i].drain_filter(|c| fixed.contains(c));
-                if removed_elements.count() > 0 {
+                let before = vec.len();
+                vec.retain(|c| !fixed.contains(c));
+                if vec.len() != before {
                     changed = true;
                 }
             }
@@ -100,19 +93,20 @@ fn clean_possibilities(possibilities: &mut [Vec<char>; 7]) {
     }
 }
 
-fn str_to_num(mappings: &HashMap<char, usize>, s: &str) -> usize {
+fn str_to_num(mappings: &HashMap<char, usize>, s: &str) -> Result<usize, String> {
     let mut bitmask = 0;
     for c in s.chars() {
         let pos = mappings.get(&c).unwrap();
         bitmask |= bit!(pos);
     }
     match SEGS.iter().position(|&seg| seg == bitmask) {
-        Some(value) => return value,
-        None => panic!("Could not find segment for {} (bitmask {})", s, bitmask),
+        Some(value) => Ok(value),
+        None => Err(format!("'{}' is not a digit with this wiring", s)),
     }
 }
 
-fn get_output_value(line: &str) -> usize {
+/// Finds the wiring of an entry from its ten patterns and decodes the output digits.
+fn get_output_value(line: &str) -> Result<usize, String> {
     let split: Vec<&str> = line.split(" | ").collect();
     let inputs: Vec<&str> = split[0].split_whitespace().collect();
     let outputs: Vec<&str> = split[1].split_whitespace().collect();
@@ -129,36 +123,205 @@ fn get_output_value(line: &str) -> usize {
     }
 
     if possibilities.iter().any(|v| v.len() != 1) {
-        println!("{}", line);
-        for i in 0..possibilities.len() {
-            println!("{}: {:?}", i, possibilities[i])
-        }
-        panic!("Could not determine mapping");
+        return Err(String::from("the patterns do not determine the wiring"));
     }
 
     let mut mappings = HashMap::<char, usize>::new();
-    for i in 0..possibilities.len() {
-        mappings.insert(possibilities[i][0], i);
+    for (i, p) in possibilities.iter().enumerate() {
+        mappings.insert(p[0], i);
     }
 
     let mut output_value: usize = 0;
     for output in outputs {
-        output_value = 10 * output_value + str_to_num(&mappings, output);
+        output_value = 10 * output_value + str_to_num(&mappings, output)?;
     }
 
-    return output_value;
+    return Ok(output_value);
 }
 
-fn part2(lines: &readfile::Lines) {
-    let mut sum = 0;
-    for l in lines.lines() {
-        sum += get_output_value(l);
+/// Checks that an entry consists of ten signal patterns and four output digits, and that they
+/// can be decoded.
+fn validate_entry(line: readfile::Line) -> Result<(), ParseError> {
+    let (inputs, outputs) = line
+        .text()
+        .split_once(" | ")
+        .ok_or_else(|| line.error(1, "expected ' | ' between patterns and output"))?;
+    for (part, expected) in [(inputs, 10), (outputs, 4)] {
+        let groups: Vec<&str> = part.split_whitespace().collect();
+        if groups.len() != expected {
+            return Err(line.error_at(part, &format!("expected {} patterns", expected)));
+        }
+        for g in groups {
+            if !(2..=7).contains(&g.len()) || !g.chars().all(|c| ('a'..='g').contains(&c)) {
+                return Err(line.error_at(g, &format!("invalid pattern '{}'", g)));
+            }
+        }
     }
-    println!("Part 2: {}", sum);
+    get_output_value(line.text()).map_err(|err| line.error(1, &err))?;
+    return Ok(());
 }
 
-pub fn run() {
-    let lines = readfile::Lines::new("day8.txt");
-    part1(&lines);
-    part2(&lines);
+pub struct Day8 {
+    entries: Vec<String>,
+}
+
+impl Solver for Day8 {
+    fn parse(lines: &readfile::Lines) -> Result<Day8, ParseError> {
+        let mut entries = Vec::new();
+        for l in lines.numbered() {
+            validate_entry(l)?;
+            entries.push(String::from(l.text()));
+        }
+        Ok(Day8 { entries })
+    }
+
+    fn part1(&self) -> Answer {
+        let count = self
+            .entries
+            .iter()
+            .map(|l| l.split(" | ").nth(1).unwrap())
+            .flat_map(|l| l.split_whitespace())
+            .filter(|g| g.len() == 2 || g.len() == 4 || g.len() == 3 || g.len() == 7)
+            .count();
+        count.into()
+    }
+
+    fn part2(&self) -> Answer {
+        let mut sum = 0;
+        for l in &self.entries {
+            // every entry was decoded once when parsing
+            sum += get_output_value(l).unwrap();
+        }
+        sum.into()
+    }
+
+    /// `size` entries, each with its own random wiring.
+    fn generate(rng: &mut Rng, size: usize) -> Option<String> {
+        let entries: Vec<String> = (0..size.max(1))
+            .map(|_| {
+                let mut wiring = ['a', 'b', 'c', 'd', 'e', 'f', 'g'];
+                rng.shuffle(&mut wiring);
+                let pattern = |rng: &mut Rng, digit: usize| -> String {
+                    let mut letters: Vec<char> = (0..7)
+                        .filter(|i| SEGS[digit] & bit!(i) != 0)
+                        .map(|i| wiring[i])
+                        .collect();
+                    rng.shuffle(&mut letters);
+                    letters.into_iter().collect()
+                };
+                let mut digits: Vec<usize> = (0..10).collect();
+                rng.shuffle(&mut digits);
+                let patterns: Vec<String> = digits.into_iter().map(|d| pattern(rng, d)).collect();
+                let outputs: Vec<String> = (0..4)
+                    .map(|_| {
+                        let digit = rng.below(10);
+                        pattern(rng, digit)
+                    })
+                    .collect();
+                format!("{} | {}", patterns.join(" "), outputs.join(" "))
+            })
+            .collect();
+        Some(entries.join("\n"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generate;
+
+    const EXAMPLE: &str = "\
+be cfbegad cbdgef fgaecd cgeb fdcge agebfd fecdb fabcd edb | fdgacbe cefdb cefbgd gcbe
+edbfga begcd cbg gc gcadebf fbgde acbgfd abcde gfcbed gfec | fcgedb cgb dgebacf gc
+fgaebd cg bdaec gdafb agbcfd gdcbef bgcad gfac gcb cdgabef | cg cg fdcagb cbg
+fbegcd cbd adcefb dageb afcb bc aefdc ecdab fgdeca fcdbega | efabcd cedba gadfec cb
+aecbfdg fbg gf bafeg dbefa fcge gcbea fcaegb dgceab fcbdga | gecf egdcabf bgf bfgea
+fgeab ca afcebg bdacfeg cfaedg gcfdb baec bfadeg bafgc acf | gebdcfa ecba ca fadegcb
+dbcfg fgd bdegcaf fgec aegbdf ecdfab fbedc dacgb gdcebf gf | cefg dcbef fcge gbcadfe
+bdfegc cbegaf gecbf dfcage bdacg ed bedf ced adcbefg gebcd | ed bcgafe cdgba cbgef
+egadfb cdbfeg cegd fecab cgb gbdefca cg fgcdab egfdb bfceg | gbdfcae bgc cg cgb
+gcafb gcf dcaebfg ecagb gf abcdeg gaef cafbge fdbac fegbdc | fgae cfgab fg bagce";
+
+    #[test]
+    fn example() {
+        let day = Day8::parse(&readfile::Lines::from(EXAMPLE)).unwrap();
+        assert_eq!(day.part1(), Answer::Number(26));
+        assert_eq!(day.part2(), Answer::Number(61229));
+    }
+
+    #[test]
+    fn single_entry() {
+        let entry =
+            "acedgfb cdfbe gcdfa fbcad dab cefabd cdfgeb eafb cagedb ab | cdfeb fcadb cdfeb cdbaf";
+        assert_eq!(get_output_value(entry), Ok(5353));
+    }
+
+    /// Decodes an entry by trying every wiring until all patterns are digits.
+    fn brute_force(entry: &str) -> usize {
+        fn permutations(rest: Vec<char>, prefix: Vec<char>, all: &mut Vec<Vec<char>>) {
+            if rest.is_empty() {
+                all.push(prefix);
+                return;
+            }
+            for (i, c) in rest.iter().enumerate() {
+                let mut rest = rest.clone();
+                rest.remove(i);
+                let mut prefix = prefix.clone();
+                prefix.push(*c);
+                permutations(rest, prefix, all);
+            }
+        }
+        let mut wirings = vec![];
+        permutations(
+            vec!['a', 'b', 'c', 'd', 'e', 'f', 'g'],
+            vec![],
+            &mut wirings,
+        );
+
+        let (patterns, outputs) = entry.split_once(" | ").unwrap();
+        let digit = |wiring: &[char], pattern: &str| {
+            let mask = pattern
+                .chars()
+                .map(|c| bit!(wiring.iter().position(|w| *w == c).unwrap()))
+                .fold(0, |mask, bit| mask | bit);
+            SEGS.iter().position(|seg| *seg == mask)
+        };
+        for wiring in wirings {
+            if patterns.split(' ').all(|p| digit(&wiring, p).is_some()) {
+                return outputs
+                    .split(' ')
+                    .fold(0, |value, p| 10 * value + digit(&wiring, p).unwrap());
+            }
+        }
+        panic!("no wiring for {}", entry);
+    }
+
+    #[test]
+    fn matches_brute_force() {
+        generate::check_answers::<Day8>(
+            0..10,
+            |seed| 1 + seed as usize,
+            |_, input| {
+                let expected: usize = input.lines().map(brute_force).sum();
+                [None, Some(expected.into())]
+            },
+        );
+    }
+
+    #[test]
+    fn invalid_entries() {
+        let entry =
+            "be cfbegad cbdgef fgaecd cgeb fdcge agebfd fecdb fabcd e | fdgacbe cefdb cefbgd gcbe";
+        let err = Day8::parse(&readfile::Lines::from(entry)).err().unwrap();
+        assert_eq!(
+            (err.column, err.message.as_str()),
+            (56, "invalid pattern 'e'")
+        );
+
+        // two patterns of 1, so the wiring is unknown
+        let entry =
+            "be cfbegad cbdgef fgaecd cgeb fdcge agebfd fecdb fabcd eb | fdgacbe cefdb cefbgd gcbe";
+        let err = Day8::parse(&readfile::Lines::from(entry)).err().unwrap();
+        assert_eq!(err.message, "the patterns do not determine the wiring");
+    }
 }
@@ -1,118 +1,222 @@
+use crate::answer::Answer;
+use crate::error::ParseError;
+use crate::generate::Rng;
+use crate::grid::{Grid, Point};
+use crate::netpbm::{self, Image};
 use crate::readfile;
+use crate::solver::Solver;
+use crate::visualize::Animation;
 use colored::*;
 
-const PRINT_DEBUG: bool = false;
+type Map = Grid<u32>;
 
-type Point = [usize; 2];
-type Map = Vec<Vec<u32>>;
-
-fn parse(lines: &readfile::Lines) -> Vec<Vec<u32>> {
-    lines
-        .lines()
-        .map(|l| l.chars().map(|c| c.to_digit(10).unwrap()).collect())
-        .collect()
+fn find_lowest_points(map: &Map) -> Vec<Point> {
+    return map
+        .iter()
+        .filter(|(p, height)| map.neighbours4(*p).all(|n| map[n] > **height))
+        .map(|(p, _)| p)
+        .collect();
 }
 
-fn find_lowest_points(map: &Map) -> Vec<Point> {
-    let mut lowest_points: Vec<Point> = Vec::new();
-    for y in 0..map.len() {
-        let vec = &map[y];
-        for x in 0..vec.len() {
-            let val = vec[x];
-            let mut is_lowest = true;
-            if y > 0 && map[y - 1][x] <= val {
-                is_lowest = false;
-            }
-            if y < map.len() - 1 && map[y + 1][x] <= val {
-                is_lowest = false;
-            }
-            if x > 0 && vec[x - 1] <= val {
-                is_lowest = false;
-            }
-            if x < vec.len() - 1 && vec[x + 1] <= val {
-                is_lowest = false;
-            }
-            if is_lowest {
-                lowest_points.push([x, y]);
+/// The points of the basin around `low_point`, bounded by heights of 9.
+fn find_basin(map: &Map, low_point: Point) -> Vec<Point> {
+    let mut visited = Grid::new(map.width(), map.height(), false);
+    visited[low_point] = true;
+    let mut to_visit: Vec<Point> = vec![low_point];
+    let mut basin = Vec::new();
+    while let Some(p) = to_visit.pop() {
+        basin.push(p);
+        for n in map.neighbours4(p) {
+            if map[n] != 9 && !visited[n] {
+                visited[n] = true;
+                to_visit.push(n);
             }
         }
     }
-    return lowest_points;
+    return basin;
 }
 
-fn part1(lines: &readfile::Lines) {
-    let map = parse(lines);
-    let lowest_points = find_lowest_points(&map);
-    let risk_level_sum: u32 = lowest_points.iter().map(|[x, y]| map[*y][*x] + 1).sum();
-
-    if PRINT_DEBUG {
-        for (y, map_y) in map.iter().enumerate() {
-            for (x, v) in map_y.iter().enumerate() {
-                if lowest_points.contains(&[x, y]) {
-                    print!("{}", v.to_string().cyan());
-                } else if *v < 9 {
-                    print!("{}", v.to_string().bright_black());
-                } else {
-                    print!(" ");
-                }
-            }
-            println!();
+/// The map with the low points highlighted, the filled basins in blue and their borders blank.
+fn render(map: &Map, lowest_points: &[Point], filled: &Grid<bool>) -> String {
+    map.render(|p, v| {
+        if lowest_points.contains(&p) {
+            v.to_string().cyan().to_string()
+        } else if filled[p] {
+            v.to_string().blue().to_string()
+        } else if *v < 9 {
+            v.to_string().bright_black().to_string()
+        } else {
+            String::from(" ")
         }
-    }
+    })
+}
 
-    println!("Part 1: {}", risk_level_sum);
+pub struct Day9 {
+    map: Map,
 }
 
-fn find_points_to_visit(
-    map: &Map,
-    visited: &[Point],
-    to_visit: &[Point],
-    [x, y]: Point,
-) -> Vec<Point> {
-    let mut neighbours: Vec<Point> = Vec::new();
-    if y > 0 {
-        neighbours.push([x, y - 1]);
+impl Solver for Day9 {
+    fn parse(lines: &readfile::Lines) -> Result<Day9, ParseError> {
+        Ok(Day9 {
+            map: Grid::parse_digits(lines)?,
+        })
     }
-    if y < map.len() - 1 {
-        neighbours.push([x, y + 1]);
+
+    fn part1(&self) -> Answer {
+        let map = &self.map;
+        let lowest_points = find_lowest_points(map);
+        let risk_level_sum: u32 = lowest_points.iter().map(|p| map[*p] + 1).sum();
+        risk_level_sum.into()
     }
-    if x > 0 {
-        neighbours.push([x - 1, y]);
+
+    fn part2(&self) -> Answer {
+        let map = &self.map;
+        let mut basins: Vec<usize> = find_lowest_points(map)
+            .into_iter()
+            .map(|p| find_basin(map, p).len())
+            .collect();
+        basins.sort_unstable();
+        basins.reverse();
+        let largest_sum: usize = basins.iter().take(3).product();
+        largest_sum.into()
     }
-    if x < map[y].len() - 1 {
-        neighbours.push([x + 1, y]);
+
+    fn visualize(&self, animation: &mut Animation) -> bool {
+        let map = &self.map;
+        let lowest_points = find_lowest_points(map);
+        let mut filled = Grid::new(map.width(), map.height(), false);
+        let caption = format!("{} low points", lowest_points.len());
+        animation.frame(&caption, &render(map, &lowest_points, &filled));
+        for (i, low_point) in lowest_points.iter().enumerate() {
+            let basin = find_basin(map, *low_point);
+            for p in &basin {
+                filled[*p] = true;
+            }
+            let caption = format!(
+                "basin {} of {}: size {}",
+                i + 1,
+                lowest_points.len(),
+                basin.len()
+            );
+            animation.frame(&caption, &render(map, &lowest_points, &filled));
+        }
+        true
+    }
+
+    /// Every basin in its own color, darker where it is higher. Low points are white, the
+    /// borders black.
+    fn image(&self) -> Option<Image> {
+        let map = &self.map;
+        let mut pixels: Grid<netpbm::Rgb> = Grid::new(map.width(), map.height(), [0, 0, 0]);
+        for (i, low_point) in find_lowest_points(map).into_iter().enumerate() {
+            let color = netpbm::palette(i);
+            for p in find_basin(map, low_point) {
+                let shade = |c: u8| (c as u32 * (12 - map[p]) / 12) as u8;
+                pixels[p] = color.map(shade);
+            }
+            pixels[low_point] = [255, 255, 255];
+        }
+        Some(Image::Pixmap(pixels))
     }
-    return neighbours
-        .into_iter()
-        .filter(|p| map[p[1]][p[0]] != 9 && !visited.contains(p) && !to_visit.contains(p))
-        .collect();
-}
 
-fn part2(lines: &readfile::Lines) {
-    let map = parse(lines);
-    let lowest_points = find_lowest_points(&map);
-    let mut basins: Vec<usize> = Vec::new();
-
-    for low_point in lowest_points {
-        let mut visited: Vec<Point> = Vec::new();
-        let mut to_visit: Vec<Point> = vec![low_point];
-        let mut basin_size = 0;
-        while !to_visit.is_empty() {
-            let p = to_visit.pop().unwrap();
-            basin_size += 1;
-            visited.push(p);
-            to_visit.extend(find_points_to_visit(&map, &visited, &to_visit, p));
+    /// A `size` x `size` map (at least 2 x 2) of basins separated by heights of 9. Like in the
+    /// puzzle every basin has a single low point, the height grows with the distance to it.
+    fn generate(rng: &mut Rng, size: usize) -> Option<String> {
+        let size = size.max(2);
+        let walls = Grid::from_fn(size, size, |_| rng.chance(1, 3));
+        let mut map: Map = Grid::new(size, size, 9);
+        for p in walls.points() {
+            if walls[p] || map[p] != 9 {
+                continue;
+            }
+            let mut basin = vec![p];
+            let mut visited = Grid::new(size, size, false);
+            visited[p] = true;
+            let mut i = 0;
+            while i < basin.len() {
+                for n in walls.neighbours4(basin[i]) {
+                    if !walls[n] && !visited[n] {
+                        visited[n] = true;
+                        basin.push(n);
+                    }
+                }
+                i += 1;
+            }
+            // breadth first from the low point, so every other point has a lower neighbour
+            let low_point = *rng.choose(&basin);
+            let mut queue = std::collections::VecDeque::from([(low_point, 0)]);
+            map[low_point] = 0;
+            while let Some((p, height)) = queue.pop_front() {
+                for n in walls.neighbours4(p) {
+                    if !walls[n] && map[n] == 9 && n != low_point {
+                        map[n] = (height + 1).min(8);
+                        queue.push_back((n, height + 1));
+                    }
+                }
+            }
         }
-        basins.push(basin_size);
+        Some(map.render(|_, height| height.to_string()))
     }
-    basins.sort_unstable();
-    basins.reverse();
-    let largest_sum: usize = basins.iter().take(3).product();
-    println!("Part 2: {}", largest_sum);
 }
 
-pub fn run() {
-    let lines = readfile::Lines::new("day9.txt");
-    part1(&lines);
-    part2(&lines);
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generate;
+
+    const EXAMPLE: &str = "\
+2199943210
+3987894921
+9856789892
+8767896789
+9899965678";
+
+    #[test]
+    fn example() {
+        let day = Day9::parse(&readfile::Lines::from(EXAMPLE)).unwrap();
+        assert_eq!(day.part1(), Answer::Number(15));
+        assert_eq!(day.part2(), Answer::Number(1134));
+    }
+
+    /// The sizes of the areas without heights of 9, found by joining neighbouring points.
+    fn brute_force_basins(map: &Map) -> Vec<usize> {
+        let mut labels: Grid<usize> = map.map(|_| 0);
+        for (i, p) in map.points().enumerate() {
+            labels[p] = i;
+        }
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for p in map.points().filter(|p| map[*p] != 9) {
+                for n in map.neighbours4(p).filter(|n| map[*n] != 9) {
+                    if labels[n] < labels[p] {
+                        labels[p] = labels[n];
+                        changed = true;
+                    }
+                }
+            }
+        }
+        let mut sizes = vec![0; map.len()];
+        for p in map.points().filter(|p| map[*p] != 9) {
+            sizes[labels[p]] += 1;
+        }
+        sizes.retain(|s| *s > 0);
+        return sizes;
+    }
+
+    #[test]
+    fn matches_brute_force() {
+        generate::check_answers::<Day9>(
+            0..20,
+            |seed| 2 + seed as usize,
+            |day, _| {
+                let mut basins = brute_force_basins(&day.map);
+                // every basin has one low point of height 0
+                let low_points = basins.len();
+                basins.sort_unstable_by(|a, b| b.cmp(a));
+                let largest: usize = basins.iter().take(3).product();
+                [Some(low_points.into()), Some(largest.into())]
+            },
+        );
+    }
 }
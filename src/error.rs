@@ -0,0 +1,86 @@
+use std::error::Error;
+use std::fmt;
+
+/// An invalid puzzle input. Points at the offending line, the column is 1-based and counted in
+/// characters.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    pub file: String,
+    pub line: usize,
+    pub column: usize,
+    /// The whole offending line.
+    pub text: String,
+    pub message: String,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}:{}:{}: {}",
+            self.file, self.line, self.column, self.message
+        )
+    }
+}
+
+impl Error for ParseError {}
+
+impl ParseError {
+    /// Renders the error with the offending line and a marker below the column:
+    ///
+    /// ```text
+    /// error: invalid digit found in string
+    ///  --> inputs/day2.txt:3:9
+    ///   |
+    /// 3 | forward x
+    ///   |         ^
+    /// ```
+    pub fn render(&self) -> String {
+        let number = self.line.to_string();
+        let gutter = " ".repeat(number.len());
+        format!(
+            "error: {}\n{}--> {}:{}:{}\n{} |\n{} | {}\n{} | {}^",
+            self.message,
+            gutter,
+            self.file,
+            self.line,
+            self.column,
+            gutter,
+            number,
+            self.text,
+            gutter,
+            " ".repeat(self.column.saturating_sub(1))
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::readfile::Lines;
+
+    #[test]
+    fn points_at_the_offending_field() {
+        let lines = Lines::from("12,7\n3,x4");
+        let line = lines.numbered().nth(1).unwrap();
+        let (_, y) = line.text().split_once(',').unwrap();
+        let err = line.parse_field::<u32>(y).unwrap_err();
+        assert_eq!((err.line, err.column), (2, 3));
+        assert_eq!(err.text, "3,x4");
+        assert_eq!(
+            err.render(),
+            "\
+error: invalid value 'x4': invalid digit found in string
+ --> <input>:2:3
+  |
+2 | 3,x4
+  |   ^"
+        );
+    }
+
+    #[test]
+    fn missing_line_is_reported_after_the_end() {
+        let err = Lines::from("").first().unwrap_err();
+        assert_eq!((err.line, err.column), (1, 1));
+        assert_eq!(err.message, "unexpected end of input");
+    }
+}
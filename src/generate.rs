@@ -0,0 +1,102 @@
+//! Random puzzle inputs, see `aoc generate <day>` and the property tests of the days.
+
+use std::ops::RangeInclusive;
+
+/// A small seeded pseudo random number generator (SplitMix64). The same seed always produces
+/// the same inputs, so failing property tests can be reproduced.
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Rng {
+        Rng { state: seed }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// A number in `0..n`, `n` must not be 0.
+    pub fn below(&mut self, n: usize) -> usize {
+        (self.next_u64() % n as u64) as usize
+    }
+
+    pub fn range(&mut self, range: RangeInclusive<isize>) -> isize {
+        let size = (range.end() - range.start()) as usize + 1;
+        range.start() + self.below(size) as isize
+    }
+
+    /// `true` with a probability of `numerator / denominator`.
+    pub fn chance(&mut self, numerator: usize, denominator: usize) -> bool {
+        self.below(denominator) < numerator
+    }
+
+    pub fn choose<'a, T>(&mut self, items: &'a [T]) -> &'a T {
+        &items[self.below(items.len())]
+    }
+
+    pub fn shuffle<T>(&mut self, items: &mut [T]) {
+        for i in (1..items.len()).rev() {
+            items.swap(i, self.below(i + 1));
+        }
+    }
+}
+
+/// The property test of a day: parses the inputs generated for `seeds`, sized by `size`, and
+/// compares the answers with those of `reference`, which gets the parsed solver and its input.
+/// Parts for which the reference returns `None` are not checked.
+#[cfg(test)]
+pub fn check_answers<S: crate::solver::Solver>(
+    seeds: std::ops::Range<u64>,
+    size: impl Fn(u64) -> usize,
+    reference: impl Fn(&S, &str) -> [Option<crate::answer::Answer>; 2],
+) {
+    for seed in seeds {
+        let input = S::generate(&mut Rng::new(seed), size(seed)).unwrap();
+        let solver = S::parse(&crate::readfile::Lines::from(input.as_str())).unwrap();
+        let [part1, part2] = reference(&solver, &input);
+        if let Some(expected) = part1 {
+            assert_eq!(solver.part1(), expected, "seed {}", seed);
+        }
+        if let Some(expected) = part2 {
+            assert_eq!(solver.part2(), expected, "seed {}", seed);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn same_seed_same_numbers() {
+        let numbers = |seed| {
+            let mut rng = Rng::new(seed);
+            (0..5).map(|_| rng.below(100)).collect::<Vec<_>>()
+        };
+        assert_eq!(numbers(7), numbers(7));
+        assert_ne!(numbers(7), numbers(8));
+    }
+
+    #[test]
+    fn ranges_are_inclusive() {
+        let mut rng = Rng::new(1);
+        let values: Vec<isize> = (0..200).map(|_| rng.range(-2..=2)).collect();
+        assert!(values.iter().all(|v| (-2..=2).contains(v)));
+        assert!(values.contains(&-2) && values.contains(&2));
+    }
+
+    #[test]
+    fn shuffle_keeps_the_items() {
+        let mut items: Vec<usize> = (0..20).collect();
+        Rng::new(3).shuffle(&mut items);
+        assert_ne!(items, (0..20).collect::<Vec<_>>());
+        items.sort_unstable();
+        assert_eq!(items, (0..20).collect::<Vec<_>>());
+    }
+}
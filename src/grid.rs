@@ -0,0 +1,276 @@
+//! A rectangular grid of cells, shared by the days working on maps.
+
+use crate::error::ParseError;
+use crate::readfile;
+use std::fmt;
+use std::ops::{Index, IndexMut};
+
+/// A position in a [`Grid`] as `[x, y]`, the origin is the top left corner.
+pub type Point = [usize; 2];
+
+/// Cells stored row by row. Access is bounds-checked, [`Grid::get`] returns `None` outside.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Grid<T> {
+    cells: Vec<T>,
+    width: usize,
+    height: usize,
+}
+
+impl<T: Clone> Grid<T> {
+    pub fn new(width: usize, height: usize, value: T) -> Grid<T> {
+        return Grid {
+            cells: vec![value; width * height],
+            width,
+            height,
+        };
+    }
+}
+
+impl<T> Grid<T> {
+    /// Parses one row per line, `parse_cell` gets the line, the index of the character in the
+    /// line and the character. All rows must have the same length.
+    pub fn parse<F>(lines: &readfile::Lines, parse_cell: F) -> Result<Grid<T>, ParseError>
+    where
+        F: FnMut(readfile::Line, usize, char) -> Result<T, ParseError>,
+    {
+        Grid::parse_after(lines, 0, parse_cell)
+    }
+
+    /// Like [`Grid::parse`] for a map below a header of `skip` lines.
+    pub fn parse_after<F>(
+        lines: &readfile::Lines,
+        skip: usize,
+        mut parse_cell: F,
+    ) -> Result<Grid<T>, ParseError>
+    where
+        F: FnMut(readfile::Line, usize, char) -> Result<T, ParseError>,
+    {
+        let mut cells = Vec::new();
+        let mut width = 0;
+        let mut height = 0;
+        for l in lines.numbered().skip(skip) {
+            let len = l.text().chars().count();
+            if height == 0 {
+                width = len;
+            } else if len != width {
+                return Err(l.error(1, &format!("expected {} columns", width)));
+            }
+            for (i, c) in l.text().chars().enumerate() {
+                cells.push(parse_cell(l, i, c)?);
+            }
+            height += 1;
+        }
+        if width == 0 {
+            return Err(lines.error_at_end("expected at least one row"));
+        }
+        return Ok(Grid {
+            cells,
+            width,
+            height,
+        });
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn len(&self) -> usize {
+        self.cells.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cells.is_empty()
+    }
+
+    pub fn contains(&self, [x, y]: Point) -> bool {
+        x < self.width && y < self.height
+    }
+
+    pub fn get(&self, p: Point) -> Option<&T> {
+        if !self.contains(p) {
+            return None;
+        }
+        return Some(&self.cells[p[1] * self.width + p[0]]);
+    }
+
+    pub fn get_mut(&mut self, p: Point) -> Option<&mut T> {
+        if !self.contains(p) {
+            return None;
+        }
+        return Some(&mut self.cells[p[1] * self.width + p[0]]);
+    }
+
+    /// The point `dx`, `dy` away from `p`, if it is inside the grid.
+    pub fn offset(&self, [x, y]: Point, dx: isize, dy: isize) -> Option<Point> {
+        let p = [x.checked_add_signed(dx)?, y.checked_add_signed(dy)?];
+        if self.contains(p) {
+            Some(p)
+        } else {
+            None
+        }
+    }
+
+    /// The horizontal and vertical neighbours of `p` inside the grid.
+    pub fn neighbours4(&self, p: Point) -> impl Iterator<Item = Point> + '_ {
+        [(0, -1), (-1, 0), (1, 0), (0, 1)]
+            .into_iter()
+            .filter_map(move |(dx, dy)| self.offset(p, dx, dy))
+    }
+
+    /// The neighbours of `p` inside the grid, including the diagonal ones.
+    pub fn neighbours8(&self, p: Point) -> impl Iterator<Item = Point> + '_ {
+        (-1..=1)
+            .flat_map(|dy| (-1..=1).map(move |dx| (dx, dy)))
+            .filter(|&(dx, dy)| dx != 0 || dy != 0)
+            .filter_map(move |(dx, dy)| self.offset(p, dx, dy))
+    }
+
+    /// All points, row by row.
+    pub fn points(&self) -> impl Iterator<Item = Point> {
+        let width = self.width;
+        (0..self.cells.len()).map(move |i| [i % width, i / width])
+    }
+
+    /// All cells with their points, row by row.
+    pub fn iter(&self) -> impl Iterator<Item = (Point, &T)> {
+        self.points().zip(self.cells.iter())
+    }
+
+    pub fn values(&self) -> impl Iterator<Item = &T> {
+        self.cells.iter()
+    }
+
+    pub fn values_mut(&mut self) -> impl Iterator<Item = &mut T> {
+        self.cells.iter_mut()
+    }
+
+    pub fn row(&self, y: usize) -> &[T] {
+        &self.cells[y * self.width..(y + 1) * self.width]
+    }
+
+    /// The rows from top to bottom, also for a grid without columns.
+    pub fn rows(&self) -> impl Iterator<Item = &[T]> {
+        (0..self.height).map(|y| self.row(y))
+    }
+
+    pub fn column(&self, x: usize) -> impl Iterator<Item = &T> {
+        // a grid without columns has no cells, the step only has to be valid
+        self.cells.iter().skip(x).step_by(self.width.max(1))
+    }
+
+    pub fn map<U, F: FnMut(&T) -> U>(&self, f: F) -> Grid<U> {
+        return Grid {
+            cells: self.cells.iter().map(f).collect(),
+            width: self.width,
+            height: self.height,
+        };
+    }
+
+    /// A grid of `width` x `height` cells computed from their points.
+    pub fn from_fn<F: FnMut(Point) -> T>(width: usize, height: usize, mut f: F) -> Grid<T> {
+        return Grid {
+            cells: (0..width * height)
+                .map(|i| f([i % width, i / width]))
+                .collect(),
+            width,
+            height,
+        };
+    }
+
+    /// One line per row, each cell formatted by `f`.
+    pub fn render<F: FnMut(Point, &T) -> String>(&self, mut f: F) -> String {
+        let mut rendered = String::new();
+        for (p, value) in self.iter() {
+            if p[0] == 0 && p[1] > 0 {
+                rendered.push('\n');
+            }
+            rendered.push_str(&f(p, value));
+        }
+        return rendered;
+    }
+}
+
+impl Grid<u32> {
+    /// Parses a map of single digits such as `2199943210`.
+    pub fn parse_digits(lines: &readfile::Lines) -> Result<Grid<u32>, ParseError> {
+        Grid::parse(lines, |line, i, c| {
+            c.to_digit(10)
+                .ok_or_else(|| line.error(i + 1, &format!("expected a digit, found '{}'", c)))
+        })
+    }
+}
+
+impl<T> Index<Point> for Grid<T> {
+    type Output = T;
+
+    fn index(&self, p: Point) -> &T {
+        match self.get(p) {
+            Some(value) => value,
+            None => panic!("{:?} is outside the {}x{} grid", p, self.width, self.height),
+        }
+    }
+}
+
+impl<T> IndexMut<Point> for Grid<T> {
+    fn index_mut(&mut self, p: Point) -> &mut T {
+        let (width, height) = (self.width, self.height);
+        match self.get_mut(p) {
+            Some(value) => value,
+            None => panic!("{:?} is outside the {}x{} grid", p, width, height),
+        }
+    }
+}
+
+/// The cells without separator, one row per line. Meant for single character cells.
+impl<T: fmt::Display> fmt::Display for Grid<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.render(|_, value| value.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "\
+123
+456";
+
+    #[test]
+    fn parse_and_access() {
+        let grid = Grid::parse_digits(&readfile::Lines::from(EXAMPLE)).unwrap();
+        assert_eq!((grid.width(), grid.height()), (3, 2));
+        assert_eq!(grid[[2, 1]], 6);
+        assert_eq!(grid.get([3, 0]), None);
+        assert_eq!(grid.get([0, 2]), None);
+        assert_eq!(grid.row(1), &[4, 5, 6]);
+        assert_eq!(grid.column(1).copied().collect::<Vec<_>>(), vec![2, 5]);
+        assert_eq!(grid.to_string(), EXAMPLE);
+    }
+
+    #[test]
+    fn grid_without_columns() {
+        let grid = Grid::new(0, 2, 0);
+        assert_eq!(grid.rows().collect::<Vec<&[i32]>>(), vec![&[], &[]]);
+        assert_eq!(grid.column(0).count(), 0);
+    }
+
+    #[test]
+    fn ragged_rows_are_rejected() {
+        let err = Grid::parse_digits(&readfile::Lines::from("123\n45")).unwrap_err();
+        assert_eq!((err.line, err.message.as_str()), (2, "expected 3 columns"));
+    }
+
+    #[test]
+    fn neighbours_stay_inside() {
+        let grid = Grid::new(3, 2, 0);
+        let corner: Vec<Point> = grid.neighbours4([0, 0]).collect();
+        assert_eq!(corner, vec![[1, 0], [0, 1]]);
+        let edge: Vec<Point> = grid.neighbours8([1, 1]).collect();
+        assert_eq!(edge, vec![[0, 0], [1, 0], [2, 0], [0, 1], [2, 1]]);
+    }
+}
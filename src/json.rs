@@ -0,0 +1,60 @@
+//! Just enough JSON to emit flat records, the runner does not need a full serializer.
+
+use crate::answer::Answer;
+
+/// A JSON string literal.
+pub fn string(s: &str) -> String {
+    let mut result = String::from("\"");
+    for c in s.chars() {
+        match c {
+            '"' => result.push_str("\\\""),
+            '\\' => result.push_str("\\\\"),
+            '\n' => result.push_str("\\n"),
+            '\r' => result.push_str("\\r"),
+            '\t' => result.push_str("\\t"),
+            c if (c as u32) < 0x20 => result.push_str(&format!("\\u{:04x}", c as u32)),
+            c => result.push(c),
+        }
+    }
+    result.push('"');
+    return result;
+}
+
+/// Numbers stay numbers, text and art become strings and unsolved parts `null`.
+pub fn answer(answer: &Answer) -> String {
+    match answer {
+        Answer::Number(v) => v.to_string(),
+        Answer::Text(s) | Answer::Art(s) => string(s),
+        Answer::Unsolved => String::from("null"),
+    }
+}
+
+/// An object from already encoded values, keeping the order of the fields.
+pub fn object(fields: &[(&str, String)]) -> String {
+    let fields: Vec<String> = fields
+        .iter()
+        .map(|(key, value)| format!("{}:{}", string(key), value))
+        .collect();
+    format!("{{{}}}", fields.join(","))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn escapes_strings() {
+        assert_eq!(string("a \"b\"\n\\"), "\"a \\\"b\\\"\\n\\\\\"");
+        assert_eq!(string("\u{1}"), "\"\\u0001\"");
+    }
+
+    #[test]
+    fn encodes_answers_and_objects() {
+        let record = object(&[
+            ("day", 13.to_string()),
+            ("answer", answer(&Answer::Art(String::from("#.\n.#")))),
+            ("error", answer(&Answer::Unsolved)),
+        ]);
+        assert_eq!(record, "{\"day\":13,\"answer\":\"#.\\n.#\",\"error\":null}");
+    }
+}
@@ -0,0 +1,50 @@
+//! Advent of Code 2021 solutions.
+//!
+//! Every day lives in its own module exposing a [`solver::Solver`] implementation, the
+//! registry in [`solver::DAYS`] lists them all. The `aoc` binary is a thin command line
+//! front end on top of [`runner`].
+
+#![allow(clippy::needless_return)]
+
+pub mod answer;
+pub mod answers;
+pub mod bench;
+pub mod day1;
+pub mod day10;
+pub mod day11;
+pub mod day12;
+pub mod day13;
+pub mod day14;
+pub mod day15;
+pub mod day16;
+pub mod day17;
+pub mod day18;
+pub mod day19;
+pub mod day2;
+pub mod day20;
+pub mod day21;
+pub mod day22;
+pub mod day23;
+pub mod day24;
+pub mod day25;
+pub mod day3;
+pub mod day4;
+pub mod day5;
+pub mod day6;
+pub mod day7;
+pub mod day8;
+pub mod day9;
+pub mod error;
+pub mod generate;
+pub mod grid;
+pub mod json;
+pub mod netpbm;
+pub mod point3;
+pub mod readfile;
+pub mod runner;
+pub mod scaffold;
+pub mod search;
+pub mod solver;
+#[cfg(test)]
+mod template;
+pub mod visualize;
@@ -1,68 +1,350 @@
-#![feature(mixed_integer_ops)]
-#![feature(int_abs_diff)]
-#![feature(drain_filter)]
-#![feature(map_first_last)]
-#![feature(exact_size_is_empty)]
-#![allow(clippy::needless_return)]
-
+use aoc::{answers, bench, generate, readfile, runner, scaffold, solver, visualize};
 use std::env;
-mod day1;
-mod day10;
-mod day11;
-mod day12;
-mod day13;
-mod day14;
-mod day15;
-mod day16;
-mod day17;
-mod day18;
-mod day2;
-mod day20;
-mod day21;
-mod day22;
-mod day24;
-mod day3;
-mod day4;
-mod day5;
-mod day6;
-mod day7;
-mod day8;
-mod day9;
-mod readfile;
+use std::path::Path;
+
+mod cli;
+
+#[global_allocator]
+static ALLOCATOR: bench::CountingAllocator = bench::CountingAllocator;
+
+fn exit_with_error(message: &str) -> ! {
+    println!("{}", message);
+    std::process::exit(1);
+}
+
+/// Runs `days` on `jobs` threads, the results are in the order of `days`.
+fn run_days(
+    days: &[&'static solver::Day],
+    input: Option<&readfile::Source>,
+    inputs_dir: &Path,
+    options: &cli::Options,
+    jobs: usize,
+) -> Vec<Result<runner::DayResult, runner::RunError>> {
+    let inputs: Vec<(&solver::Day, readfile::Source)> = days
+        .iter()
+        .map(|day| (*day, runner::input_source(day, input, inputs_dir)))
+        .collect();
+    runner::run_days(&inputs, options.part, &options.params, jobs)
+}
+
+fn unwrap_result(result: Result<runner::DayResult, runner::RunError>) -> runner::DayResult {
+    match result {
+        Ok(result) => result,
+        Err(err) => exit_with_error(&err.to_string()),
+    }
+}
+
+/// Runs every selected day with recorded answers and reports the parts that differ. A day that
+/// cannot be run fails all its recorded parts. Returns whether all recorded answers matched.
+fn verify(
+    days: &[&'static solver::Day],
+    recorded: &answers::Answers,
+    inputs_dir: &Path,
+    options: &cli::Options,
+    jobs: usize,
+) -> bool {
+    let is_recorded = |day: &solver::Day| {
+        recorded.contains_key(&(day.number, 1)) || recorded.contains_key(&(day.number, 2))
+    };
+    let recorded_days: Vec<&solver::Day> =
+        days.iter().copied().filter(|d| is_recorded(d)).collect();
+    let mut results = run_days(&recorded_days, None, inputs_dir, options, jobs).into_iter();
+
+    let (mut passed, mut failed, mut skipped) = (0, 0, 0);
+    for day in days {
+        if !is_recorded(day) {
+            println!("Day {:>2}: no recorded answers", day.number);
+            skipped += 2;
+            continue;
+        }
+
+        let expected = [
+            recorded.get(&(day.number, 1)),
+            recorded.get(&(day.number, 2)),
+        ];
+        let result = match results.next().unwrap() {
+            Ok(result) => result,
+            Err(err) => {
+                println!("Day {:>2}: FAILED", day.number);
+                println!("{}", err);
+                failed += expected.iter().flatten().count();
+                continue;
+            }
+        };
+        for (part, actual, expected) in [
+            (1, &result.part1, expected[0]),
+            (2, &result.part2, expected[1]),
+        ] {
+            match (actual, expected) {
+                (None, _) | (_, None) => skipped += 1,
+                (Some(actual), Some(expected)) if answers::matches(actual, expected) => {
+                    println!("Day {:>2} part {}: ok", day.number, part);
+                    passed += 1;
+                }
+                (Some(actual), Some(expected)) => {
+                    println!("Day {:>2} part {}: FAILED", day.number, part);
+                    println!("  expected: {}", expected);
+                    println!("  actual:   {}", actual);
+                    failed += 1;
+                }
+            }
+        }
+    }
+    println!();
+    println!("{} passed, {} failed, {} skipped", passed, failed, skipped);
+    failed == 0
+}
+
+/// Benchmarks each day in turn on the current thread, so timings do not interfere.
+fn run_bench(days: &[&solver::Day], options: &cli::Options, inputs_dir: &Path) {
+    let baseline = options.baseline.as_ref().map(|path| match bench::read_baseline(path) {
+        Ok(baseline) => baseline,
+        Err(err) => exit_with_error(&err),
+    });
+
+    let mut results = Vec::new();
+    for day in days {
+        let source = runner::input_source(day, options.input.as_ref(), inputs_dir);
+        let lines = match readfile::Lines::read(&source) {
+            Ok(lines) => lines,
+            Err(err) => exit_with_error(&format!("Could not read {}: {}", source.name(), err)),
+        };
+        let result = match bench::bench_day(
+            day,
+            &lines,
+            &options.params,
+            options.warmup,
+            options.repetitions,
+        ) {
+            Ok(result) => result,
+            Err(err) => exit_with_error(&err),
+        };
+        println!(
+            "Day {} ({} runs after {} warmup runs)",
+            day.number, options.repetitions, options.warmup
+        );
+        runner::print_bench_table(&result, baseline.as_ref());
+        println!();
+        results.push(result);
+    }
+
+    if let Some(path) = &options.save_baseline {
+        if let Err(err) = bench::save_baseline(path, &results) {
+            exit_with_error(&err);
+        }
+        println!("Saved baseline to {}", path.display());
+    }
+}
 
 fn main() {
-    let args: Vec<String> = env::args().collect();
+    let args: Vec<String> = env::args().skip(1).collect();
+    let options = match cli::parse_args(&args) {
+        Ok(options) => options,
+        Err(err) => exit_with_error(&format!("{}\n\n{}", err, cli::USAGE)),
+    };
 
-    if args.len() == 1 || (&args[1]).is_empty() {
-        println!("No day specified");
-        std::process::exit(1);
+    let selection = match &options.command {
+        cli::Command::List => {
+            for day in 1..=25 {
+                let status = if solver::is_implemented(day) {
+                    "implemented"
+                } else {
+                    "-"
+                };
+                println!("Day {:>2}: {}", day, status);
+                for param in solver::find(day).map_or(&[][..], |d| d.params()) {
+                    println!("        --param {}=<value>: {}", param.name, param.help);
+                }
+            }
+            return;
+        }
+        cli::Command::Run(selection) => selection,
+        cli::Command::Verify(selection) => selection,
+        cli::Command::Bench(selection) => selection,
+        cli::Command::New(day) => {
+            let day = match day.parse::<u8>() {
+                Ok(day) => day,
+                Err(_) => exit_with_error(&format!("Invalid day: {}", day)),
+            };
+            let inputs_dir = options
+                .inputs_dir
+                .clone()
+                .unwrap_or_else(readfile::default_inputs_dir);
+            let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+            match scaffold::new_day(root, &inputs_dir, day) {
+                Ok(files) => {
+                    for file in files {
+                        println!("Wrote {}", file.display());
+                    }
+                    println!("Rebuild to run day {}", day);
+                }
+                Err(err) => exit_with_error(&err),
+            }
+            return;
+        }
+        cli::Command::Generate(day) => {
+            let day = match runner::select_days(day) {
+                Ok(days) if days.len() == 1 => days[0],
+                Ok(_) => exit_with_error("generate needs a single day"),
+                Err(err) => exit_with_error(&err),
+            };
+            let mut rng = generate::Rng::new(options.seed);
+            match day.generate(&mut rng, options.size) {
+                Some(input) => println!("{}", input),
+                None => exit_with_error(&format!("Day {} has no input generator", day.number)),
+            }
+            return;
+        }
+    };
+
+    let days = match runner::select_days(selection) {
+        Ok(days) => days,
+        Err(err) => exit_with_error(&err),
+    };
+
+    if options.input.is_some() && days.len() != 1 {
+        exit_with_error("--input can only be used with a single day");
+    }
+    for day in &days {
+        if let Err(err) = day.check_params(&options.params) {
+            exit_with_error(&err);
+        }
+    }
+
+    let inputs_dir = options
+        .inputs_dir
+        .clone()
+        .unwrap_or_else(readfile::default_inputs_dir);
+    let jobs = options.jobs.unwrap_or_else(runner::default_jobs);
+
+    if options.visualize {
+        let day = match (&options.command, days.as_slice()) {
+            (cli::Command::Run(_), [day]) => day,
+            _ => exit_with_error("--visualize can only be used to run a single day"),
+        };
+        let source = runner::input_source(day, options.input.as_ref(), &inputs_dir);
+        let mut animation = visualize::Animation::new(options.frames_per_second);
+        match runner::visualize(day, &source, &options.params, &mut animation) {
+            Ok(true) => {}
+            Ok(false) => exit_with_error(&format!("Day {} has no visualization", day.number)),
+            Err(err) => exit_with_error(&err.to_string()),
+        }
+        return;
+    }
+
+    if let Some(path) = &options.export_image {
+        let day = match (&options.command, days.as_slice()) {
+            (cli::Command::Run(_), [day]) => day,
+            _ => exit_with_error("--export-image can only be used to run a single day"),
+        };
+        let source = runner::input_source(day, options.input.as_ref(), &inputs_dir);
+        let image = match runner::image(day, &source, &options.params) {
+            Ok(Some(image)) => image,
+            Ok(None) => exit_with_error(&format!("Day {} has no image", day.number)),
+            Err(err) => exit_with_error(&err.to_string()),
+        };
+        if let Err(err) = image.save(path) {
+            exit_with_error(&err);
+        }
+        println!(
+            "Wrote {} ({}x{} {})",
+            path.display(),
+            image.width(),
+            image.height(),
+            image.extension().to_uppercase()
+        );
+        return;
+    }
+
+    if let cli::Command::Bench(_) = options.command {
+        if options.part.is_some() {
+            exit_with_error("--part cannot be used with bench, it measures both parts");
+        }
+        run_bench(&days, &options, &inputs_dir);
+        return;
+    }
+
+    if let cli::Command::Verify(_) = options.command {
+        if !options.params.is_empty() {
+            exit_with_error("--param cannot be used with verify, the answers are for the puzzle");
+        }
+        let path = options
+            .answers
+            .clone()
+            .unwrap_or_else(|| inputs_dir.join("answers.toml"));
+        let recorded = match answers::read(&path) {
+            Ok(recorded) => recorded,
+            Err(err) => exit_with_error(&err),
+        };
+        if !verify(&days, &recorded, &inputs_dir, &options, jobs) {
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    // running several days skips the ones without an input yet instead of failing on them
+    let several = days.len() > 1;
+    let days = if several && options.input.is_none() {
+        let (days, skipped): (Vec<_>, Vec<_>) = days
+            .into_iter()
+            .partition(|day| !runner::has_no_input(day, &inputs_dir));
+        if let cli::Format::Text = options.format {
+            for day in &skipped {
+                println!("Day {}: skipped, no input in {}", day.number, inputs_dir.display());
+            }
+            if !skipped.is_empty() {
+                println!();
+            }
+        }
+        days
+    } else {
+        days
+    };
+
+    if let cli::Format::Json = options.format {
+        let mut failed = false;
+        let results = run_days(&days, options.input.as_ref(), &inputs_dir, &options, jobs);
+        for (day, result) in days.iter().zip(results) {
+            match result {
+                Ok(result) => runner::print_json_records(&result),
+                Err(err) => {
+                    runner::print_json_error(day, &err);
+                    failed = true;
+                }
+            }
+        }
+        if failed {
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    let results = run_days(&days, options.input.as_ref(), &inputs_dir, &options, jobs);
+    if !several {
+        let result = unwrap_result(results.into_iter().next().unwrap());
+        runner::print_answers(&result);
+        return;
     }
 
-    let day = &args[1];
-
-    match day.as_str() {
-        "1" => day1::run(),
-        "2" => day2::run(),
-        "3" => day3::run(),
-        "4" => day4::run(),
-        "5" => day5::run(),
-        "6" => day6::run(),
-        "7" => day7::run(),
-        "8" => day8::run(),
-        "9" => day9::run(),
-        "10" => day10::run(),
-        "11" => day11::run(),
-        "12" => day12::run(),
-        "13" => day13::run(),
-        "14" => day14::run(),
-        "15" => day15::run(),
-        "16" => day16::run(),
-        "17" => day17::run(),
-        "18" => day18::run(),
-        "20" => day20::run(),
-        "21" => day21::run(),
-        "22" => day22::run(),
-        "24" => day24::run(),
-        _ => panic!("Unknown day: {}", day),
+    // a day that cannot be run does not stop the others
+    let mut finished = Vec::new();
+    let mut failed = false;
+    for (day, result) in days.iter().zip(results) {
+        println!("Day {}", day.number);
+        match result {
+            Ok(result) => {
+                runner::print_answers(&result);
+                finished.push(result);
+            }
+            Err(err) => {
+                println!("{}", err);
+                failed = true;
+            }
+        }
+        println!();
+    }
+    runner::print_timing_table(&finished);
+    if failed {
+        std::process::exit(1);
     }
 }
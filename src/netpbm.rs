@@ -0,0 +1,162 @@
+//! Images in the plain Netpbm formats, see `aoc <day> --export-image`.
+//!
+//! The plain (ASCII) variants PBM (`P1`), PGM (`P2`) and PPM (`P3`) are simple enough to write
+//! by hand and most image viewers and converters read them.
+
+use crate::grid::Grid;
+use std::fs;
+use std::path::Path;
+
+/// Viewers should accept longer lines, but the format asks for at most 70 characters.
+const MAX_LINE_LENGTH: usize = 70;
+
+pub type Rgb = [u8; 3];
+
+pub enum Image {
+    /// Black and white, `true` is black. Written as PBM.
+    Bitmap(Grid<bool>),
+    /// Gray levels from 0 (black) up to the given maximum (white). Written as PGM.
+    Graymap(Grid<u16>, u16),
+    /// Colors, written as PPM.
+    Pixmap(Grid<Rgb>),
+}
+
+impl Image {
+    /// A gray map whose maximum is the brightest value, at least 1 as the format requires.
+    pub fn graymap(levels: Grid<u16>) -> Image {
+        let max = levels.values().copied().max().unwrap_or(0).max(1);
+        Image::Graymap(levels, max)
+    }
+
+    /// The file extension of the format.
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Image::Bitmap(_) => "pbm",
+            Image::Graymap(..) => "pgm",
+            Image::Pixmap(_) => "ppm",
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        match self {
+            Image::Bitmap(grid) => grid.width(),
+            Image::Graymap(grid, _) => grid.width(),
+            Image::Pixmap(grid) => grid.width(),
+        }
+    }
+
+    pub fn height(&self) -> usize {
+        match self {
+            Image::Bitmap(grid) => grid.height(),
+            Image::Graymap(grid, _) => grid.height(),
+            Image::Pixmap(grid) => grid.height(),
+        }
+    }
+
+    /// The image in the plain format, each row starts on a new line.
+    pub fn to_netpbm(&self) -> String {
+        let (header, rows): (String, Vec<Vec<String>>) = match self {
+            Image::Bitmap(grid) => (
+                format!("P1\n{} {}", grid.width(), grid.height()),
+                grid.rows()
+                    .map(|row| {
+                        row.iter()
+                            .map(|b| String::from(if *b { "1" } else { "0" }))
+                            .collect()
+                    })
+                    .collect(),
+            ),
+            Image::Graymap(grid, max) => (
+                format!("P2\n{} {}\n{}", grid.width(), grid.height(), max),
+                grid.rows()
+                    .map(|row| row.iter().map(|v| v.to_string()).collect())
+                    .collect(),
+            ),
+            Image::Pixmap(grid) => (
+                format!("P3\n{} {}\n255", grid.width(), grid.height()),
+                grid.rows()
+                    .map(|row| row.iter().flatten().map(|v| v.to_string()).collect())
+                    .collect(),
+            ),
+        };
+
+        let mut text = header;
+        text.push('\n');
+        for row in rows {
+            let mut line_length = 0;
+            for value in row {
+                if line_length > 0 && line_length + 1 + value.len() > MAX_LINE_LENGTH {
+                    text.push('\n');
+                    line_length = 0;
+                } else if line_length > 0 {
+                    text.push(' ');
+                    line_length += 1;
+                }
+                line_length += value.len();
+                text.push_str(&value);
+            }
+            text.push('\n');
+        }
+        return text;
+    }
+
+    /// Writes the image to `path`, whose extension must match the format.
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        if path.extension().and_then(|e| e.to_str()) != Some(self.extension()) {
+            return Err(format!(
+                "The image is a {}, use a file ending in .{}",
+                self.extension().to_uppercase(),
+                self.extension()
+            ));
+        }
+        fs::write(path, self.to_netpbm())
+            .map_err(|err| format!("Could not write {}: {}", path.display(), err))
+    }
+}
+
+/// A color for each index, neighbouring indices get clearly different hues.
+pub fn palette(index: usize) -> Rgb {
+    const COLORS: [Rgb; 8] = [
+        [230, 25, 75],
+        [60, 180, 75],
+        [255, 225, 25],
+        [0, 130, 200],
+        [245, 130, 48],
+        [145, 30, 180],
+        [70, 240, 240],
+        [240, 50, 230],
+    ];
+    COLORS[index % COLORS.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bitmap() {
+        let grid = Grid::from_fn(3, 2, |[x, y]| x == y);
+        assert_eq!(Image::Bitmap(grid).to_netpbm(), "P1\n3 2\n1 0 0\n0 1 0\n");
+    }
+
+    #[test]
+    fn graymap_uses_the_brightest_value() {
+        let grid = Grid::from_fn(2, 1, |[x, _]| 3 * x as u16);
+        assert_eq!(Image::graymap(grid).to_netpbm(), "P2\n2 1\n3\n0 3\n");
+    }
+
+    #[test]
+    fn long_rows_are_wrapped() {
+        let image = Image::Pixmap(Grid::new(10, 1, [255, 255, 255]));
+        let text = image.to_netpbm();
+        assert!(text.lines().all(|l| l.len() <= MAX_LINE_LENGTH));
+        assert_eq!(text.split_whitespace().count(), 4 + 30);
+    }
+
+    #[test]
+    fn extension_must_match() {
+        let image = Image::Bitmap(Grid::new(1, 1, false));
+        let err = image.save(Path::new("image.png")).unwrap_err();
+        assert_eq!(err, "The image is a PBM, use a file ending in .pbm");
+    }
+}
@@ -0,0 +1,194 @@
+//! Points in three dimensions and the rotations of a cube, shared by the days in 3D space.
+
+use std::fmt;
+use std::ops::{Add, Index, IndexMut, Neg, Sub};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Point3 {
+    pub x: isize,
+    pub y: isize,
+    pub z: isize,
+}
+
+impl Point3 {
+    pub const ORIGIN: Point3 = Point3 { x: 0, y: 0, z: 0 };
+
+    pub fn new(x: isize, y: isize, z: isize) -> Point3 {
+        Point3 { x, y, z }
+    }
+
+    /// The sum of the distances along the axes.
+    pub fn manhattan_distance(&self, other: &Point3) -> usize {
+        (0..3).map(|axis| self[axis].abs_diff(other[axis])).sum()
+    }
+
+    /// The square of the straight line distance, which stays exact.
+    pub fn squared_distance(&self, other: &Point3) -> isize {
+        (0..3).map(|axis| (self[axis] - other[axis]).pow(2)).sum()
+    }
+}
+
+/// The coordinate along an axis, 0 is x, 1 is y and 2 is z.
+impl Index<usize> for Point3 {
+    type Output = isize;
+
+    fn index(&self, axis: usize) -> &isize {
+        match axis {
+            0 => &self.x,
+            1 => &self.y,
+            2 => &self.z,
+            _ => panic!("Invalid axis {}", axis),
+        }
+    }
+}
+
+impl IndexMut<usize> for Point3 {
+    fn index_mut(&mut self, axis: usize) -> &mut isize {
+        match axis {
+            0 => &mut self.x,
+            1 => &mut self.y,
+            2 => &mut self.z,
+            _ => panic!("Invalid axis {}", axis),
+        }
+    }
+}
+
+impl Add for Point3 {
+    type Output = Point3;
+
+    fn add(self, other: Point3) -> Point3 {
+        Point3::new(self.x + other.x, self.y + other.y, self.z + other.z)
+    }
+}
+
+impl Sub for Point3 {
+    type Output = Point3;
+
+    fn sub(self, other: Point3) -> Point3 {
+        Point3::new(self.x - other.x, self.y - other.y, self.z - other.z)
+    }
+}
+
+impl Neg for Point3 {
+    type Output = Point3;
+
+    fn neg(self) -> Point3 {
+        Point3::new(-self.x, -self.y, -self.z)
+    }
+}
+
+impl fmt::Display for Point3 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{},{},{}", self.x, self.y, self.z)
+    }
+}
+
+/// One of the 24 ways to turn a cube onto itself, as a matrix with a single 1 or -1 per row.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Rotation {
+    matrix: [[isize; 3]; 3],
+}
+
+impl Rotation {
+    pub const IDENTITY: Rotation = Rotation {
+        matrix: [[1, 0, 0], [0, 1, 0], [0, 0, 1]],
+    };
+
+    /// All 24 rotations, starting with the identity: every permutation of the axes with every
+    /// choice of signs that keeps the orientation, the other half would mirror.
+    pub fn all() -> Vec<Rotation> {
+        let permutations = [
+            [0, 1, 2],
+            [0, 2, 1],
+            [1, 0, 2],
+            [1, 2, 0],
+            [2, 0, 1],
+            [2, 1, 0],
+        ];
+        let mut rotations = vec![];
+        for permutation in permutations {
+            for signs in 0..8 {
+                let mut matrix = [[0; 3]; 3];
+                for row in 0..3 {
+                    matrix[row][permutation[row]] = if signs & (1 << row) == 0 { 1 } else { -1 };
+                }
+                let rotation = Rotation { matrix };
+                if rotation.determinant() == 1 {
+                    rotations.push(rotation);
+                }
+            }
+        }
+        return rotations;
+    }
+
+    fn determinant(&self) -> isize {
+        let m = &self.matrix;
+        m[0][0] * (m[1][1] * m[2][2] - m[1][2] * m[2][1])
+            - m[0][1] * (m[1][0] * m[2][2] - m[1][2] * m[2][0])
+            + m[0][2] * (m[1][0] * m[2][1] - m[1][1] * m[2][0])
+    }
+
+    pub fn apply(&self, p: Point3) -> Point3 {
+        let row = |r: usize| (0..3).map(|axis| self.matrix[r][axis] * p[axis]).sum();
+        Point3::new(row(0), row(1), row(2))
+    }
+
+    /// The rotation turning back, the transposed matrix.
+    pub fn inverse(&self) -> Rotation {
+        let mut matrix = [[0; 3]; 3];
+        for (r, row) in matrix.iter_mut().enumerate() {
+            for (c, value) in row.iter_mut().enumerate() {
+                *value = self.matrix[c][r];
+            }
+        }
+        Rotation { matrix }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    #[test]
+    fn arithmetic_and_distances() {
+        let a = Point3::new(1, -2, 3);
+        let b = Point3::new(-4, 5, 6);
+        assert_eq!(a + b, Point3::new(-3, 3, 9));
+        assert_eq!(a - b, Point3::new(5, -7, -3));
+        assert_eq!(-a, Point3::new(-1, 2, -3));
+        assert_eq!(a.manhattan_distance(&b), 15);
+        assert_eq!(a.squared_distance(&b), 83);
+        assert_eq!(a.to_string(), "1,-2,3");
+    }
+
+    #[test]
+    fn rotations_are_distinct_and_invertible() {
+        let rotations = Rotation::all();
+        assert_eq!(rotations.len(), 24);
+        assert_eq!(rotations[0], Rotation::IDENTITY);
+        let p = Point3::new(1, 2, 3);
+        let images: HashSet<Point3> = rotations.iter().map(|r| r.apply(p)).collect();
+        assert_eq!(images.len(), 24);
+        for r in &rotations {
+            assert_eq!(r.inverse().apply(r.apply(p)), p);
+            assert_eq!(r.apply(p).manhattan_distance(&Point3::ORIGIN), 6);
+        }
+    }
+
+    #[test]
+    fn rotations_keep_the_orientation() {
+        // a quarter turn around z takes x to y, a mirror would be missing from the list
+        let quarter_turn = Rotation::all()
+            .into_iter()
+            .find(|r| {
+                r.apply(Point3::new(1, 0, 0)) == Point3::new(0, 1, 0)
+                    && r.apply(Point3::new(0, 0, 1)) == Point3::new(0, 0, 1)
+            })
+            .unwrap();
+        assert_eq!(
+            quarter_turn.apply(Point3::new(0, 1, 0)),
+            Point3::new(-1, 0, 0)
+        );
+    }
+}
@@ -1,21 +1,179 @@
+use crate::error::ParseError;
+use regex::{Captures, Regex};
+use std::env;
+use std::fmt::Display;
 use std::fs;
-use std::str;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+/// Where to read a puzzle input from.
+#[derive(Clone, Debug)]
+pub enum Source {
+    File(PathBuf),
+    Stdin,
+}
+
+impl Source {
+    pub fn name(&self) -> String {
+        match self {
+            Source::File(path) => path.display().to_string(),
+            Source::Stdin => String::from("<stdin>"),
+        }
+    }
+}
 
 pub struct Lines {
+    name: String,
     data: String,
 }
 
 impl Lines {
-    pub fn new(file: &str) -> Lines {
-        let filename = format!("./inputs/{}", file);
-        let contents = match fs::read_to_string(filename) {
-            Ok(s) => s,
-            Err(err) => panic!("Could not read file {}: {:?}", file, err),
+    pub fn read(source: &Source) -> io::Result<Lines> {
+        let data = match source {
+            Source::File(path) => fs::read_to_string(path)?,
+            Source::Stdin => {
+                let mut data = String::new();
+                io::stdin().read_to_string(&mut data)?;
+                data
+            }
         };
-        Lines { data: contents }
+        Ok(Lines {
+            name: source.name(),
+            data,
+        })
     }
 
-    pub fn lines(&self) -> std::str::Lines {
+    /// The name of the file the input was read from, used in parse errors.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn lines(&self) -> std::str::Lines<'_> {
         self.data.lines()
     }
+
+    /// The lines together with their position, for parsers that report errors.
+    pub fn numbered(&self) -> impl Iterator<Item = Line<'_>> {
+        self.data.lines().enumerate().map(|(i, text)| Line {
+            file: &self.name,
+            number: i + 1,
+            text,
+        })
+    }
+
+    /// The first line, for inputs consisting of a single line.
+    pub fn first(&self) -> Result<Line<'_>, ParseError> {
+        self.numbered()
+            .next()
+            .ok_or_else(|| self.error_at_end("unexpected end of input"))
+    }
+
+    /// An error located after the last line, e.g. for missing sections.
+    pub fn error_at_end(&self, message: &str) -> ParseError {
+        ParseError {
+            file: self.name.clone(),
+            line: self.data.lines().count() + 1,
+            column: 1,
+            text: String::new(),
+            message: String::from(message),
+        }
+    }
+}
+
+impl From<&str> for Lines {
+    fn from(data: &str) -> Lines {
+        Lines {
+            name: String::from("<input>"),
+            data: String::from(data),
+        }
+    }
+}
+
+/// A single line of an input together with its position.
+#[derive(Clone, Copy, Debug)]
+pub struct Line<'a> {
+    file: &'a str,
+    number: usize,
+    text: &'a str,
+}
+
+/// A standalone line, e.g. a value passed to a public parser outside of an input file.
+impl<'a> From<&'a str> for Line<'a> {
+    fn from(text: &'a str) -> Line<'a> {
+        Line {
+            file: "<input>",
+            number: 1,
+            text,
+        }
+    }
+}
+
+impl<'a> Line<'a> {
+    pub fn text(&self) -> &'a str {
+        self.text
+    }
+
+    pub fn number(&self) -> usize {
+        self.number
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.text.is_empty()
+    }
+
+    /// An error at a 1-based column of this line.
+    pub fn error(&self, column: usize, message: &str) -> ParseError {
+        ParseError {
+            file: String::from(self.file),
+            line: self.number,
+            column,
+            text: String::from(self.text),
+            message: String::from(message),
+        }
+    }
+
+    /// An error pointing at `part`, which has to be a slice of this line's text. Anything else is
+    /// reported at the start of the line.
+    pub fn error_at(&self, part: &str, message: &str) -> ParseError {
+        let start = self.text.as_ptr() as usize;
+        let offset = (part.as_ptr() as usize).wrapping_sub(start);
+        let column = match self.text.get(..offset) {
+            Some(before) => before.chars().count() + 1,
+            _ => 1,
+        };
+        self.error(column, message)
+    }
+
+    /// Parses `part`, a slice of this line's text, reporting failures at its position.
+    pub fn parse_field<T>(&self, part: &str) -> Result<T, ParseError>
+    where
+        T: FromStr,
+        T::Err: Display,
+    {
+        part.trim()
+            .parse()
+            .map_err(|err| self.error_at(part, &format!("invalid value '{}': {}", part, err)))
+    }
+
+    /// Matches the whole line against `re`.
+    pub fn captures(&self, re: &Regex) -> Result<Captures<'a>, ParseError> {
+        re.captures(self.text).ok_or_else(|| {
+            self.error(1, &format!("expected a line matching '{}'", re.as_str()))
+        })
+    }
+}
+
+/// The directory containing the `dayN.txt` inputs. `AOC_INPUTS_DIR` takes precedence, then
+/// `./inputs` if it exists, and finally the `inputs` directory next to this crate's manifest so
+/// the binary also works when started from somewhere else.
+pub fn default_inputs_dir() -> PathBuf {
+    if let Some(dir) = env::var_os("AOC_INPUTS_DIR") {
+        return PathBuf::from(dir);
+    }
+    let local = Path::new("inputs");
+    if local.is_dir() {
+        return local.to_path_buf();
+    }
+    return Path::new(env!("CARGO_MANIFEST_DIR")).join("inputs");
 }
@@ -0,0 +1,573 @@
+use crate::answer::{self, Answer};
+use crate::bench::{self, BenchResult, Baseline};
+use crate::error::ParseError;
+use crate::json;
+use crate::netpbm::Image;
+use crate::readfile::{self, Source};
+use crate::solver::{self, Day, Params, Solver};
+use crate::visualize::Animation;
+use std::any::Any;
+use std::collections::VecDeque;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::panic::{self, AssertUnwindSafe};
+use std::path::Path;
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// The answers and timings of a day. A part that was not selected with `--part` has no answer
+/// and takes no time.
+pub struct DayResult {
+    pub day: u8,
+    pub parse_time: Duration,
+    pub part1: Option<Answer>,
+    pub part1_time: Duration,
+    pub part2: Option<Answer>,
+    pub part2_time: Duration,
+}
+
+impl DayResult {
+    pub fn total_time(&self) -> Duration {
+        self.parse_time + self.part1_time + self.part2_time
+    }
+}
+
+/// Why a day could not be run.
+#[derive(Debug)]
+pub enum RunError {
+    Read(String, io::Error),
+    Parse(ParseError),
+    /// A `--param` value the solver does not accept.
+    Param(String),
+    /// The solver panicked while parsing or solving a part, the other days still run.
+    Panic(String),
+}
+
+impl fmt::Display for RunError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RunError::Read(name, err) => write!(f, "Could not read {}: {}", name, err),
+            RunError::Parse(err) => write!(f, "{}", err.render()),
+            RunError::Param(err) => write!(f, "{}", err),
+            RunError::Panic(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+/// Parses a day selection: a single day (`15`), an inclusive range (`1-10`) or `all`.
+/// Ranges silently skip days that are not implemented, a single day must exist.
+pub fn select_days(selection: &str) -> Result<Vec<&'static Day>, String> {
+    if selection == "all" {
+        return Ok(solver::DAYS.iter().collect());
+    }
+
+    if let Some((from, to)) = selection.split_once('-') {
+        let from = parse_day_number(from)?;
+        let to = parse_day_number(to)?;
+        if from > to {
+            return Err(format!("Invalid day range: {}", selection));
+        }
+        return Ok(solver::DAYS
+            .iter()
+            .filter(|d| d.number >= from && d.number <= to)
+            .collect());
+    }
+
+    let number = parse_day_number(selection)?;
+    match solver::find(number) {
+        Some(day) => Ok(vec![day]),
+        None => Err(format!("Day {} is not implemented", number)),
+    }
+}
+
+fn parse_day_number(s: &str) -> Result<u8, String> {
+    match s.trim().parse::<u8>() {
+        Ok(n) if (1..=25).contains(&n) => Ok(n),
+        _ => Err(format!("Invalid day: {}", s)),
+    }
+}
+
+/// The input of `day` inside `inputs_dir`, unless an explicit source was given.
+pub fn input_source(day: &Day, input: Option<&Source>, inputs_dir: &Path) -> Source {
+    match input {
+        Some(source) => source.clone(),
+        None => Source::File(inputs_dir.join(day.input_file())),
+    }
+}
+
+/// Whether the input of `day` inside `inputs_dir` is missing or empty, like the one `aoc new`
+/// writes before the puzzle input is pasted in.
+pub fn has_no_input(day: &Day, inputs_dir: &Path) -> bool {
+    !fs::metadata(inputs_dir.join(day.input_file())).is_ok_and(|m| m.len() > 0)
+}
+
+/// Reads and parses the input of a day and applies the parameters, the time excludes reading.
+fn load(
+    day: &Day,
+    source: &Source,
+    params: &Params,
+) -> Result<(Box<dyn Solver>, Duration), RunError> {
+    let lines =
+        readfile::Lines::read(source).map_err(|err| RunError::Read(source.name(), err))?;
+
+    let start = Instant::now();
+    let mut solver = day.load(&lines).map_err(RunError::Parse)?;
+    solver.configure(params).map_err(RunError::Param)?;
+    Ok((solver, start.elapsed()))
+}
+
+/// Whether `part` is run when `--part` selected `selected`, both parts run by default.
+fn is_selected(selected: Option<u8>, part: u8) -> bool {
+    selected.is_none_or(|p| p == part)
+}
+
+/// Runs the selected parts of a day in turn, without the other part its answer is `None`.
+fn run_part(
+    solver: &dyn Solver,
+    selected: Option<u8>,
+    part: u8,
+) -> (Option<Answer>, Duration) {
+    if !is_selected(selected, part) {
+        return (None, Duration::ZERO);
+    }
+    let start = Instant::now();
+    let answer = if part == 1 {
+        solver.part1()
+    } else {
+        solver.part2()
+    };
+    (Some(answer), start.elapsed())
+}
+
+pub fn run_day(
+    day: &Day,
+    source: &Source,
+    part: Option<u8>,
+    params: &Params,
+) -> Result<DayResult, RunError> {
+    let (solver, parse_time) = load(day, source, params)?;
+    let (part1, part1_time) = run_part(solver.as_ref(), part, 1);
+    let (part2, part2_time) = run_part(solver.as_ref(), part, 2);
+    Ok(DayResult {
+        day: day.number,
+        parse_time,
+        part1,
+        part1_time,
+        part2,
+        part2_time,
+    })
+}
+
+/// Loads a day and plays its visualization, `false` if the day has none.
+pub fn visualize(
+    day: &Day,
+    source: &Source,
+    params: &Params,
+    animation: &mut Animation,
+) -> Result<bool, RunError> {
+    let (solver, _) = load(day, source, params)?;
+    Ok(solver.visualize(animation))
+}
+
+/// Loads a day and draws its image, `None` if the day has none.
+pub fn image(day: &Day, source: &Source, params: &Params) -> Result<Option<Image>, RunError> {
+    let (solver, _) = load(day, source, params)?;
+    Ok(solver.image())
+}
+
+/// The number of threads used by default, one per CPU.
+pub fn default_jobs() -> usize {
+    thread::available_parallelism().map(|n| n.get()).unwrap_or(1)
+}
+
+/// Work items of [`run_days`], loading a day schedules its two parts.
+enum Task {
+    Load(usize),
+    Part(usize, u8),
+}
+
+#[derive(Default)]
+struct Slot {
+    solver: Option<Arc<dyn Solver>>,
+    parse_time: Duration,
+    parts: [Option<(Option<Answer>, Duration)>; 2],
+    error: Option<RunError>,
+}
+
+struct Queue {
+    tasks: VecDeque<Task>,
+    running: usize,
+    slots: Vec<Slot>,
+}
+
+struct Pool<'a> {
+    days: &'a [(&'a Day, Source)],
+    part: Option<u8>,
+    params: &'a Params,
+    queue: Mutex<Queue>,
+    changed: Condvar,
+}
+
+impl Pool<'_> {
+    /// Blocks until a task is available, `None` once everything is done.
+    fn next_task(&self) -> Option<Task> {
+        let mut queue = self.queue.lock().unwrap();
+        loop {
+            if let Some(task) = queue.tasks.pop_front() {
+                queue.running += 1;
+                return Some(task);
+            }
+            if queue.running == 0 {
+                return None;
+            }
+            queue = self.changed.wait(queue).unwrap();
+        }
+    }
+
+    fn run(&self, task: Task) {
+        match task {
+            Task::Load(i) => {
+                let (day, source) = &self.days[i];
+                let loaded = load(day, source, self.params);
+                let mut queue = self.queue.lock().unwrap();
+                match loaded {
+                    Ok((solver, parse_time)) => {
+                        queue.slots[i].solver = Some(Arc::from(solver));
+                        queue.slots[i].parse_time = parse_time;
+                        // run the parts before loading further days, results come in sooner
+                        for part in [2, 1] {
+                            if is_selected(self.part, part) {
+                                queue.tasks.push_front(Task::Part(i, part));
+                            }
+                        }
+                    }
+                    Err(err) => queue.slots[i].error = Some(err),
+                }
+            }
+            Task::Part(i, part) => {
+                let solver = self.queue.lock().unwrap().slots[i].solver.clone().unwrap();
+                let result = run_part(solver.as_ref(), self.part, part);
+                self.queue.lock().unwrap().slots[i].parts[part as usize - 1] = Some(result);
+            }
+        }
+    }
+
+    /// Runs a task, a panic of the solver becomes the error of its day instead of taking down the
+    /// whole run.
+    fn run_caught(&self, task: Task) {
+        let (i, step) = match task {
+            Task::Load(i) => (i, String::from("Parsing")),
+            Task::Part(i, part) => (i, format!("Part {}", part)),
+        };
+        if let Err(payload) = panic::catch_unwind(AssertUnwindSafe(|| self.run(task))) {
+            let message = format!("{} panicked: {}", step, panic_message(payload.as_ref()));
+            let mut queue = self.queue.lock().unwrap_or_else(|err| err.into_inner());
+            queue.slots[i].error.get_or_insert(RunError::Panic(message));
+        }
+    }
+}
+
+/// The message given to `panic!`, payloads of other types have none.
+fn panic_message(payload: &(dyn Any + Send)) -> &str {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message
+    } else {
+        "unknown cause"
+    }
+}
+
+/// Marks a task as finished when dropped, also if the solver panicked so the other workers
+/// do not wait forever.
+struct Finished<'a, 'b>(&'a Pool<'b>);
+
+impl Drop for Finished<'_, '_> {
+    fn drop(&mut self) {
+        let mut queue = self.0.queue.lock().unwrap_or_else(|err| err.into_inner());
+        queue.running -= 1;
+        self.0.changed.notify_all();
+    }
+}
+
+/// Runs several days on a pool of `jobs` threads. Days are independent and the two parts of a
+/// day only share the parsed input, so they are all scheduled separately. The results are in the
+/// order of `days`, regardless of which finished first. Only the `part` is run if given, the
+/// parameters apply to every day.
+pub fn run_days(
+    days: &[(&Day, Source)],
+    part: Option<u8>,
+    params: &Params,
+    jobs: usize,
+) -> Vec<Result<DayResult, RunError>> {
+    let pool = Pool {
+        days,
+        part,
+        params,
+        queue: Mutex::new(Queue {
+            tasks: (0..days.len()).map(Task::Load).collect(),
+            running: 0,
+            slots: days.iter().map(|_| Slot::default()).collect(),
+        }),
+        changed: Condvar::new(),
+    };
+
+    let workers = jobs.clamp(1, (2 * days.len()).max(1));
+    thread::scope(|scope| {
+        for _ in 0..workers {
+            scope.spawn(|| {
+                while let Some(task) = pool.next_task() {
+                    let _finished = Finished(&pool);
+                    pool.run_caught(task);
+                }
+            });
+        }
+    });
+
+    let queue = pool.queue.into_inner().unwrap();
+    days.iter()
+        .zip(queue.slots)
+        .map(|((day, _), slot)| {
+            if let Some(err) = slot.error {
+                return Err(err);
+            }
+            let [part1, part2] = slot.parts;
+            let (part1, part1_time) = part1.unwrap_or((None, Duration::ZERO));
+            let (part2, part2_time) = part2.unwrap_or((None, Duration::ZERO));
+            Ok(DayResult {
+                day: day.number,
+                parse_time: slot.parse_time,
+                part1,
+                part1_time,
+                part2,
+                part2_time,
+            })
+        })
+        .collect()
+}
+
+/// One JSON record per part, printed on its own line:
+/// `{"day":1,"part":1,"answer":1832,"parse_time_ns":1250,"time_ns":830,"error":null}`.
+pub fn print_json_records(result: &DayResult) {
+    let parts = [
+        (1, &result.part1, result.part1_time),
+        (2, &result.part2, result.part2_time),
+    ];
+    for (part, answer, time) in parts {
+        let Some(answer) = answer else {
+            continue;
+        };
+        let record = json::object(&[
+            ("day", result.day.to_string()),
+            ("part", part.to_string()),
+            ("answer", json::answer(answer)),
+            ("parse_time_ns", result.parse_time.as_nanos().to_string()),
+            ("time_ns", time.as_nanos().to_string()),
+            ("error", String::from("null")),
+        ]);
+        println!("{}", record);
+    }
+}
+
+/// A record for a day that could not be run, without part and timings.
+pub fn print_json_error(day: &Day, err: &RunError) {
+    // the compact `file:line:column: message` form, the rendered snippet spans several lines
+    let message = match err {
+        RunError::Read(..) => err.to_string(),
+        RunError::Parse(err) => err.to_string(),
+        RunError::Param(err) | RunError::Panic(err) => err.clone(),
+    };
+    let record = json::object(&[
+        ("day", day.number.to_string()),
+        ("part", String::from("null")),
+        ("answer", String::from("null")),
+        ("parse_time_ns", String::from("null")),
+        ("time_ns", String::from("null")),
+        ("error", json::string(&message)),
+    ]);
+    println!("{}", record);
+}
+
+pub fn print_answers(result: &DayResult) {
+    for (part, answer) in [(1, &result.part1), (2, &result.part2)] {
+        if let Some(answer) = answer {
+            println!("{}", answer::format_part(part, answer));
+        }
+    }
+}
+
+fn format_duration(d: Duration) -> String {
+    let micros = d.as_secs_f64() * 1_000_000.0;
+    if micros < 1000.0 {
+        format!("{:.1}µs", micros)
+    } else if micros < 1_000_000.0 {
+        format!("{:.2}ms", micros / 1000.0)
+    } else {
+        format!("{:.3}s", micros / 1_000_000.0)
+    }
+}
+
+/// The time of a part, `-` if it was not run.
+fn format_part_duration(answer: &Option<Answer>, d: Duration) -> String {
+    match answer {
+        Some(_) => format_duration(d),
+        None => String::from("-"),
+    }
+}
+
+pub fn print_timing_table(results: &[DayResult]) {
+    println!(
+        "{:>5} | {:>10} | {:>10} | {:>10} | {:>10}",
+        "Day", "Parse", "Part 1", "Part 2", "Total"
+    );
+    println!("{}", "-".repeat(58));
+    for r in results {
+        println!(
+            "{:>5} | {:>10} | {:>10} | {:>10} | {:>10}",
+            r.day,
+            format_duration(r.parse_time),
+            format_part_duration(&r.part1, r.part1_time),
+            format_part_duration(&r.part2, r.part2_time),
+            format_duration(r.total_time())
+        );
+    }
+    println!("{}", "-".repeat(58));
+    let sum = |f: fn(&DayResult) -> Duration| results.iter().map(f).sum::<Duration>();
+    println!(
+        "{:>5} | {:>10} | {:>10} | {:>10} | {:>10}",
+        "Total",
+        format_duration(sum(|r| r.parse_time)),
+        format_duration(sum(|r| r.part1_time)),
+        format_duration(sum(|r| r.part2_time)),
+        format_duration(sum(|r| r.total_time()))
+    );
+}
+
+/// The statistics of `aoc bench`, with the change of the median if a baseline is given.
+pub fn print_bench_table(result: &BenchResult, baseline: Option<&Baseline>) {
+    println!(
+        "{:>5} | {:>10} | {:>10} | {:>10} | {:>10} | {:>10}{}",
+        "Step",
+        "Min",
+        "Median",
+        "P95",
+        "Allocs",
+        "Bytes",
+        if baseline.is_some() { " | Baseline" } else { "" }
+    );
+    println!("{}", "-".repeat(if baseline.is_some() { 82 } else { 71 }));
+    for (step, stats) in bench::STEPS.iter().zip(&result.steps) {
+        let change = match baseline {
+            Some(baseline) => match baseline.get(&(result.day, String::from(*step))) {
+                Some(before) => format!(" | {:>8}", bench::compare_median(stats, before)),
+                None => format!(" | {:>8}", "-"),
+            },
+            None => String::new(),
+        };
+        println!(
+            "{:>5} | {:>10} | {:>10} | {:>10} | {:>10} | {:>10}{}",
+            step,
+            format_duration(stats.min),
+            format_duration(stats.median),
+            format_duration(stats.p95),
+            stats.allocations,
+            stats.bytes,
+            change
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn parallel_results_keep_the_order_of_the_days() {
+        let dir = std::env::temp_dir().join(format!("aoc-runner-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let depths = "199\n200\n208\n210\n200\n207\n240\n269\n260\n263";
+        fs::write(dir.join("day1.txt"), depths).unwrap();
+        fs::write(dir.join("day6.txt"), "3,4,3,1,2").unwrap();
+        fs::write(dir.join("day7.txt"), "16,1,2,x").unwrap();
+
+        let days: Vec<(&Day, Source)> = [6, 7, 1]
+            .iter()
+            .map(|n| solver::find(*n).unwrap())
+            .map(|day| (day, input_source(day, None, &dir)))
+            .collect();
+        let results = run_days(&days, None, &Params::default(), 4);
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(results.len(), 3);
+        let day6 = results[0].as_ref().unwrap();
+        assert_eq!((day6.day, &day6.part1), (6, &Some(Answer::Number(5934))));
+        assert!(matches!(&results[1], Err(RunError::Parse(err)) if err.column == 8));
+        let day1 = results[2].as_ref().unwrap();
+        assert_eq!((day1.day, &day1.part2), (1, &Some(Answer::Number(5))));
+    }
+
+    #[test]
+    fn selected_part_with_params() {
+        let dir = std::env::temp_dir().join(format!("aoc-runner-part-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("day6.txt"), "3,4,3,1,2").unwrap();
+
+        let day = solver::find(6).unwrap();
+        let mut params = Params::default();
+        params.insert("days=18").unwrap();
+        let days = [(day, input_source(day, None, &dir))];
+        let result = run_days(&days, Some(2), &params, 2).remove(0).unwrap();
+        params.insert("days=x").unwrap();
+        let invalid = run_day(day, &input_source(day, None, &dir), None, &params);
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(result.part1, None);
+        assert_eq!(result.part2, Some(Answer::Number(26)));
+        assert!(matches!(invalid, Err(RunError::Param(_))));
+    }
+
+    struct Panicking;
+
+    impl Solver for Panicking {
+        fn parse(_lines: &readfile::Lines) -> Result<Self, ParseError> {
+            Ok(Panicking)
+        }
+
+        fn part1(&self) -> Answer {
+            panic!("no answer")
+        }
+
+        fn part2(&self) -> Answer {
+            Answer::Number(2)
+        }
+    }
+
+    #[test]
+    fn panicking_solver_fails_only_its_day() {
+        let dir = std::env::temp_dir().join(format!("aoc-runner-panic-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("day6.txt"), "3,4,3,1,2").unwrap();
+        fs::write(dir.join("day26.txt"), "").unwrap();
+
+        let panicking = Day::of::<Panicking>(26);
+        let day6 = solver::find(6).unwrap();
+        let days = [
+            (&panicking, input_source(&panicking, None, &dir)),
+            (day6, input_source(day6, None, &dir)),
+        ];
+        let results = run_days(&days, None, &Params::default(), 2);
+        fs::remove_dir_all(&dir).unwrap();
+
+        match &results[0] {
+            Err(err @ RunError::Panic(_)) => {
+                assert_eq!(err.to_string(), "Part 1 panicked: no answer")
+            }
+            _ => panic!("expected a panic error"),
+        }
+        let day6 = results[1].as_ref().unwrap();
+        assert_eq!(day6.part1, Some(Answer::Number(5934)));
+    }
+}
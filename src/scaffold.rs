@@ -0,0 +1,166 @@
+//! `aoc new`: adds a day to the source tree, based on `src/template.rs`.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const TEMPLATE: &str = include_str!("template.rs");
+
+/// The template with its `Template` solver renamed for `day`.
+pub fn render_template(day: u8) -> String {
+    TEMPLATE.replace("Template", &format!("Day{}", day))
+}
+
+/// Adds `pub mod dayN;` to lib.rs, keeping the day modules sorted like rustfmt does.
+pub fn register_module(lib_rs: &str, day: u8) -> Result<String, String> {
+    let module = format!("pub mod day{};", day);
+    let mut lines: Vec<&str> = lib_rs.lines().collect();
+    if lines.contains(&module.as_str()) {
+        return Err(format!("day{} is already declared in lib.rs", day));
+    }
+    let name = |line: &str| String::from(line.trim_start_matches("pub mod ").trim_end_matches(';'));
+    let days: Vec<usize> = (0..lines.len())
+        .filter(|i| lines[*i].starts_with("pub mod day"))
+        .collect();
+    let position = days
+        .iter()
+        .copied()
+        .find(|i| name(lines[*i]) > format!("day{}", day))
+        .or_else(|| days.last().map(|i| i + 1))
+        .ok_or("lib.rs does not declare any day modules")?;
+    lines.insert(position, &module);
+    Ok(lines.join("\n") + "\n")
+}
+
+/// Adds the `day!` entry to the `DAYS` registry in solver.rs, ordered by day number.
+pub fn register_day(solver_rs: &str, day: u8) -> Result<String, String> {
+    let entry = format!("    day!({}, day{}::Day{}),", day, day, day);
+    let mut lines: Vec<&str> = solver_rs.lines().collect();
+    let entries: Vec<(usize, u8)> = lines
+        .iter()
+        .enumerate()
+        .filter_map(|(i, l)| {
+            let number = l.trim().strip_prefix("day!(")?.split(',').next()?;
+            Some((i, number.parse().ok()?))
+        })
+        .collect();
+    if entries.iter().any(|(_, n)| *n == day) {
+        return Err(format!("Day {} is already registered in solver.rs", day));
+    }
+    let position = entries
+        .iter()
+        .find(|(_, n)| *n > day)
+        .map(|(i, _)| *i)
+        .or_else(|| entries.last().map(|(i, _)| i + 1))
+        .ok_or("solver.rs does not contain the DAYS registry")?;
+    lines.insert(position, &entry);
+    Ok(lines.join("\n") + "\n")
+}
+
+fn read(path: &Path) -> Result<String, String> {
+    fs::read_to_string(path).map_err(|err| format!("Could not read {}: {}", path.display(), err))
+}
+
+fn write(path: &Path, contents: &str) -> Result<(), String> {
+    fs::write(path, contents).map_err(|err| format!("Could not write {}: {}", path.display(), err))
+}
+
+/// Creates `src/dayN.rs` and an empty input in `inputs_dir`, and registers the day in lib.rs
+/// and solver.rs of the crate in `root`. Returns the created and changed files. Nothing is
+/// written if the day already exists.
+pub fn new_day(root: &Path, inputs_dir: &Path, day: u8) -> Result<Vec<PathBuf>, String> {
+    if !(1..=25).contains(&day) {
+        return Err(format!("Invalid day: {}", day));
+    }
+    let src = root.join("src");
+    let source = src.join(format!("day{}.rs", day));
+    if source.exists() {
+        return Err(format!("{} already exists", source.display()));
+    }
+    let lib_path = src.join("lib.rs");
+    let solver_path = src.join("solver.rs");
+    let lib_rs = register_module(&read(&lib_path)?, day)?;
+    let solver_rs = register_day(&read(&solver_path)?, day)?;
+
+    write(&source, &render_template(day))?;
+    write(&lib_path, &lib_rs)?;
+    write(&solver_path, &solver_rs)?;
+    let mut files = vec![source, lib_path, solver_path];
+
+    let input = inputs_dir.join(format!("day{}.txt", day));
+    if !input.exists() {
+        write(&input, "")?;
+        files.push(input);
+    }
+    return Ok(files);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn renames_the_template() {
+        let source = render_template(19);
+        assert!(source.contains("pub struct Day19 {}"));
+        assert!(source.contains("Day19::parse(&readfile::Lines::from(EXAMPLE))"));
+        assert!(!source.contains("Template"));
+    }
+
+    #[test]
+    fn modules_stay_sorted() {
+        let lib_rs = "\
+pub mod answer;
+pub mod day1;
+pub mod day18;
+pub mod day2;
+pub mod error;
+";
+        let registered = register_module(lib_rs, 19).unwrap();
+        assert!(registered.contains("pub mod day18;\npub mod day19;\npub mod day2;\n"));
+        let registered = register_module(lib_rs, 9).unwrap();
+        assert!(registered.contains("pub mod day2;\npub mod day9;\npub mod error;\n"));
+        assert!(register_module(lib_rs, 2).is_err());
+    }
+
+    #[test]
+    fn registry_is_ordered_by_day() {
+        let solver_rs = "\
+pub const DAYS: &[Day] = &[
+    day!(18, day18::Day18),
+    day!(20, day20::Day20),
+];
+";
+        let registered = register_day(solver_rs, 19).unwrap();
+        assert!(registered.contains("Day18),\n    day!(19, day19::Day19),\n    day!(20,"));
+        let registered = register_day(solver_rs, 25).unwrap();
+        assert!(registered.contains("Day20),\n    day!(25, day25::Day25),\n];\n"));
+        assert!(register_day(solver_rs, 18).is_err());
+    }
+
+    #[test]
+    fn new_day_writes_and_registers() {
+        let root = std::env::temp_dir().join(format!("aoc-scaffold-{}", std::process::id()));
+        let inputs_dir = root.join("inputs");
+        fs::create_dir_all(root.join("src")).unwrap();
+        fs::create_dir_all(&inputs_dir).unwrap();
+        fs::write(root.join("src/lib.rs"), "pub mod day18;\npub mod day20;\n").unwrap();
+        let solver_rs = "pub const DAYS: &[Day] = &[\n    day!(18, day18::Day18),\n];\n";
+        fs::write(root.join("src/solver.rs"), solver_rs).unwrap();
+
+        let files = new_day(&root, &inputs_dir, 19).unwrap();
+        let again = new_day(&root, &inputs_dir, 19);
+        let read = |path: &str| fs::read_to_string(root.join(path)).unwrap();
+        let source = read("src/day19.rs");
+        let lib_rs = read("src/lib.rs");
+        let solver_rs = read("src/solver.rs");
+        let input = read("inputs/day19.txt");
+        fs::remove_dir_all(&root).unwrap();
+
+        assert_eq!(files.len(), 4);
+        assert_eq!(source, render_template(19));
+        assert_eq!(lib_rs, "pub mod day18;\npub mod day19;\npub mod day20;\n");
+        assert!(solver_rs.contains("    day!(19, day19::Day19),\n];"));
+        assert_eq!(input, "");
+        assert!(again.unwrap_err().ends_with("day19.rs already exists"));
+    }
+}
@@ -0,0 +1,308 @@
+//! Searches over arbitrary states, for the days that explore a graph of moves.
+//!
+//! The states only need to be hashable, `neighbours` functions return the states one move
+//! away, with the cost of the move for the weighted searches.
+
+use std::cmp::Reverse;
+use std::collections::hash_map::Entry;
+use std::collections::{BinaryHeap, HashMap, VecDeque};
+use std::hash::Hash;
+
+/// The cheapest way found to a goal: its total cost and the states from the start to the goal.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Path<S> {
+    pub cost: usize,
+    pub states: Vec<S>,
+}
+
+/// A state reached by a search: its cost from the start and where it was reached from.
+struct Visit<S> {
+    state: S,
+    cost: usize,
+    previous: Option<usize>,
+}
+
+/// A* (Dijkstra with a heuristic of 0) stepping through the states in the order they are
+/// settled, each with its final cost. Meant for callers that watch the progress, [`dijkstra`]
+/// and [`astar`] just return the path to a goal.
+pub struct Search<S, N, H> {
+    neighbours: N,
+    heuristic: H,
+    /// The states in the order they were reached, the heap and `previous` refer to them by
+    /// their position.
+    visits: Vec<Visit<S>>,
+    index: HashMap<S, usize>,
+    heap: BinaryHeap<Reverse<(usize, usize)>>,
+}
+
+impl<S, N, I, H> Search<S, N, H>
+where
+    S: Clone + Eq + Hash,
+    N: FnMut(&S) -> I,
+    I: IntoIterator<Item = (S, usize)>,
+    H: FnMut(&S) -> usize,
+{
+    pub fn new(start: S, neighbours: N, mut heuristic: H) -> Search<S, N, H> {
+        let mut heap = BinaryHeap::new();
+        heap.push(Reverse((heuristic(&start), 0)));
+        return Search {
+            neighbours,
+            heuristic,
+            visits: vec![Visit {
+                state: start.clone(),
+                cost: 0,
+                previous: None,
+            }],
+            index: HashMap::from([(start, 0)]),
+            heap,
+        };
+    }
+
+    /// The cost of the cheapest way to `state` found so far, final once it was settled.
+    pub fn cost(&self, state: &S) -> Option<usize> {
+        self.index.get(state).map(|i| self.visits[*i].cost)
+    }
+
+    /// The states from the start to `state` along the cheapest way found so far, following
+    /// the state each one was reached from.
+    pub fn path(&self, state: &S) -> Option<Vec<S>> {
+        let mut i = *self.index.get(state)?;
+        let mut states = vec![self.visits[i].state.clone()];
+        while let Some(previous) = self.visits[i].previous {
+            states.push(self.visits[previous].state.clone());
+            i = previous;
+        }
+        states.reverse();
+        return Some(states);
+    }
+
+    fn reach(&mut self, state: S, cost: usize, previous: usize) {
+        let i = match self.index.entry(state) {
+            Entry::Occupied(entry) => {
+                let i = *entry.get();
+                if self.visits[i].cost <= cost {
+                    return;
+                }
+                self.visits[i].cost = cost;
+                self.visits[i].previous = Some(previous);
+                i
+            }
+            Entry::Vacant(entry) => {
+                let i = self.visits.len();
+                self.visits.push(Visit {
+                    state: entry.key().clone(),
+                    cost,
+                    previous: Some(previous),
+                });
+                entry.insert(i);
+                i
+            }
+        };
+        let estimate = cost + (self.heuristic)(&self.visits[i].state);
+        self.heap.push(Reverse((estimate, i)));
+    }
+}
+
+/// Settles the next state and reaches its neighbours, so their costs are known right away.
+impl<S, N, I, H> Iterator for Search<S, N, H>
+where
+    S: Clone + Eq + Hash,
+    N: FnMut(&S) -> I,
+    I: IntoIterator<Item = (S, usize)>,
+    H: FnMut(&S) -> usize,
+{
+    type Item = (S, usize);
+
+    fn next(&mut self) -> Option<(S, usize)> {
+        while let Some(Reverse((estimate, i))) = self.heap.pop() {
+            let cost = self.visits[i].cost;
+            if estimate > cost + (self.heuristic)(&self.visits[i].state) {
+                // reached more cheaply after this entry was pushed
+                continue;
+            }
+            let state = self.visits[i].state.clone();
+            for (next, step) in (self.neighbours)(&state) {
+                self.reach(next, cost + step, i);
+            }
+            return Some((state, cost));
+        }
+        return None;
+    }
+}
+
+/// Dijkstra's algorithm from `start` to the first state for which `is_goal` holds.
+pub fn dijkstra<S, N, I, G>(start: S, neighbours: N, is_goal: G) -> Option<Path<S>>
+where
+    S: Clone + Eq + Hash,
+    N: FnMut(&S) -> I,
+    I: IntoIterator<Item = (S, usize)>,
+    G: FnMut(&S) -> bool,
+{
+    astar(start, neighbours, |_| 0, is_goal)
+}
+
+/// A* from `start` to the first state for which `is_goal` holds. The path is only guaranteed
+/// to be the cheapest if `heuristic` never overestimates the remaining cost.
+pub fn astar<S, N, I, H, G>(
+    start: S,
+    neighbours: N,
+    heuristic: H,
+    mut is_goal: G,
+) -> Option<Path<S>>
+where
+    S: Clone + Eq + Hash,
+    N: FnMut(&S) -> I,
+    I: IntoIterator<Item = (S, usize)>,
+    H: FnMut(&S) -> usize,
+    G: FnMut(&S) -> bool,
+{
+    let mut search = Search::new(start, neighbours, heuristic);
+    while let Some((state, cost)) = search.next() {
+        if is_goal(&state) {
+            let states = search.path(&state).unwrap();
+            return Some(Path { cost, states });
+        }
+    }
+    return None;
+}
+
+/// Breadth-first search for the fewest moves from `start` to a state for which `is_goal`
+/// holds, the cost of the path is the number of moves.
+pub fn bfs<S, N, I, G>(start: S, mut neighbours: N, mut is_goal: G) -> Option<Path<S>>
+where
+    S: Clone + Eq + Hash,
+    N: FnMut(&S) -> I,
+    I: IntoIterator<Item = S>,
+    G: FnMut(&S) -> bool,
+{
+    let mut previous: HashMap<S, Option<S>> = HashMap::from([(start.clone(), None)]);
+    let mut queue = VecDeque::from([start]);
+    while let Some(state) = queue.pop_front() {
+        if is_goal(&state) {
+            let mut states = vec![state];
+            while let Some(Some(p)) = previous.get(states.last().unwrap()) {
+                states.push(p.clone());
+            }
+            states.reverse();
+            return Some(Path {
+                cost: states.len() - 1,
+                states,
+            });
+        }
+        for next in neighbours(&state) {
+            if let Entry::Vacant(entry) = previous.entry(next.clone()) {
+                entry.insert(Some(state.clone()));
+                queue.push_back(next);
+            }
+        }
+    }
+    return None;
+}
+
+/// The number of different paths from `start` to states for which `is_goal` holds, paths end
+/// at the first goal. The states must not form cycles, the count of every state is only
+/// computed once.
+pub fn count_paths<S, N, I, G>(start: S, mut neighbours: N, mut is_goal: G) -> usize
+where
+    S: Clone + Eq + Hash,
+    N: FnMut(&S) -> I,
+    I: IntoIterator<Item = S>,
+    G: FnMut(&S) -> bool,
+{
+    fn count<S, N, I, G>(
+        state: S,
+        neighbours: &mut N,
+        is_goal: &mut G,
+        counts: &mut HashMap<S, usize>,
+    ) -> usize
+    where
+        S: Clone + Eq + Hash,
+        N: FnMut(&S) -> I,
+        I: IntoIterator<Item = S>,
+        G: FnMut(&S) -> bool,
+    {
+        if is_goal(&state) {
+            return 1;
+        }
+        if let Some(n) = counts.get(&state) {
+            return *n;
+        }
+        let next: Vec<S> = neighbours(&state).into_iter().collect();
+        let n = next
+            .into_iter()
+            .map(|s| count(s, neighbours, is_goal, counts))
+            .sum();
+        counts.insert(state, n);
+        return n;
+    }
+
+    count(start, &mut neighbours, &mut is_goal, &mut HashMap::new())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Moves along a line of numbers, a step right costs 1 and jumping to the double costs 3.
+    fn moves(n: &usize) -> Vec<(usize, usize)> {
+        vec![(n + 1, 1), (2 * n, 3)]
+    }
+
+    #[test]
+    fn cheapest_path() {
+        let path = dijkstra(1, moves, |n| *n == 12).unwrap();
+        assert_eq!(path.cost, 8);
+        assert_eq!(path.states, vec![1, 2, 3, 6, 12]);
+    }
+
+    #[test]
+    fn astar_with_heuristic_finds_the_same_cost() {
+        // 1 per missing number up to the goal would overestimate the jumps, a quarter does not
+        let path = astar(1, moves, |n| 12usize.saturating_sub(*n) / 4, |n| *n == 12).unwrap();
+        assert_eq!(path.cost, 8);
+    }
+
+    #[test]
+    fn unreachable_goal() {
+        let neighbours = |n: &usize| if *n < 5 { vec![(n + 1, 1)] } else { vec![] };
+        assert_eq!(dijkstra(0, neighbours, |n| *n == 10), None);
+        let path = dijkstra(0, neighbours, |n| *n == 0).unwrap();
+        assert_eq!((path.cost, path.states), (0, vec![0]));
+    }
+
+    #[test]
+    fn states_are_settled_by_cost() {
+        let mut search = Search::new(1, moves, |_| 0);
+        let settled: Vec<(usize, usize)> = search.by_ref().take(5).collect();
+        assert_eq!(settled, vec![(1, 0), (2, 1), (3, 2), (4, 3), (5, 4)]);
+        // reached but not settled yet
+        assert_eq!(search.cost(&10), Some(7));
+        assert_eq!(search.path(&10), Some(vec![1, 2, 3, 4, 5, 10]));
+        assert_eq!(search.cost(&11), None);
+    }
+
+    #[test]
+    fn bfs_counts_moves() {
+        let path = bfs(1, |n: &usize| [n + 1, 2 * n], |n| *n == 12).unwrap();
+        assert_eq!(path.cost, 4);
+        assert_eq!(path.states, vec![1, 2, 3, 6, 12]);
+        let odd = |n: &usize| if *n < 20 { vec![n + 2] } else { vec![] };
+        assert_eq!(bfs(1, odd, |n| *n == 12), None);
+    }
+
+    #[test]
+    fn count_paths_through_a_grid() {
+        // moving right or down from the top left to the bottom right of a 3 x 3 grid
+        let neighbours = |&(x, y): &(usize, usize)| {
+            let mut next = vec![];
+            if x < 2 {
+                next.push((x + 1, y));
+            }
+            if y < 2 {
+                next.push((x, y + 1));
+            }
+            next
+        };
+        assert_eq!(count_paths((0, 0), neighbours, |p| *p == (2, 2)), 6);
+    }
+}
@@ -0,0 +1,225 @@
+use crate::answer::Answer;
+use crate::error::ParseError;
+use crate::generate::Rng;
+use crate::netpbm::Image;
+use crate::readfile;
+use crate::visualize::Animation;
+use std::collections::BTreeMap;
+use std::str::FromStr;
+
+/// A puzzle solution. The input is parsed once and shared by both parts, invalid input is
+/// reported as a [`ParseError`] instead of panicking. The parts may run concurrently on
+/// different threads.
+pub trait Solver: Send + Sync {
+    fn parse(lines: &readfile::Lines) -> Result<Self, ParseError>
+    where
+        Self: Sized;
+
+    fn part1(&self) -> Answer;
+
+    fn part2(&self) -> Answer;
+
+    /// A random valid input for `aoc generate` and property tests, `size` scales it in a way
+    /// that depends on the day. `None` if the day has no generator.
+    fn generate(_rng: &mut Rng, _size: usize) -> Option<String>
+    where
+        Self: Sized,
+    {
+        None
+    }
+
+    /// The parameters that can be changed with `--param name=value`.
+    fn params() -> &'static [Param]
+    where
+        Self: Sized,
+    {
+        &[]
+    }
+
+    /// Applies the values given with `--param`, called after parsing. Only declared names are
+    /// passed, a value that does not parse is reported as error.
+    fn configure(&mut self, _params: &Params) -> Result<(), String> {
+        Ok(())
+    }
+
+    /// Plays the simulation of the puzzle as frames of `animation`, `false` if the day has no
+    /// visualization.
+    fn visualize(&self, _animation: &mut Animation) -> bool {
+        false
+    }
+
+    /// A picture of the result for `--export-image`, `None` if the day has none.
+    fn image(&self) -> Option<Image> {
+        None
+    }
+}
+
+/// A puzzle constant that a solver allows to change, e.g. the number of simulated days.
+pub struct Param {
+    pub name: &'static str,
+    /// What the parameter changes, including the puzzle's defaults.
+    pub help: &'static str,
+}
+
+/// The values given with `--param name=value`, parameters not given keep the puzzle's defaults.
+#[derive(Clone, Debug, Default)]
+pub struct Params {
+    values: BTreeMap<String, String>,
+}
+
+impl Params {
+    /// Adds a `name=value` argument.
+    pub fn insert(&mut self, arg: &str) -> Result<(), String> {
+        match arg.split_once('=') {
+            Some((name, value)) if !name.is_empty() => {
+                self.values.insert(String::from(name), String::from(value));
+                Ok(())
+            }
+            _ => Err(format!("Invalid parameter, expected name=value: {}", arg)),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.values.keys().map(|k| k.as_str())
+    }
+
+    /// The value of `name`, `None` if it was not given.
+    pub fn get<T: FromStr>(&self, name: &str) -> Result<Option<T>, String> {
+        match self.values.get(name) {
+            Some(value) => match value.parse() {
+                Ok(value) => Ok(Some(value)),
+                Err(_) => Err(format!("Invalid value for {}: {}", name, value)),
+            },
+            None => Ok(None),
+        }
+    }
+}
+
+/// An entry in the registry of implemented days.
+pub struct Day {
+    pub number: u8,
+    parse: fn(&readfile::Lines) -> Result<Box<dyn Solver>, ParseError>,
+    params: fn() -> &'static [Param],
+    generate: fn(&mut Rng, usize) -> Option<String>,
+}
+
+impl Day {
+    pub fn input_file(&self) -> String {
+        format!("day{}.txt", self.number)
+    }
+
+    pub fn load(&self, lines: &readfile::Lines) -> Result<Box<dyn Solver>, ParseError> {
+        (self.parse)(lines)
+    }
+
+    pub fn generate(&self, rng: &mut Rng, size: usize) -> Option<String> {
+        (self.generate)(rng, size)
+    }
+
+    pub fn params(&self) -> &'static [Param] {
+        (self.params)()
+    }
+
+    /// Checks that the day declares every given parameter.
+    pub fn check_params(&self, params: &Params) -> Result<(), String> {
+        for name in params.names() {
+            if !self.params().iter().any(|p| p.name == name) {
+                return Err(format!("Day {} has no parameter {}", self.number, name));
+            }
+        }
+        Ok(())
+    }
+
+    /// A day outside of [`DAYS`], for tests of the runner with solvers that misbehave.
+    #[cfg(test)]
+    pub(crate) fn of<S: Solver + 'static>(number: u8) -> Day {
+        Day {
+            number,
+            parse: parse_boxed::<S>,
+            params: S::params,
+            generate: S::generate,
+        }
+    }
+}
+
+fn parse_boxed<S: Solver + 'static>(
+    lines: &readfile::Lines,
+) -> Result<Box<dyn Solver>, ParseError> {
+    Ok(Box::new(S::parse(lines)?))
+}
+
+/// An entry of [`DAYS`], `aoc new` adds these lines.
+macro_rules! day {
+    ($number:expr, $module:ident::$solver:ident) => {
+        Day {
+            number: $number,
+            parse: parse_boxed::<crate::$module::$solver>,
+            params: <crate::$module::$solver as Solver>::params,
+            generate: <crate::$module::$solver as Solver>::generate,
+        }
+    };
+}
+
+pub const DAYS: &[Day] = &[
+    day!(1, day1::Day1),
+    day!(2, day2::Day2),
+    day!(3, day3::Day3),
+    day!(4, day4::Day4),
+    day!(5, day5::Day5),
+    day!(6, day6::Day6),
+    day!(7, day7::Day7),
+    day!(8, day8::Day8),
+    day!(9, day9::Day9),
+    day!(10, day10::Day10),
+    day!(11, day11::Day11),
+    day!(12, day12::Day12),
+    day!(13, day13::Day13),
+    day!(14, day14::Day14),
+    day!(15, day15::Day15),
+    day!(16, day16::Day16),
+    day!(17, day17::Day17),
+    day!(18, day18::Day18),
+    day!(19, day19::Day19),
+    day!(20, day20::Day20),
+    day!(21, day21::Day21),
+    day!(22, day22::Day22),
+    day!(23, day23::Day23),
+    day!(24, day24::Day24),
+    day!(25, day25::Day25),
+];
+
+pub fn find(number: u8) -> Option<&'static Day> {
+    DAYS.iter().find(|d| d.number == number)
+}
+
+pub fn is_implemented(number: u8) -> bool {
+    find(number).is_some()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn params() {
+        let mut params = Params::default();
+        params.insert("days=18").unwrap();
+        assert!(params.insert("days").is_err());
+        assert_eq!(params.get::<usize>("days"), Ok(Some(18)));
+        assert_eq!(params.get::<usize>("steps"), Ok(None));
+        params.insert("days=many").unwrap();
+        assert!(params.get::<usize>("days").is_err());
+    }
+
+    #[test]
+    fn undeclared_params_are_rejected() {
+        let mut params = Params::default();
+        params.insert("days=18").unwrap();
+        assert!(find(6).unwrap().check_params(&params).is_ok());
+        assert!(find(1).unwrap().check_params(&params).is_err());
+    }
+}
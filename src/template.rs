@@ -1,11 +1,35 @@
+use crate::answer::Answer;
+use crate::error::ParseError;
 use crate::readfile;
+use crate::solver::Solver;
 
-fn part1(lines: &readfile::Lines) {}
+pub struct Template {}
 
-fn part2(lines: &readfile::Lines) {}
+impl Solver for Template {
+    fn parse(_lines: &readfile::Lines) -> Result<Template, ParseError> {
+        Ok(Template {})
+    }
 
-pub fn run() {
-    let lines = readfile::Lines::new("day1.txt");
-    part1(&lines);
-    part2(&lines);
+    fn part1(&self) -> Answer {
+        Answer::Unsolved
+    }
+
+    fn part2(&self) -> Answer {
+        Answer::Unsolved
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "\
+";
+
+    #[test]
+    fn example() {
+        let day = Template::parse(&readfile::Lines::from(EXAMPLE)).unwrap();
+        assert_eq!(day.part1(), Answer::Unsolved);
+        assert_eq!(day.part2(), Answer::Unsolved);
+    }
 }
@@ -0,0 +1,74 @@
+//! Terminal animations of the simulation-style days, see `aoc <day> --visualize`.
+
+use std::io::{self, Write};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Shows frames one after another in the terminal at a fixed rate, or records them.
+pub struct Animation {
+    delay: Duration,
+    last_frame: Option<Instant>,
+    recorded: Option<Vec<String>>,
+}
+
+impl Animation {
+    pub fn new(frames_per_second: u32) -> Animation {
+        return Animation {
+            delay: Duration::from_secs(1) / frames_per_second.max(1),
+            last_frame: None,
+            recorded: None,
+        };
+    }
+
+    /// An animation that keeps the frames instead of printing them.
+    pub fn recording() -> Animation {
+        return Animation {
+            delay: Duration::ZERO,
+            last_frame: None,
+            recorded: Some(Vec::new()),
+        };
+    }
+
+    /// Replaces the previous frame with `frame` below a `caption` line, waiting until the
+    /// previous frame was shown long enough.
+    pub fn frame(&mut self, caption: &str, frame: &str) {
+        let text = format!("{}\n{}", caption, frame);
+        if let Some(recorded) = &mut self.recorded {
+            recorded.push(text);
+            return;
+        }
+
+        if let Some(last_frame) = self.last_frame {
+            let elapsed = last_frame.elapsed();
+            if elapsed < self.delay {
+                thread::sleep(self.delay - elapsed);
+            }
+        }
+        // move the cursor home and clear the screen, then draw the frame
+        let mut out = io::stdout().lock();
+        let _ = writeln!(out, "\x1b[H\x1b[2J{}", text);
+        let _ = out.flush();
+        self.last_frame = Some(Instant::now());
+    }
+
+    /// The frames of a [`Animation::recording`], each with its caption as first line.
+    pub fn recorded(&self) -> &[String] {
+        match &self.recorded {
+            Some(recorded) => recorded,
+            None => &[],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn recording_keeps_the_frames() {
+        let mut animation = Animation::recording();
+        animation.frame("step 1", "#.\n.#");
+        animation.frame("step 2", "..\n..");
+        assert_eq!(animation.recorded(), &["step 1\n#.\n.#", "step 2\n..\n.."]);
+    }
+}